@@ -0,0 +1,240 @@
+use crate::{EncodedGameConstructionError, FORMAT_VERSION};
+
+const KIND_NAG: u8 = 0;
+const KIND_COMMENT: u8 = 1;
+const KIND_VARIATION: u8 = 2;
+
+/// Maximum nesting depth of variations. Annotations with deeper variations cannot be read.
+pub const MAX_VARIATION_DEPTH: usize = 32;
+
+/// A single annotation of a game.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Annotation {
+    /// Numeric annotation glyph, e.g. 1 for `!` or 14 for `+=`.
+    Nag(u8),
+    /// Free-text comment.
+    Comment(String),
+    /// Sideline that replaces the move at its ply: the bytes of an `EncodedGame` starting from
+    /// the position before that move, and the annotations of the sideline itself.
+    Variation(Vec<u8>, Annotations),
+}
+
+/// Annotations of a game (NAGs, comments and sidelines), each anchored at a ply.
+///
+/// NAGs and comments at ply `n` follow the `n`-th move (ply 0 is before the first move), a
+/// variation at ply `n` is an alternative to the `n`-th move. Annotations are kept in order of
+/// their ply; annotations at the same ply keep the order in which they were added.
+///
+/// The binary representation starts with the format version, followed by the annotations:
+/// the ply (2 bytes, little endian), the kind (0 for NAG, 1 for comment, 2 for variation)
+/// and the value. A NAG is a single byte, a comment is its length (4 bytes, little endian) and
+/// its UTF-8 text, a variation is the length and bytes of the encoded sideline followed by
+/// the length and bytes of its annotations. No annotations are represented by empty bytes.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Annotations {
+    entries: Vec<(u16, Annotation)>,
+}
+
+impl Annotations {
+    /// Creates an empty set of annotations.
+    #[must_use]
+    pub fn new() -> Self {
+        Annotations::default()
+    }
+
+    /// Adds an annotation at `ply`, after the annotations already present at that ply.
+    pub fn push(&mut self, ply: u16, annotation: Annotation) {
+        let index = self.entries.partition_point(|(p, _)| *p <= ply);
+        self.entries.insert(index, (ply, annotation));
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterates over the annotations and their plies, in order of ply.
+    pub fn iter(&self) -> impl Iterator<Item = (u16, &Annotation)> {
+        self.entries.iter().map(|(ply, a)| (*ply, a))
+    }
+
+    /// Returns the annotations at `ply`.
+    pub fn at(&self, ply: u16) -> impl Iterator<Item = &Annotation> {
+        let start = self.entries.partition_point(|(p, _)| *p < ply);
+        self.entries[start..]
+            .iter()
+            .take_while(move |(p, _)| *p == ply)
+            .map(|(_, a)| a)
+    }
+
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        if !self.is_empty() {
+            bytes.push(FORMAT_VERSION);
+            self.write_entries(&mut bytes);
+        }
+        bytes
+    }
+
+    fn write_entries(&self, bytes: &mut Vec<u8>) {
+        for (ply, annotation) in &self.entries {
+            bytes.extend_from_slice(&ply.to_le_bytes());
+            match annotation {
+                Annotation::Nag(nag) => {
+                    bytes.push(KIND_NAG);
+                    bytes.push(*nag);
+                }
+                Annotation::Comment(comment) => {
+                    bytes.push(KIND_COMMENT);
+                    write_slice(bytes, comment.as_bytes());
+                }
+                Annotation::Variation(game, annotations) => {
+                    bytes.push(KIND_VARIATION);
+                    write_slice(bytes, game);
+                    write_slice(bytes, &annotations.to_bytes());
+                }
+            }
+        }
+    }
+
+    /// Reads annotations from their binary representation.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, EncodedGameConstructionError> {
+        Annotations::from_bytes_at_depth(bytes, 0)
+    }
+
+    /// Reads annotations nested in `depth` variations.
+    fn from_bytes_at_depth(
+        bytes: &[u8],
+        depth: usize,
+    ) -> Result<Self, EncodedGameConstructionError> {
+        let Some((&version, mut rest)) = bytes.split_first() else {
+            return Ok(Annotations::new());
+        };
+        if version == 0 {
+            return Err(EncodedGameConstructionError::InvalidData);
+        }
+        if version > FORMAT_VERSION {
+            return Err(EncodedGameConstructionError::UnsupportedVersion);
+        }
+
+        let mut annotations = Annotations::new();
+        while !rest.is_empty() {
+            let ply = u16::from_le_bytes(take(&mut rest, 2)?.try_into().unwrap());
+            if annotations.entries.last().is_some_and(|(p, _)| *p > ply) {
+                return Err(EncodedGameConstructionError::InvalidData);
+            }
+
+            let annotation = match take(&mut rest, 1)?[0] {
+                KIND_NAG => Annotation::Nag(take(&mut rest, 1)?[0]),
+                KIND_COMMENT => Annotation::Comment(
+                    String::from_utf8(read_slice(&mut rest)?.to_vec())
+                        .map_err(|_| EncodedGameConstructionError::InvalidData)?,
+                ),
+                KIND_VARIATION if depth < MAX_VARIATION_DEPTH => {
+                    let game = read_slice(&mut rest)?.to_vec();
+                    let nested =
+                        Annotations::from_bytes_at_depth(read_slice(&mut rest)?, depth + 1)?;
+                    Annotation::Variation(game, nested)
+                }
+                KIND_VARIATION => return Err(EncodedGameConstructionError::InvalidData),
+                _ => return Err(EncodedGameConstructionError::UnsupportedVersion),
+            };
+            annotations.entries.push((ply, annotation));
+        }
+
+        Ok(annotations)
+    }
+}
+
+fn write_slice(bytes: &mut Vec<u8>, slice: &[u8]) {
+    let len = u32::try_from(slice.len()).expect("annotation is smaller than 4 GiB");
+    bytes.extend_from_slice(&len.to_le_bytes());
+    bytes.extend_from_slice(slice);
+}
+
+fn read_slice<'a>(rest: &mut &'a [u8]) -> Result<&'a [u8], EncodedGameConstructionError> {
+    let len = u32::from_le_bytes(take(rest, 4)?.try_into().unwrap());
+    take(rest, len as usize)
+}
+
+fn take<'a>(rest: &mut &'a [u8], n: usize) -> Result<&'a [u8], EncodedGameConstructionError> {
+    if rest.len() < n {
+        return Err(EncodedGameConstructionError::InvalidData);
+    }
+    let (taken, remaining) = rest.split_at(n);
+    *rest = remaining;
+    Ok(taken)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_test() {
+        let mut sideline = Annotations::new();
+        sideline.push(1, Annotation::Comment("also good".to_owned()));
+
+        let mut annotations = Annotations::new();
+        annotations.push(3, Annotation::Comment("main idea".to_owned()));
+        annotations.push(1, Annotation::Nag(1));
+        annotations.push(3, Annotation::Variation(vec![1, 2, 3], sideline));
+        annotations.push(0, Annotation::Comment("{ start }".to_owned()));
+
+        let plies: Vec<u16> = annotations.iter().map(|(ply, _)| ply).collect();
+        assert_eq!(plies, vec![0, 1, 3, 3]);
+        assert!(matches!(
+            annotations.at(3).next(),
+            Some(Annotation::Comment(c)) if c == "main idea"
+        ));
+        assert_eq!(annotations.at(2).count(), 0);
+
+        let bytes = annotations.to_bytes();
+        assert_eq!(Annotations::from_bytes(&bytes), Ok(annotations));
+        assert_eq!(Annotations::from_bytes(&[]), Ok(Annotations::new()));
+        assert!(Annotations::new().to_bytes().is_empty());
+    }
+
+    #[test]
+    fn invalid_bytes_test() {
+        let mut annotations = Annotations::new();
+        annotations.push(2, Annotation::Comment("text".to_owned()));
+        let bytes = annotations.to_bytes();
+
+        assert_eq!(
+            Annotations::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(EncodedGameConstructionError::InvalidData)
+        );
+        assert_eq!(
+            Annotations::from_bytes(&[0]),
+            Err(EncodedGameConstructionError::InvalidData)
+        );
+        assert_eq!(
+            Annotations::from_bytes(&[FORMAT_VERSION + 1]),
+            Err(EncodedGameConstructionError::UnsupportedVersion)
+        );
+        assert_eq!(
+            Annotations::from_bytes(&[FORMAT_VERSION, 0, 0, 7]),
+            Err(EncodedGameConstructionError::UnsupportedVersion)
+        );
+    }
+
+    #[test]
+    fn nesting_depth_test() {
+        fn nested(depth: usize) -> Annotations {
+            let mut annotations = Annotations::new();
+            if depth > 0 {
+                annotations.push(1, Annotation::Variation(vec![1], nested(depth - 1)));
+            }
+            annotations
+        }
+
+        let deepest = nested(MAX_VARIATION_DEPTH);
+        assert_eq!(Annotations::from_bytes(&deepest.to_bytes()), Ok(deepest));
+        assert_eq!(
+            Annotations::from_bytes(&nested(MAX_VARIATION_DEPTH + 1).to_bytes()),
+            Err(EncodedGameConstructionError::InvalidData)
+        );
+    }
+}
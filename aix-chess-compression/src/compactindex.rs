@@ -1,14 +1,28 @@
 use bitm::BitAccess;
 use chess_huffman as huff;
-use shakmaty::{Chess, Color, File, Move, Position, Rank, Role, Square, uci::UciMove};
-
-use crate::{Decode, DecodeError, DecodeResult, EncodeError, EncodedGame, EncodedGameContent};
-
-static NEEDED_BITS: [u8; 33] = [
+use shakmaty::{
+    CastlingSide, Color, File, Move, Position, Rank, Role, Square, uci::UciMove,
+    variant::VariantPosition,
+};
+
+use crate::{
+    CompressionLevel, Decode, DecodeError, DecodeErrorKind, DecodeResult, EncodeError, EncodedGame,
+    EncodedGameContent, Keyframes,
+};
+
+// Horde and Crazyhouse allow more than 16 pieces per side.
+static NEEDED_BITS: [u8; 65] = [
     0, 0, 1, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 4, 4, 4, 4, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5,
-    5,
+    5, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6,
+    6,
 ];
 
+/// Bit lengths of a drop (Crazyhouse): the dropped role and the destination square. A drop is
+/// preceded by a single bit that is set, which is only present when the side to move has pieces
+/// in hand; regular moves have a zero bit there.
+const DROP_ROLE_BITS: u8 = 3;
+const DROP_SQUARE_BITS: u8 = 6;
+
 fn move_index_knight(from: Square, to: Square) -> u64 {
     let diff = to as i32 - from as i32;
     match diff {
@@ -54,12 +68,22 @@ fn move_index_queen(from: Square, to: Square) -> u64 {
     }
 }
 
-fn move_index_king(from: Square, to: Square, white: bool) -> u64 {
-    let diff = to as i32 - from as i32;
+fn move_index_king(
+    from: Square,
+    to: Square,
+    white: bool,
+    castling_side: Option<CastlingSide>,
+) -> u64 {
+    // For castling: if a king can castle, it must be on its back rank (in
+    // standard chess and in Chess960), so we know that it cannot move back a
+    // rank and we can use those moves to encode castling instead.
+    match castling_side {
+        Some(CastlingSide::KingSide) => return if white { 5 } else { 1 }, // O-O
+        Some(CastlingSide::QueenSide) => return if white { 6 } else { 2 }, // O-O-O
+        None => {}
+    }
 
-    // For castling: if a king can castle, it must be in the initial position,
-    // so we know that it cannot move back a rank and we can use those moves to
-    // encode castling instead.
+    let diff = to as i32 - from as i32;
     match diff {
         1 => 0,
         9 => 1,
@@ -69,20 +93,6 @@ fn move_index_king(from: Square, to: Square, white: bool) -> u64 {
         -9 => 5,
         -8 => 6,
         -7 => 7,
-        3 => {
-            if white {
-                5
-            } else {
-                1
-            }
-        } // O-O
-        -4 => {
-            if white {
-                6
-            } else {
-                2
-            }
-        } // O-O-O
         _ => panic!("Invalid king move"),
     }
 }
@@ -99,7 +109,18 @@ fn move_index_pawn_standard(from: Square, to: Square) -> u64 {
 }
 
 fn move_index_pawn_promoting(from: Square, to: Square, promotion_role: Role) -> u64 {
-    (match to.file() as i32 - from.file() as i32 {
+    let file_diff = to.file() as i32 - from.file() as i32;
+    if promotion_role == Role::King {
+        // Antichess: the indices 12-14 are unused by the other promotions
+        return match file_diff {
+            0 => 12,
+            -1 => 13,
+            1 => 14,
+            _ => panic!("Invalid pawn promotion move"),
+        };
+    }
+
+    (match file_diff {
         0 => 0,
         -1 => 4,
         1 => 8,
@@ -119,13 +140,14 @@ fn move_index_and_bits(
     role: Role,
     white: bool,
     promotion_role: Option<Role>,
+    castling_side: Option<CastlingSide>,
 ) -> (u64, u8) {
     match role {
         Role::Knight => (move_index_knight(from, to), 3),
         Role::Rook => (move_index_rook(from, to), 4),
         Role::Bishop => (move_index_bishop(from, to), 4),
         Role::Queen => (move_index_queen(from, to), 5),
-        Role::King => (move_index_king(from, to, white), 3),
+        Role::King => (move_index_king(from, to, white, castling_side), 3),
         Role::Pawn => match promotion_role {
             Some(promotion_role) => (move_index_pawn_promoting(from, to, promotion_role), 4),
             None => (move_index_pawn_standard(from, to), 2),
@@ -133,15 +155,18 @@ fn move_index_and_bits(
     }
 }
 
-fn new_square(from: u32) -> DecodeResult<Square> {
+/// Result of decoding a square or move index; the decoder adds the ply to the error.
+type IndexResult<T> = Result<T, DecodeErrorKind>;
+
+fn new_square(from: u32) -> IndexResult<Square> {
     if from < 64 {
         Ok(unsafe { Square::new_unchecked(from) })
     } else {
-        Err(DecodeError {})
+        Err(DecodeErrorKind::InvalidIndex)
     }
 }
 
-fn knight_destination_from_move_index(from: Square, move_index: u64) -> DecodeResult<Square> {
+fn knight_destination_from_move_index(from: Square, move_index: u64) -> IndexResult<Square> {
     let from = from as u32;
     new_square(match move_index {
         0 => from + 6,
@@ -152,7 +177,7 @@ fn knight_destination_from_move_index(from: Square, move_index: u64) -> DecodeRe
         5 => from.wrapping_sub(10),
         6 => from.wrapping_sub(15),
         7 => from.wrapping_sub(17),
-        _ => return Err(DecodeError {}),
+        _ => return Err(DecodeErrorKind::InvalidIndex),
     })
 }
 
@@ -172,7 +197,7 @@ fn rook_destination_from_move_index(from: Square, move_index: u64) -> Square {
 
 #[allow(clippy::cast_possible_truncation)]
 #[allow(clippy::cast_sign_loss)]
-fn bishop_destination_from_move_index(from: Square, move_index: u64) -> DecodeResult<Square> {
+fn bishop_destination_from_move_index(from: Square, move_index: u64) -> IndexResult<Square> {
     let dest_rank: u64;
     let dest_file: u32;
 
@@ -187,7 +212,7 @@ fn bishop_destination_from_move_index(from: Square, move_index: u64) -> DecodeRe
     }
 
     if dest_file > 7 {
-        return Err(DecodeError {});
+        return Err(DecodeErrorKind::InvalidIndex);
     }
 
     Ok(unsafe {
@@ -198,7 +223,7 @@ fn bishop_destination_from_move_index(from: Square, move_index: u64) -> DecodeRe
     })
 }
 
-fn queen_destination_from_move_index(from: Square, move_index: u64) -> DecodeResult<Square> {
+fn queen_destination_from_move_index(from: Square, move_index: u64) -> IndexResult<Square> {
     if move_index & (1 << 4) == 0 {
         Ok(rook_destination_from_move_index(from, move_index))
     } else {
@@ -206,46 +231,32 @@ fn queen_destination_from_move_index(from: Square, move_index: u64) -> DecodeRes
     }
 }
 
-fn king_destination_from_move_index(
+/// Returns the castling side if `move_index` encodes castling for a king on
+/// `from`, see `move_index_king`.
+fn castling_side_from_move_index(
     from: Square,
     white: bool,
     move_index: u64,
-) -> DecodeResult<Square> {
+) -> Option<CastlingSide> {
+    match (white, from.rank(), move_index) {
+        (true, Rank::First, 5) | (false, Rank::Eighth, 1) => Some(CastlingSide::KingSide),
+        (true, Rank::First, 6) | (false, Rank::Eighth, 2) => Some(CastlingSide::QueenSide),
+        _ => None,
+    }
+}
+
+fn king_destination_from_move_index(from: Square, move_index: u64) -> IndexResult<Square> {
     let from = from as u32;
     new_square(match move_index {
         0 => from + 1,
-        1 => {
-            if !white && from == 60 {
-                63
-            } else {
-                from + 9
-            }
-        }
-        2 => {
-            if !white && from == 60 {
-                56
-            } else {
-                from + 8
-            }
-        }
+        1 => from + 9,
+        2 => from + 8,
         3 => from + 7,
         4 => from.wrapping_sub(1),
-        5 => {
-            if white && from == 4 {
-                7
-            } else {
-                from.wrapping_sub(9)
-            }
-        }
-        6 => {
-            if white && from == 4 {
-                0
-            } else {
-                from.wrapping_sub(8)
-            }
-        }
+        5 => from.wrapping_sub(9),
+        6 => from.wrapping_sub(8),
         7 => from.wrapping_sub(7),
-        _ => return Err(DecodeError {}),
+        _ => return Err(DecodeErrorKind::InvalidIndex),
     })
 }
 
@@ -253,18 +264,29 @@ fn pawn_destination_from_move_index(
     from: Square,
     white: bool,
     move_index: u64,
-) -> DecodeResult<(Square, Option<Role>)> {
+) -> IndexResult<(Square, Option<Role>)> {
     let from_rank = from.rank();
     let from = from as u32;
 
     Ok(if white {
         if from_rank == Rank::Seventh {
+            if move_index & 0b1100 == 12 {
+                return Ok((
+                    new_square(match move_index & 0b11 {
+                        0 => from + 8,
+                        1 => from + 7,
+                        2 => from + 9,
+                        _ => return Err(DecodeErrorKind::InvalidIndex),
+                    })?,
+                    Some(Role::King),
+                ));
+            }
             (
                 new_square(match move_index & 0b1100 {
                     0 => from + 8,
                     4 => from + 7,
                     8 => from + 9,
-                    _ => return Err(DecodeError {}),
+                    _ => return Err(DecodeErrorKind::InvalidIndex),
                 })?,
                 Some(match move_index & 0b11 {
                     0 => Role::Queen,
@@ -281,18 +303,29 @@ fn pawn_destination_from_move_index(
                     1 => from + 7,
                     2 => from + 9,
                     3 => from + 16,
-                    _ => return Err(DecodeError {}),
+                    _ => return Err(DecodeErrorKind::InvalidIndex),
                 })?,
                 None,
             )
         }
     } else if from_rank == Rank::Second {
+        if move_index & 0b1100 == 12 {
+            return Ok((
+                new_square(match move_index & 0b11 {
+                    0 => from - 8,
+                    1 => from - 9,
+                    2 => from - 7,
+                    _ => return Err(DecodeErrorKind::InvalidIndex),
+                })?,
+                Some(Role::King),
+            ));
+        }
         (
             new_square(match move_index & 0b1100 {
                 0 => from - 8,
                 4 => from - 9,
                 8 => from - 7,
-                _ => return Err(DecodeError {}),
+                _ => return Err(DecodeErrorKind::InvalidIndex),
             })?,
             Some(match move_index & 0b11 {
                 0 => Role::Queen,
@@ -309,7 +342,7 @@ fn pawn_destination_from_move_index(
                 1 => from - 7,
                 2 => from - 9,
                 3 => from - 16,
-                _ => return Err(DecodeError {}),
+                _ => return Err(DecodeErrorKind::InvalidIndex),
             })?,
             None,
         )
@@ -321,21 +354,18 @@ fn destination_from_move_index(
     role: Role,
     white: bool,
     move_index: u64,
-) -> DecodeResult<(Square, Option<Role>)> {
+) -> IndexResult<(Square, Option<Role>)> {
     match role {
         Role::Knight => Ok((knight_destination_from_move_index(from, move_index)?, None)),
         Role::Rook => Ok((rook_destination_from_move_index(from, move_index), None)),
         Role::Bishop => Ok((bishop_destination_from_move_index(from, move_index)?, None)),
         Role::Queen => Ok((queen_destination_from_move_index(from, move_index)?, None)),
-        Role::King => Ok((
-            king_destination_from_move_index(from, white, move_index)?,
-            None,
-        )),
+        Role::King => Ok((king_destination_from_move_index(from, move_index)?, None)),
         Role::Pawn => pawn_destination_from_move_index(from, white, move_index),
     }
 }
 
-fn index_nth_set_bit(v: u64, n: u64) -> DecodeResult<u32> {
+fn index_nth_set_bit(v: u64, n: u64) -> IndexResult<u32> {
     let mut v = v;
     let mut count = 0;
     let mut i: u32 = 0;
@@ -353,12 +383,14 @@ fn index_nth_set_bit(v: u64, n: u64) -> DecodeResult<u32> {
             i += tz;
         }
     }
-    Err(DecodeError {})
+    Err(DecodeErrorKind::InvalidIndex)
 }
 
 pub struct CompactIndexEncoder {
     result: huff::EncodedGame,
-    chess: shakmaty::Chess,
+    chess: VariantPosition,
+    start_position: Option<VariantPosition>,
+    plies: usize,
 }
 
 impl CompactIndexEncoder {
@@ -368,9 +400,33 @@ impl CompactIndexEncoder {
                 inner: vec![0; 8],
                 bit_index: 0,
             },
-            chess: shakmaty::Chess::new(),
+            chess: VariantPosition::default(),
+            start_position: None,
+            plies: 0,
+        }
+    }
+
+    pub fn with_start_position(pos: VariantPosition) -> Self {
+        Self {
+            result: huff::EncodedGame {
+                inner: vec![0; 8],
+                bit_index: 0,
+            },
+            chess: pos.clone(),
+            start_position: Some(pos),
+            plies: 0,
         }
     }
+
+    /// Returns the position after the moves encoded so far.
+    pub(crate) fn position(&self) -> &VariantPosition {
+        &self.chess
+    }
+
+    /// Returns the number of moves encoded so far.
+    pub(crate) fn plies(&self) -> usize {
+        self.plies
+    }
 }
 
 impl Default for CompactIndexEncoder {
@@ -379,20 +435,50 @@ impl Default for CompactIndexEncoder {
     }
 }
 
+/// Returns true if the side to move has pieces in hand, so that a drop bit precedes the move.
+fn can_drop(pos: &VariantPosition) -> bool {
+    pos.pockets()
+        .is_some_and(|pockets| pockets.get(pos.turn()).iter().any(|&n| n > 0))
+}
+
 impl crate::Encode for CompactIndexEncoder {
     fn encode_move(&mut self, m: Move) -> Result<(), EncodeError> {
-        let from = m.from().ok_or(EncodeError {
-            inner: Box::new("missing from square in CompactIndexEncoder::encode_move"),
-        })?;
+        // resize buffer if it may be too small
+        if self.result.inner.len() * 64 < self.result.bit_index + 12 {
+            self.result.inner.resize(self.result.inner.len() + 4, 0);
+        }
+
+        if can_drop(&self.chess) {
+            let is_drop = matches!(m, Move::Put { .. });
+            self.result
+                .inner
+                .set_bits(self.result.bit_index, u64::from(is_drop), 1);
+            self.result.bit_index += 1;
+        }
+
+        if let Move::Put { role, to } = m {
+            self.result
+                .inner
+                .set_bits(self.result.bit_index, role as u64 - 1, DROP_ROLE_BITS);
+            self.result.bit_index += DROP_ROLE_BITS as usize;
+            self.result
+                .inner
+                .set_bits(self.result.bit_index, to as u64, DROP_SQUARE_BITS);
+            self.result.bit_index += DROP_SQUARE_BITS as usize;
+
+            self.chess.play_unchecked(m);
+            self.plies += 1;
+            return Ok(());
+        }
+
+        let from = m.from().ok_or(EncodeError::unsupported(m))?;
         let turn = self.chess.turn();
         let white = turn == Color::White;
         let role = self
             .chess
             .board()
             .piece_at(from)
-            .ok_or(EncodeError {
-                inner: Box::new("missing piece at from square in CompactIndexEncoder::encode_move"),
-            })?
+            .ok_or(EncodeError::unsupported(m))?
             .role;
 
         let bb = if white {
@@ -403,11 +489,6 @@ impl crate::Encode for CompactIndexEncoder {
         let piece_count = bb.count();
         let needed_bits = NEEDED_BITS[piece_count];
 
-        // resize buffer if it may be too small
-        if self.result.inner.len() * 64 < self.result.bit_index + 9 {
-            self.result.inner.resize(self.result.inner.len() + 4, 0);
-        }
-
         if needed_bits > 0 {
             let mask = u64::MAX >> (63 - from as u8);
             let index = (bb & mask).count() as u64 - 1;
@@ -417,7 +498,8 @@ impl crate::Encode for CompactIndexEncoder {
             self.result.bit_index += needed_bits as usize;
         }
 
-        let (move_index, move_bits) = move_index_and_bits(from, m.to(), role, white, m.promotion());
+        let (move_index, move_bits) =
+            move_index_and_bits(from, m.to(), role, white, m.promotion(), m.castling_side());
         unsafe {
             self.result
                 .inner
@@ -426,6 +508,7 @@ impl crate::Encode for CompactIndexEncoder {
         self.result.bit_index += move_bits as usize;
 
         self.chess.play_unchecked(m);
+        self.plies += 1;
 
         Ok(())
     }
@@ -433,39 +516,91 @@ impl crate::Encode for CompactIndexEncoder {
     fn finish(self) -> EncodedGame<'static> {
         EncodedGame {
             content: EncodedGameContent::Bits(self.result),
-            compression_level: crate::CompressionLevel::Medium,
+            compression_level: CompressionLevel::Medium,
+            start_position: self.start_position,
+            keyframes: None,
         }
     }
 }
 
 pub struct CompactIndexDecoder<'a> {
-    chess: shakmaty::Chess,
+    chess: VariantPosition,
     encoded: &'a huff::EncodedGame,
     index: usize,
+    ply: usize,
+    pub(crate) start_position: Option<&'a VariantPosition>,
+    pub(crate) keyframes: Option<&'a Keyframes>,
 }
 
 impl<'a> CompactIndexDecoder<'a> {
-    pub(crate) fn new(encoded: &'a crate::EncodedGameContent<'a>) -> Self {
+    pub(crate) fn new(
+        encoded: &'a crate::EncodedGameContent<'a>,
+        start_position: Option<&'a VariantPosition>,
+        keyframes: Option<&'a Keyframes>,
+    ) -> Self {
         if let crate::EncodedGameContent::Bits(enc) = encoded {
             Self {
-                chess: shakmaty::Chess::new(),
+                chess: start_position.cloned().unwrap_or_default(),
                 encoded: enc,
                 index: 0,
+                ply: 0,
+                start_position,
+                keyframes,
             }
         } else {
             panic!("CompactIndexDecoder only accepts EncodedGameRef::Bits");
         }
     }
-}
 
-fn get_bits_checked(buffer: &[u64], begin: usize, len: u8) -> DecodeResult<u64> {
-    buffer.try_get_bits(begin, len).ok_or(DecodeError {})
+    /// Returns the bit offset of the next move.
+    pub(crate) fn offset(&self) -> usize {
+        self.index
+    }
+
+    /// Continues decoding from bit `offset`, where the position is `pos` after ply `ply`.
+    pub(crate) fn jump(
+        &mut self,
+        offset: usize,
+        ply: usize,
+        pos: &VariantPosition,
+    ) -> DecodeResult<()> {
+        if offset > self.encoded.bit_index {
+            return Err(DecodeError::new(
+                DecodeErrorKind::InvalidData,
+                ply,
+                CompressionLevel::Medium,
+            ));
+        }
+        self.index = offset;
+        self.ply = ply;
+        self.chess = pos.clone();
+        Ok(())
+    }
 }
 
-impl Decode for CompactIndexDecoder<'_> {
-    fn next_move(&mut self) -> Option<DecodeResult<Move>> {
-        if self.index == self.encoded.bit_index {
-            return None;
+impl CompactIndexDecoder<'_> {
+    fn next_drop(&mut self) -> IndexResult<Move> {
+        let role_bits = get_bits_checked(&self.encoded.inner, self.index, DROP_ROLE_BITS)?;
+        self.index += DROP_ROLE_BITS as usize;
+        let square_bits = get_bits_checked(&self.encoded.inner, self.index, DROP_SQUARE_BITS)?;
+        self.index += DROP_SQUARE_BITS as usize;
+
+        let role = Role::try_from(role_bits + 1).map_err(|_| DecodeErrorKind::InvalidIndex)?;
+        let to = new_square(square_bits as u32)?;
+        UciMove::Put { role, to }
+            .to_move(&self.chess)
+            .map_err(|_| DecodeErrorKind::IllegalMove)
+    }
+
+    /// Decodes the move at the current offset without playing it.
+    fn decode_move(&mut self) -> IndexResult<Move> {
+        if can_drop(&self.chess) {
+            let drop_bit = get_bits_checked(&self.encoded.inner, self.index, 1)?;
+            self.index += 1;
+
+            if drop_bit == 1 {
+                return self.next_drop();
+            }
         }
 
         let turn = self.chess.turn();
@@ -480,23 +615,15 @@ impl Decode for CompactIndexDecoder<'_> {
         let square_bits = NEEDED_BITS[piece_count];
 
         let from = if square_bits > 0 {
-            let square_index = match get_bits_checked(&self.encoded.inner, self.index, square_bits)
-            {
-                Ok(v) => v,
-                Err(e) => return Some(Err(e)),
-            };
+            let square_index = get_bits_checked(&self.encoded.inner, self.index, square_bits)?;
             self.index += square_bits as usize;
 
-            let bb = bb.0;
-
-            match index_nth_set_bit(bb, square_index) {
-                Ok(i) => unsafe { Square::new_unchecked(i) },
-                Err(e) => return Some(Err(e)),
-            }
+            let i = index_nth_set_bit(bb.0, square_index)?;
+            unsafe { Square::new_unchecked(i) }
         } else if let Some(sq) = bb.single_square() {
             sq
         } else {
-            return Some(Err(DecodeError {}));
+            return Err(DecodeErrorKind::InvalidData);
         };
 
         let role_at_from = self
@@ -519,35 +646,63 @@ impl Decode for CompactIndexDecoder<'_> {
                 }
             }
         };
-        let move_bits = match get_bits_checked(&self.encoded.inner, self.index, move_bit_len) {
-            Ok(v) => v,
-            Err(e) => return Some(Err(e)),
-        };
+        let move_bits = get_bits_checked(&self.encoded.inner, self.index, move_bit_len)?;
         self.index += move_bit_len as usize;
-        match destination_from_move_index(from, role_at_from, white, move_bits) {
-            Ok((to, promotion)) => {
-                let uci = UciMove::Normal {
-                    from,
-                    to,
-                    promotion,
-                };
-
-                let r = uci.to_move(&self.chess).map_err(|_| DecodeError {});
-                Some(r.map(|m| {
-                    self.chess.play_unchecked(m); // uci.to_move already checks legality
-                    m
-                }))
-            }
-            Err(e) => Some(Err(e)),
+
+        if role_at_from == Role::King
+            && let Some(side) = castling_side_from_move_index(from, white, move_bits)
+        {
+            let rook = self
+                .chess
+                .castles()
+                .rook(turn, side)
+                .ok_or(DecodeErrorKind::IllegalMove)?;
+            let m = Move::Castle { king: from, rook };
+            return if self.chess.is_legal(m) {
+                Ok(m)
+            } else {
+                Err(DecodeErrorKind::IllegalMove)
+            };
         }
+
+        let (to, promotion) = destination_from_move_index(from, role_at_from, white, move_bits)?;
+        let uci = UciMove::Normal {
+            from,
+            to,
+            promotion,
+        };
+        uci.to_move(&self.chess)
+            .map_err(|_| DecodeErrorKind::IllegalMove)
+    }
+}
+
+fn get_bits_checked(buffer: &[u64], begin: usize, len: u8) -> IndexResult<u64> {
+    buffer
+        .try_get_bits(begin, len)
+        .ok_or(DecodeErrorKind::TruncatedData)
+}
+
+impl Decode for CompactIndexDecoder<'_> {
+    fn next_move(&mut self) -> Option<DecodeResult<Move>> {
+        if self.index == self.encoded.bit_index {
+            return None;
+        }
+
+        let r = self
+            .decode_move()
+            .map_err(|kind| DecodeError::new(kind, self.ply + 1, CompressionLevel::Medium));
+        Some(r.inspect(|&m| {
+            self.chess.play_unchecked(m); // legality is checked by decode_move
+            self.ply += 1;
+        }))
     }
 
-    fn next_move_and_position(&mut self) -> Option<DecodeResult<(Move, &Chess)>> {
+    fn next_move_and_position(&mut self) -> Option<DecodeResult<(Move, &VariantPosition)>> {
         let maybe_next = self.next_move();
         maybe_next.map(|next| next.map(|m| (m, &self.chess)))
     }
 
-    fn next_position(&mut self) -> Option<DecodeResult<&Chess>> {
+    fn next_position(&mut self) -> Option<DecodeResult<&VariantPosition>> {
         let maybe_next = self.next_move();
         maybe_next.map(|next| next.map(|_| &self.chess))
     }
@@ -602,6 +757,6 @@ mod tests {
         for i in 0..64 {
             assert_eq!(index_nth_set_bit(v, i).unwrap(), i as u32);
         }
-        assert_eq!(index_nth_set_bit(v, 64).is_err(), true);
+        assert!(index_nth_set_bit(v, 64).is_err());
     }
 }
@@ -1,20 +1,32 @@
 use crate::{
-    CompressionLevel, Decode, DecodeError, DecodeResult, Encode, EncodeError, EncodedGame,
-    EncodedGameContent,
+    CompressionLevel, Decode, DecodeError, DecodeErrorKind, DecodeResult, Encode, EncodeError,
+    EncodedGame, EncodedGameContent,
 };
 use chess_huffman as huff;
-use shakmaty::{Chess, Move};
+use shakmaty::{Chess, Move, variant::VariantPosition};
 
 pub struct HuffEncoder<'a> {
     inner: huff::MoveByMoveEncoder<'a>,
+    plies: usize,
 }
 
 impl HuffEncoder<'_> {
     pub fn new() -> Self {
         Self {
             inner: huff::MoveByMoveEncoder::new(),
+            plies: 0,
         }
     }
+
+    /// Returns the position after the moves encoded so far.
+    pub(crate) fn position(&self) -> &Chess {
+        &self.inner.pos
+    }
+
+    /// Returns the number of moves encoded so far.
+    pub(crate) fn plies(&self) -> usize {
+        self.plies
+    }
 }
 
 impl Default for HuffEncoder<'_> {
@@ -27,7 +39,8 @@ impl Encode for HuffEncoder<'_> {
     fn encode_move(&mut self, m: Move) -> Result<(), EncodeError> {
         self.inner
             .add_move(m)
-            .map_err(|e| EncodeError::from_inner(e))?;
+            .map_err(|_| EncodeError::unsupported(m))?;
+        self.plies += 1;
         Ok(())
     }
 
@@ -35,12 +48,18 @@ impl Encode for HuffEncoder<'_> {
         EncodedGame {
             content: EncodedGameContent::Bits(self.inner.result),
             compression_level: CompressionLevel::High,
+            start_position: None,
+            keyframes: None,
         }
     }
 }
 
 pub struct HuffDecoder<'a> {
     inner: huff::MoveByMoveDecoder<'a>,
+    // copy of the inner decoder's position, which is always a standard chess position; only
+    // kept up to date by the methods that return a reference to it
+    pos: VariantPosition,
+    ply: usize,
 }
 
 impl<'a> HuffDecoder<'a> {
@@ -48,6 +67,8 @@ impl<'a> HuffDecoder<'a> {
         if let EncodedGameContent::Bits(enc) = encoded {
             Self {
                 inner: huff::MoveByMoveDecoder::new(enc),
+                pos: VariantPosition::default(),
+                ply: 0,
             }
         } else {
             panic!("HuffDecoder only accepts EncodedGameRef::Bits");
@@ -55,23 +76,71 @@ impl<'a> HuffDecoder<'a> {
     }
 }
 
+impl HuffDecoder<'_> {
+    /// Counts a decoded move; chess-huffman does not tell why decoding failed, so all failures
+    /// are reported as invalid data.
+    fn count_ply<T, E>(&mut self, r: Result<T, E>) -> DecodeResult<T> {
+        self.ply += 1;
+        r.map_err(|_| {
+            DecodeError::new(
+                DecodeErrorKind::InvalidData,
+                self.ply,
+                CompressionLevel::High,
+            )
+        })
+    }
+}
+
 impl Decode for HuffDecoder<'_> {
     fn next_move(&mut self) -> Option<DecodeResult<Move>> {
-        self.inner
-            .next_move()
-            .map(|m| m.map_err(|_| DecodeError {}))
+        let r = self.inner.next_move()?;
+        Some(self.count_ply(r))
     }
 
-    fn next_move_and_position(&mut self) -> Option<DecodeResult<(Move, &Chess)>> {
-        self.inner
-            .next_move_and_position()
-            .map(|m| m.map_err(|_| DecodeError {}))
+    fn next_move_and_position(&mut self) -> Option<DecodeResult<(Move, &VariantPosition)>> {
+        let r = self
+            .inner
+            .next_move_and_position()?
+            .map(|(m, pos)| (m, VariantPosition::Chess(pos.clone())));
+        match self.count_ply(r) {
+            Ok((m, pos)) => {
+                self.pos = pos;
+                Some(Ok((m, &self.pos)))
+            }
+            Err(e) => Some(Err(e)),
+        }
     }
 
-    fn next_position(&mut self) -> Option<DecodeResult<&Chess>> {
-        self.inner
-            .next_position()
-            .map(|m| m.map_err(|_| DecodeError {}))
+    fn next_position(&mut self) -> Option<DecodeResult<&VariantPosition>> {
+        let r = self
+            .inner
+            .next_position()?
+            .map(|pos| VariantPosition::Chess(pos.clone()));
+        match self.count_ply(r) {
+            Ok(pos) => {
+                self.pos = pos;
+                Some(Ok(&self.pos))
+            }
+            Err(e) => Some(Err(e)),
+        }
+    }
+
+    // the owned variants skip the copy into `self.pos`
+
+    fn next_position_owned(&mut self) -> Option<DecodeResult<VariantPosition>> {
+        let r = self
+            .inner
+            .next_position()?
+            .map(|pos| VariantPosition::Chess(pos.clone()));
+        Some(self.count_ply(r))
+    }
+
+    fn next_move_and_position_owned(&mut self) -> Option<DecodeResult<(Move, VariantPosition)>> {
+        let r = self
+            .inner
+            .next_move_and_position()?
+            .map(|(m, pos)| (m, VariantPosition::Chess(pos.clone())));
+        Some(self.count_ply(r))
     }
 }
 
@@ -1,21 +1,28 @@
 use chess_huffman::EncodedGame as BitsEncodedGame;
 use shakmaty::{
-    Chess, Move,
-    san::{San, SanPlus, Suffix},
+    CastlingMode, Chess, Color, EnPassantMode, Move, Position,
+    packed::PackedSetup,
+    san::{San, SanError, SanPlus, Suffix},
     uci::UciMove,
+    variant::{Variant, VariantPosition},
 };
 use std::{
     borrow::Cow,
+    collections::VecDeque,
     fmt::{self},
 };
 
+mod annotations;
 mod compactindex;
 mod huffman;
 mod naive;
+mod pgn;
 
+pub use annotations::{Annotation, Annotations, MAX_VARIATION_DEPTH};
 use compactindex::{CompactIndexDecoder, CompactIndexEncoder};
 use huffman::{HuffDecoder, HuffEncoder};
 use naive::{NaiveDecoder, NaiveEncoder};
+pub use pgn::{PgnWriteError, PgnWriter};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
@@ -31,6 +38,28 @@ const LEVELS: [CompressionLevel; 3] = [
     CompressionLevel::High,
 ];
 
+/// Value of the two level bits in the last byte that marks an extended encoded game. The
+/// remaining six bits of that byte are the format version of the header, see `FORMAT_VERSION`.
+const EXTENDED_LEVEL_BITS: u8 = 0b11;
+/// Format version of the extended header written by `EncodedGame::into_bytes`. Games without
+/// extension data are written in the original format, which has no header.
+pub const FORMAT_VERSION: u8 = 1;
+/// Header flag: a packed start position (followed by its length) is embedded.
+const FLAG_START_POSITION: u8 = 0b0000_0001;
+/// Header flag: the game uses Chess960 castling rules. Requires `FLAG_START_POSITION`.
+const FLAG_CHESS960: u8 = 0b0000_0010;
+/// Header flag bits holding the variant of the game (see `variant_to_bits`). Requires
+/// `FLAG_START_POSITION`; zero for standard chess.
+const FLAG_VARIANT_MASK: u8 = 0b0001_1100;
+const FLAG_VARIANT_SHIFT: u8 = 2;
+/// Header flag: keyframes (see `Keyframes`) are embedded.
+const FLAG_KEYFRAMES: u8 = 0b0010_0000;
+/// Header flag: annotations are embedded. Reserved for a later format version; games that set
+/// it are rejected with `EncodedGameConstructionError::UnsupportedVersion`.
+const FLAG_ANNOTATIONS: u8 = 0b0100_0000;
+/// Header flags understood by this format version.
+const KNOWN_FLAGS: u8 = FLAG_START_POSITION | FLAG_CHESS960 | FLAG_VARIANT_MASK | FLAG_KEYFRAMES;
+
 /// Encoder for chess games with different compression levels.
 pub enum Encoder<'a> {
     Naive(NaiveEncoder),
@@ -43,6 +72,18 @@ pub enum Encoder<'a> {
 pub struct EncodedGame<'a> {
     content: EncodedGameContent<'a>,
     compression_level: CompressionLevel,
+    start_position: Option<VariantPosition>,
+    keyframes: Option<Keyframes>,
+}
+
+/// Snapshots of the position every `interval` plies, so that decoding can start from the
+/// nearest keyframe instead of the start of the game. Only used with the Low and Medium levels.
+#[derive(Clone, Debug)]
+pub(crate) struct Keyframes {
+    interval: u16,
+    /// The i-th keyframe holds the offset into the content (in bytes for Low, in bits for
+    /// Medium) and the position after ply `(i + 1) * interval`.
+    frames: Vec<(usize, VariantPosition)>,
 }
 
 #[derive(Clone, Debug)]
@@ -58,6 +99,7 @@ pub enum EncodedGameConstructionError {
     EmptyData = 0,
     InvalidCompressionLevel = 1,
     InvalidData = 2,
+    UnsupportedVersion = 3,
 }
 
 impl std::error::Error for EncodedGameConstructionError {}
@@ -74,6 +116,8 @@ impl fmt::Display for EncodedGameConstructionError {
                     "Invalid compression level in EncodedGame data",
                 EncodedGameConstructionError::InvalidData =>
                     "Invalid data for constructing EncodedGame",
+                EncodedGameConstructionError::UnsupportedVersion =>
+                    "Unsupported format version or feature in EncodedGame data",
             }
         )
     }
@@ -94,6 +138,14 @@ impl From<chess_huffman::EncodedGameConstructionError> for EncodedGameConstructi
 
 impl<'a> EncodedGame<'a> {
     /// Converts an encoded game into bytes. Use `from_bytes` to reconstruct.
+    ///
+    /// The compression level is stored in the top two bits of the last byte. Games that need
+    /// extension data are followed by a header: a flags byte and a last byte with both level bits
+    /// set and `FORMAT_VERSION` in the lower bits. The extension data precedes the header: the
+    /// keyframes, each as a 32-bit offset followed by the packed position and its length, and then
+    /// the interval and the number of keyframes as 16-bit integers (`FLAG_KEYFRAMES`), and the
+    /// packed start position and its length (`FLAG_START_POSITION`, with `FLAG_CHESS960` for
+    /// Chess960 games and the variant bits for variant games).
     #[must_use]
     pub fn into_bytes(self) -> Vec<u8> {
         let mut bytes = match self.content {
@@ -108,24 +160,51 @@ impl<'a> EncodedGame<'a> {
             bytes[len_minus_one] = last_byte | (self.compression_level as u8) << 6;
         }
 
+        let mut flags = 0;
+        if let Some(keyframes) = self.keyframes.filter(|k| !k.frames.is_empty()) {
+            for (offset, pos) in &keyframes.frames {
+                #[allow(clippy::cast_possible_truncation)]
+                bytes.extend_from_slice(&(*offset as u32).to_le_bytes());
+                push_packed_position(&mut bytes, pos);
+            }
+            bytes.extend_from_slice(&keyframes.interval.to_le_bytes());
+            #[allow(clippy::cast_possible_truncation)]
+            bytes.extend_from_slice(&(keyframes.frames.len() as u16).to_le_bytes());
+            flags |= FLAG_KEYFRAMES;
+        }
+
+        if let Some(pos) = self.start_position {
+            push_packed_position(&mut bytes, &pos);
+            flags |= FLAG_START_POSITION | variant_to_bits(pos.variant()) << FLAG_VARIANT_SHIFT;
+            if pos.castles().mode() == CastlingMode::Chess960 {
+                flags |= FLAG_CHESS960;
+            }
+        }
+
+        if flags != 0 {
+            bytes.push(flags);
+            bytes.push(EXTENDED_LEVEL_BITS << 6 | FORMAT_VERSION);
+        }
+
         bytes
     }
 
     /// Constructs an encoded game from a byte slice produced by `into_bytes`.
-    #[must_use]
     pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, EncodedGameConstructionError> {
         if bytes.is_empty() {
             return Err(EncodedGameConstructionError::EmptyData);
         }
 
-        let len_minus_one = bytes.len() - 1;
+        let (content_len, start_position, keyframes) = split_extension(bytes)?;
+        let bytes = &bytes[..content_len];
+        let len_minus_one = content_len - 1;
 
         let level_bits = bytes[len_minus_one] >> 6;
         if level_bits > 2 {
             return Err(EncodedGameConstructionError::InvalidCompressionLevel);
         }
 
-        let level = LEVELS[(bytes[len_minus_one] >> 6) as usize];
+        let level = LEVELS[level_bits as usize];
 
         let content = if level == CompressionLevel::Low {
             EncodedGameContent::Bytes(Cow::Borrowed(&bytes[..len_minus_one]))
@@ -133,18 +212,34 @@ impl<'a> EncodedGame<'a> {
             EncodedGameContent::Bits(BitsEncodedGame::from_bytes(bytes)?)
         };
 
+        if keyframes.is_some() && level == CompressionLevel::High {
+            return Err(EncodedGameConstructionError::InvalidData);
+        }
+
         Ok(EncodedGame {
             content,
             compression_level: level,
+            start_position,
+            keyframes,
         })
     }
 
     /// Constructs an encoded game from an owned byte vector produced by `into_bytes`.
-    #[must_use]
     pub fn from_owned_bytes(mut bytes: Vec<u8>) -> Result<Self, EncodedGameConstructionError> {
-        let len_minus_one = bytes.len() - 1;
+        if bytes.is_empty() {
+            return Err(EncodedGameConstructionError::EmptyData);
+        }
 
-        let level = LEVELS[(bytes[len_minus_one] >> 6) as usize];
+        let (content_len, start_position, keyframes) = split_extension(&bytes)?;
+        bytes.truncate(content_len);
+        let len_minus_one = content_len - 1;
+
+        let level_bits = bytes[len_minus_one] >> 6;
+        if level_bits > 2 {
+            return Err(EncodedGameConstructionError::InvalidCompressionLevel);
+        }
+
+        let level = LEVELS[level_bits as usize];
 
         let content = if level == CompressionLevel::Low {
             bytes.pop();
@@ -153,21 +248,318 @@ impl<'a> EncodedGame<'a> {
             EncodedGameContent::Bits(BitsEncodedGame::from_bytes(&bytes)?)
         };
 
+        if keyframes.is_some() && level == CompressionLevel::High {
+            return Err(EncodedGameConstructionError::InvalidData);
+        }
+
         Ok(EncodedGame {
             content,
             compression_level: level,
+            start_position,
+            keyframes,
         })
     }
 
-    /// Recompresses the encoded game into a different compression level.
+    /// Encodes a game given as space-separated UCI moves from the standard starting position,
+    /// the inverse of `Decoder::into_uci_string`. Use `Encoder::encode_uci` for games from
+    /// other start positions.
+    pub fn from_uci_str(
+        uci: &str,
+        compression_level: CompressionLevel,
+    ) -> Result<EncodedGame<'static>, EncodeError> {
+        let mut encoder = Encoder::new(compression_level);
+        for token in uci.split_whitespace() {
+            encoder.encode_uci(token)?;
+        }
+        Ok(encoder.finish())
+    }
+
+    /// Encodes a game given as PGN movetext from the standard starting position, the inverse of
+    /// `Decoder::into_pgn_string`. Move numbers, the game result and numeric annotation glyphs
+    /// are skipped; comments and variations are not supported. Use `Encoder::encode_san` for
+    /// games from other start positions.
+    pub fn from_san_movetext(
+        movetext: &str,
+        compression_level: CompressionLevel,
+    ) -> Result<EncodedGame<'static>, EncodeError> {
+        let mut encoder = Encoder::new(compression_level);
+        for token in movetext.split_whitespace() {
+            // move numbers may be attached to the move, as in "1.e4"
+            let san = token.rsplit_once('.').map_or(token, |(number, san)| {
+                if number
+                    .trim_end_matches('.')
+                    .bytes()
+                    .all(|b| b.is_ascii_digit())
+                {
+                    san
+                } else {
+                    token
+                }
+            });
+            if san.is_empty() || is_movetext_filler(san) {
+                continue;
+            }
+            encoder.encode_san(san)?;
+        }
+        Ok(encoder.finish())
+    }
+
+    /// Returns the position the game starts from.
     #[must_use]
+    pub fn start_position(&self) -> VariantPosition {
+        self.start_position.clone().unwrap_or_default()
+    }
+
+    /// Returns the variant of the game, `Variant::Chess` for standard chess.
+    #[must_use]
+    pub fn variant(&self) -> Variant {
+        self.start_position
+            .as_ref()
+            .map_or(Variant::Chess, VariantPosition::variant)
+    }
+
+    /// Returns the castling rules of the game: `CastlingMode::Chess960` for Chess960 games,
+    /// `CastlingMode::Standard` otherwise.
+    #[must_use]
+    pub fn castling_mode(&self) -> CastlingMode {
+        self.start_position
+            .as_ref()
+            .map_or(CastlingMode::Standard, |pos| pos.castles().mode())
+    }
+
+    /// Returns the compression level the game is encoded with. This can be lower than the level
+    /// requested from the encoder, see `Encoder::with_start_position`.
+    #[must_use]
+    pub fn compression_level(&self) -> CompressionLevel {
+        self.compression_level
+    }
+
+    /// Returns the number of plies between keyframes, if the game has keyframes.
+    #[must_use]
+    pub fn keyframe_interval(&self) -> Option<u16> {
+        self.keyframes.as_ref().map(|k| k.interval)
+    }
+
+    /// Recompresses the encoded game into a different compression level.
+    ///
+    /// Games that do not start from the standard position (including all variant games) are
+    /// recompressed to Medium when High is requested, see `Encoder::with_start_position`.
+    /// Keyframes are kept, so games with keyframes are recompressed to Medium as well, see
+    /// `with_keyframes`.
     pub fn recompress(self, level: CompressionLevel) -> DecodeResult<Self> {
-        let mut encoder = Encoder::new(level);
+        let mut encoder = Encoder::with_start_position(level, self.start_position());
         let mut decoder = Decoder::new(&self);
         while let Some(m) = decoder.next_move() {
             encoder.encode_move(m?).expect("Encoding in recompress() failed, which should not happen because decoding succeeded");
         }
-        Ok(encoder.finish())
+        let recompressed = encoder.finish();
+        match self.keyframe_interval() {
+            Some(interval) => recompressed.with_keyframes(interval),
+            None => Ok(recompressed),
+        }
+    }
+
+    /// Embeds a keyframe every `interval` plies, so that `Decoder::position_at` can skip the
+    /// moves before the nearest keyframe. An interval of 0 removes the keyframes.
+    ///
+    /// Keyframes are not supported by the High compression level, so such games are
+    /// recompressed to Medium first.
+    pub fn with_keyframes(self, interval: u16) -> DecodeResult<Self> {
+        let mut game = if self.compression_level == CompressionLevel::High && interval > 0 {
+            self.recompress(CompressionLevel::Medium)?
+        } else {
+            self
+        };
+        if interval == 0 {
+            game.keyframes = None;
+            return Ok(game);
+        }
+
+        let mut frames = vec![];
+        let mut decoder = Decoder::new(&game);
+        let mut ply = 0;
+        while let Some(pos) = decoder.next_position() {
+            let pos = pos?;
+            ply += 1;
+            if ply % interval == 0 {
+                let pos = pos.clone();
+                frames.push((decoder.offset(), pos));
+            }
+        }
+
+        game.keyframes = Some(Keyframes { interval, frames });
+        Ok(game)
+    }
+}
+
+/// Splits the header and extension data off bytes produced by `into_bytes`. Returns the length
+/// of the regular content (including its own level byte), the embedded start position and the
+/// keyframes, if any.
+#[allow(clippy::type_complexity)]
+fn split_extension(
+    bytes: &[u8],
+) -> Result<(usize, Option<VariantPosition>, Option<Keyframes>), EncodedGameConstructionError> {
+    let len_minus_one = bytes.len() - 1;
+    let last_byte = bytes[len_minus_one];
+    if last_byte >> 6 != EXTENDED_LEVEL_BITS {
+        return Ok((bytes.len(), None, None));
+    }
+
+    let version = last_byte & 0b0011_1111;
+    if version == 0 {
+        return Err(EncodedGameConstructionError::InvalidData);
+    }
+    if version > FORMAT_VERSION {
+        return Err(EncodedGameConstructionError::UnsupportedVersion);
+    }
+
+    let mut end = len_minus_one;
+    let [flags] = pop_array(bytes, &mut end)?;
+    // annotations and unknown flags come from a newer encoder
+    if flags & FLAG_ANNOTATIONS != 0 || flags & !KNOWN_FLAGS != 0 {
+        return Err(EncodedGameConstructionError::UnsupportedVersion);
+    }
+    let requires_start_position = FLAG_CHESS960 | FLAG_VARIANT_MASK;
+    if flags == 0 || (flags & requires_start_position != 0 && flags & FLAG_START_POSITION == 0) {
+        return Err(EncodedGameConstructionError::InvalidData);
+    }
+    let castling_mode = if flags & FLAG_CHESS960 != 0 {
+        CastlingMode::Chess960
+    } else {
+        CastlingMode::Standard
+    };
+    let variant = variant_from_bits((flags & FLAG_VARIANT_MASK) >> FLAG_VARIANT_SHIFT);
+
+    let start_position = if flags & FLAG_START_POSITION != 0 {
+        Some(pop_packed_position(
+            bytes,
+            &mut end,
+            variant,
+            castling_mode,
+        )?)
+    } else {
+        None
+    };
+
+    let keyframes = if flags & FLAG_KEYFRAMES != 0 {
+        let count = u16::from_le_bytes(pop_array(bytes, &mut end)?);
+        let interval = u16::from_le_bytes(pop_array(bytes, &mut end)?);
+        if interval == 0 {
+            return Err(EncodedGameConstructionError::InvalidData);
+        }
+        let mut frames = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let pos = pop_packed_position(bytes, &mut end, variant, castling_mode)?;
+            let offset = u32::from_le_bytes(pop_array(bytes, &mut end)?) as usize;
+            frames.push((offset, pos));
+        }
+        frames.reverse();
+        Some(Keyframes { interval, frames })
+    } else {
+        None
+    };
+
+    if end == 0 {
+        return Err(EncodedGameConstructionError::InvalidData);
+    }
+
+    Ok((end, start_position, keyframes))
+}
+
+/// Appends the packed `pos` followed by its length.
+fn push_packed_position(bytes: &mut Vec<u8>, pos: &VariantPosition) {
+    let setup = pos.to_setup(EnPassantMode::Legal);
+    let packed = match pos.variant() {
+        Variant::Chess => PackedSetup::pack_standard(&setup),
+        variant => PackedSetup::pack_variant(&setup, variant),
+    }
+    .expect("a legal position can always be packed");
+    let packed_bytes = packed.as_bytes();
+    bytes.extend_from_slice(packed_bytes);
+    #[allow(clippy::cast_possible_truncation)]
+    bytes.push(packed_bytes.len() as u8); // at most PackedSetup::MAX_BYTES
+}
+
+/// Reads a position written by `push_packed_position` that ends at `end`, and moves `end` to
+/// its start.
+fn pop_packed_position(
+    bytes: &[u8],
+    end: &mut usize,
+    variant: Variant,
+    castling_mode: CastlingMode,
+) -> Result<VariantPosition, EncodedGameConstructionError> {
+    let [packed_len] = pop_array(bytes, end)?;
+    let packed_len = packed_len as usize;
+    if packed_len > *end {
+        return Err(EncodedGameConstructionError::InvalidData);
+    }
+    let packed = PackedSetup::try_from_bytes(&bytes[*end - packed_len..*end])
+        .map_err(|_| EncodedGameConstructionError::InvalidData)?;
+    let (setup, packed_variant) = packed
+        .unpack_variant()
+        .map_err(|_| EncodedGameConstructionError::InvalidData)?;
+    if packed_variant != variant {
+        return Err(EncodedGameConstructionError::InvalidData);
+    }
+    *end -= packed_len;
+    VariantPosition::from_setup(variant, setup, castling_mode)
+        .map_err(|_| EncodedGameConstructionError::InvalidData)
+}
+
+/// Reads the `N` bytes that end at `end`, and moves `end` to their start.
+fn pop_array<const N: usize>(
+    bytes: &[u8],
+    end: &mut usize,
+) -> Result<[u8; N], EncodedGameConstructionError> {
+    if *end < N {
+        return Err(EncodedGameConstructionError::InvalidData);
+    }
+    *end -= N;
+    Ok(bytes[*end..*end + N]
+        .try_into()
+        .expect("slice has length N"))
+}
+
+/// Returns the value of the variant bits in the extension flags for `variant`.
+fn variant_to_bits(variant: Variant) -> u8 {
+    match variant {
+        Variant::Chess => 0,
+        Variant::Crazyhouse => 1,
+        Variant::Atomic => 2,
+        Variant::KingOfTheHill => 3,
+        Variant::ThreeCheck => 4,
+        Variant::Antichess => 5,
+        Variant::Horde => 6,
+        Variant::RacingKings => 7,
+    }
+}
+
+/// Inverse of `variant_to_bits`, `bits` must be at most 7.
+fn variant_from_bits(bits: u8) -> Variant {
+    match bits {
+        0 => Variant::Chess,
+        1 => Variant::Crazyhouse,
+        2 => Variant::Atomic,
+        3 => Variant::KingOfTheHill,
+        4 => Variant::ThreeCheck,
+        5 => Variant::Antichess,
+        6 => Variant::Horde,
+        7 => Variant::RacingKings,
+        _ => unreachable!("variant bits are masked to three bits"),
+    }
+}
+
+/// Returns true if `pos` is the standard starting position of standard chess, including the
+/// move counters and castling rules.
+fn is_standard_start(pos: &VariantPosition) -> bool {
+    match pos {
+        VariantPosition::Chess(chess) => {
+            chess.castles().mode() == CastlingMode::Standard
+                && chess.halfmoves() == 0
+                && chess.fullmoves().get() == 1
+                && *chess == Chess::new()
+        }
+        _ => false,
     }
 }
 
@@ -181,6 +573,76 @@ impl Encoder<'_> {
             CompressionLevel::High => Encoder::Huffman(HuffEncoder::new()),
         }
     }
+
+    /// Creates a new encoder for a game that starts from `pos` (e.g. from a `FEN` header).
+    /// The castling rules of `pos` are used for the game, so a position set up with
+    /// `CastlingMode::Chess960` produces a Chess960 game. Likewise, the variant of `pos`
+    /// (standard chess for a `Chess` position) is the variant of the game.
+    ///
+    /// The High compression level can only encode standard chess games from the standard
+    /// starting position, so Medium is used instead when High is requested for any other
+    /// start position or variant.
+    #[must_use]
+    pub fn with_start_position(
+        compression_level: CompressionLevel,
+        pos: impl Into<VariantPosition>,
+    ) -> Self {
+        let pos = pos.into();
+        if is_standard_start(&pos) {
+            return Encoder::new(compression_level);
+        }
+
+        match compression_level {
+            CompressionLevel::Low => Encoder::Naive(NaiveEncoder::with_start_position(pos)),
+            CompressionLevel::Medium | CompressionLevel::High => {
+                Encoder::CompactIndex(CompactIndexEncoder::with_start_position(pos))
+            }
+        }
+    }
+
+    /// Parses `uci` as a move in the current position of the game, checks that it is legal and
+    /// encodes it. Castling moves of Chess960 games are given as the king moving to the rook
+    /// square, like `Decoder::into_uci_string` produces them. Returns the encoded move.
+    pub fn encode_uci(&mut self, uci: &str) -> Result<Move, EncodeError> {
+        let m = match self {
+            Encoder::Naive(enc) => parse_uci_move(enc.position(), uci),
+            Encoder::CompactIndex(enc) => parse_uci_move(enc.position(), uci),
+            Encoder::Huffman(enc) => parse_uci_move(enc.position(), uci),
+        };
+        self.encode_token(m, uci)
+    }
+
+    /// Parses `san` as a move in the current position of the game, checks that it is legal and
+    /// encodes it. Returns the encoded move.
+    pub fn encode_san(&mut self, san: &str) -> Result<Move, EncodeError> {
+        let m = match self {
+            Encoder::Naive(enc) => parse_san_move(enc.position(), san),
+            Encoder::CompactIndex(enc) => parse_san_move(enc.position(), san),
+            Encoder::Huffman(enc) => parse_san_move(enc.position(), san),
+        };
+        self.encode_token(m, san)
+    }
+
+    /// Returns the number of moves encoded so far.
+    fn plies(&self) -> usize {
+        match self {
+            Encoder::Naive(enc) => enc.plies(),
+            Encoder::CompactIndex(enc) => enc.plies(),
+            Encoder::Huffman(enc) => enc.plies(),
+        }
+    }
+
+    /// Encodes the move parsed from `token`, attaching the ply and token to errors.
+    fn encode_token(
+        &mut self,
+        m: Result<Move, EncodeError>,
+        token: &str,
+    ) -> Result<Move, EncodeError> {
+        let ply = self.plies() + 1;
+        let m = m.map_err(|e| e.at(ply, token))?;
+        self.encode_move(m).map_err(|e| e.at(ply, token))?;
+        Ok(m)
+    }
 }
 
 impl Encode for Encoder<'_> {
@@ -218,20 +680,70 @@ impl<'a> Decoder<'a> {
     /// Creates a new decoder for an encoded game.
     #[must_use]
     pub fn new(encoded: &'a EncodedGame) -> Self {
+        let start_position = encoded.start_position.as_ref();
+        let keyframes = encoded.keyframes.as_ref();
         match encoded.compression_level {
-            CompressionLevel::Low => Decoder::Naive(NaiveDecoder::new(&encoded.content)),
-            CompressionLevel::Medium => {
-                Decoder::CompactIndex(CompactIndexDecoder::new(&encoded.content))
-            }
+            CompressionLevel::Low => Decoder::Naive(NaiveDecoder::new(
+                &encoded.content,
+                start_position,
+                keyframes,
+            )),
+            CompressionLevel::Medium => Decoder::CompactIndex(CompactIndexDecoder::new(
+                &encoded.content,
+                start_position,
+                keyframes,
+            )),
             CompressionLevel::High => Decoder::Huffman(HuffDecoder::new(&encoded.content)),
         }
     }
 
+    /// Returns the position the decoded game starts from.
+    #[must_use]
+    pub fn start_position(&self) -> VariantPosition {
+        match self {
+            Decoder::Naive(decoder) => decoder.start_position.cloned().unwrap_or_default(),
+            Decoder::CompactIndex(decoder) => decoder.start_position.cloned().unwrap_or_default(),
+            // High only supports the standard starting position
+            Decoder::Huffman(_) => VariantPosition::default(),
+        }
+    }
+
+    /// Returns the variant of the decoded game, see `EncodedGame::variant`.
+    #[must_use]
+    pub fn variant(&self) -> Variant {
+        match self {
+            Decoder::Naive(decoder) => decoder
+                .start_position
+                .map_or(Variant::Chess, VariantPosition::variant),
+            Decoder::CompactIndex(decoder) => decoder
+                .start_position
+                .map_or(Variant::Chess, VariantPosition::variant),
+            Decoder::Huffman(_) => Variant::Chess,
+        }
+    }
+
+    /// Returns the castling rules of the decoded game, see `EncodedGame::castling_mode`.
+    #[must_use]
+    pub fn castling_mode(&self) -> CastlingMode {
+        match self {
+            Decoder::Naive(decoder) => decoder
+                .start_position
+                .map_or(CastlingMode::Standard, |pos| pos.castles().mode()),
+            Decoder::CompactIndex(decoder) => decoder
+                .start_position
+                .map_or(CastlingMode::Standard, |pos| pos.castles().mode()),
+            Decoder::Huffman(_) => CastlingMode::Standard,
+        }
+    }
+
     /// Decodes all moves and represents the game as a UCI string.
+    /// Castling moves of Chess960 games are represented as the king moving to the rook square,
+    /// drops (Crazyhouse) as e.g. `N@f3`.
     pub fn into_uci_string(self) -> DecodeResult<String>
     where
         Self: Sized,
     {
+        let castling_mode = self.castling_mode();
         let mut s = String::new();
         let mut first = true;
         for m in self.into_iter_moves() {
@@ -239,7 +751,7 @@ impl<'a> Decoder<'a> {
                 s.push(' ');
             }
             first = false;
-            s.push_str(&UciMove::from_standard(m?).to_string());
+            s.push_str(&UciMove::from_move(m?, castling_mode).to_string());
         }
         Ok(s)
     }
@@ -250,21 +762,20 @@ impl<'a> Decoder<'a> {
         Self: Sized,
     {
         let mut s = String::new();
-        let mut pos = Chess::new();
+        let mut pos = self.start_position();
         let mut first = true;
-        let mut i = 2;
         for r in self.into_iter_moves_and_positions() {
             let (m, next_pos) = r?;
             if !first {
                 s.push(' ');
             }
-            first = false;
 
-            if i % 2 == 0 {
-                let move_number = i / 2;
-                s.push_str(&format!("{}. ", move_number));
+            if pos.turn() == Color::White {
+                s.push_str(&format!("{}. ", pos.fullmoves()));
+            } else if first {
+                s.push_str(&format!("{}... ", pos.fullmoves()));
             }
-            i += 1;
+            first = false;
 
             let san = San::from_move(&pos, m);
             let suffix = Suffix::from_position(&next_pos);
@@ -277,8 +788,106 @@ impl<'a> Decoder<'a> {
         Ok(s)
     }
 
+    /// Returns the position at `index` without decoding the rest of the game: index 0 is the
+    /// start position, index `n` the position after the `n`-th ply. Negative indices count from
+    /// the end, -1 is the final position. Returns `None` if the index is out of range.
+    ///
+    /// Negative indices are handled in a single pass over the game, keeping only the last
+    /// `-index` positions. If the game has keyframes, decoding starts from the nearest
+    /// keyframe before the position (for negative indices: the last keyframe, if the position
+    /// is after it).
+    pub fn position_at(mut self, index: i32) -> DecodeResult<Option<VariantPosition>> {
+        if index == 0 {
+            return Ok(Some(self.start_position()));
+        }
+
+        let keyframes = self.keyframes();
+        if index > 0 {
+            let mut ply = index as usize;
+            if let Some(keyframes) = keyframes {
+                let frame_count = (ply / keyframes.interval as usize).min(keyframes.frames.len());
+                if frame_count > 0 {
+                    let (offset, pos) = &keyframes.frames[frame_count - 1];
+                    let frame_ply = frame_count * keyframes.interval as usize;
+                    ply -= frame_ply;
+                    if ply == 0 {
+                        return Ok(Some(pos.clone()));
+                    }
+                    self.jump(*offset, frame_ply, pos)?;
+                }
+            }
+            return self.seek_to_ply(ply).map(|r| r.cloned()).transpose();
+        }
+
+        let keep = index.unsigned_abs() as usize;
+        if let Some(keyframes) = keyframes
+            && let Some((offset, pos)) = keyframes.frames.last()
+        {
+            let frame_ply = keyframes.frames.len() * keyframes.interval as usize;
+            self.jump(*offset, frame_ply, pos)?;
+            if let Some(found) = self.position_from_end(pos.clone(), keep)? {
+                return Ok(Some(found));
+            }
+            // the position is before the last keyframe, start over
+            let start = self.start_position();
+            self.jump(0, 0, &start)?;
+        }
+        let start = self.start_position();
+        self.position_from_end(start, keep)
+    }
+
+    /// Decodes the rest of the game and returns the `keep`-th last position, counting `first`
+    /// (the current position) as well.
+    fn position_from_end(
+        &mut self,
+        first: VariantPosition,
+        keep: usize,
+    ) -> DecodeResult<Option<VariantPosition>> {
+        let mut last_positions = VecDeque::new();
+        last_positions.push_back(first);
+        while let Some(pos) = self.next_position_owned() {
+            if last_positions.len() == keep {
+                last_positions.pop_front();
+            }
+            last_positions.push_back(pos?);
+        }
+
+        Ok(if last_positions.len() == keep {
+            last_positions.pop_front()
+        } else {
+            None
+        })
+    }
+
+    fn keyframes(&self) -> Option<&'a Keyframes> {
+        match self {
+            Decoder::Naive(decoder) => decoder.keyframes,
+            Decoder::CompactIndex(decoder) => decoder.keyframes,
+            Decoder::Huffman(_) => None,
+        }
+    }
+
+    /// Continues decoding from `offset` in the content, where the position is `pos` after ply
+    /// `ply`.
+    fn jump(&mut self, offset: usize, ply: usize, pos: &VariantPosition) -> DecodeResult<()> {
+        match self {
+            Decoder::Naive(decoder) => decoder.jump(offset, ply, pos),
+            Decoder::CompactIndex(decoder) => decoder.jump(offset, ply, pos),
+            Decoder::Huffman(_) => unreachable!("High games have no keyframes"),
+        }
+    }
+
+    /// Returns the offset in the content of the next move to decode.
+    fn offset(&self) -> usize {
+        match self {
+            Decoder::Naive(decoder) => decoder.offset(),
+            Decoder::CompactIndex(decoder) => decoder.offset(),
+            Decoder::Huffman(_) => unreachable!("High games have no keyframes"),
+        }
+    }
+
     /// Decodes all moves and positions into vectors.
-    pub fn decode_all_moves_and_positions(self) -> DecodeResult<(Vec<Move>, Vec<Chess>)> {
+    pub fn decode_all_moves_and_positions(self) -> DecodeResult<(Vec<Move>, Vec<VariantPosition>)> {
         let mut moves = vec![];
         let mut positions = vec![];
 
@@ -301,7 +910,7 @@ impl Decode for Decoder<'_> {
         }
     }
 
-    fn next_position(&mut self) -> Option<DecodeResult<&Chess>> {
+    fn next_position(&mut self) -> Option<DecodeResult<&VariantPosition>> {
         match self {
             Decoder::Naive(decoder) => decoder.next_position(),
             Decoder::CompactIndex(decoder) => decoder.next_position(),
@@ -309,22 +918,67 @@ impl Decode for Decoder<'_> {
         }
     }
 
-    fn next_move_and_position(&mut self) -> Option<DecodeResult<(Move, &Chess)>> {
+    fn next_move_and_position(&mut self) -> Option<DecodeResult<(Move, &VariantPosition)>> {
         match self {
             Decoder::Naive(decoder) => decoder.next_move_and_position(),
             Decoder::CompactIndex(decoder) => decoder.next_move_and_position(),
             Decoder::Huffman(decoder) => decoder.next_move_and_position(),
         }
     }
+
+    fn next_position_owned(&mut self) -> Option<DecodeResult<VariantPosition>> {
+        match self {
+            Decoder::Naive(decoder) => decoder.next_position_owned(),
+            Decoder::CompactIndex(decoder) => decoder.next_position_owned(),
+            Decoder::Huffman(decoder) => decoder.next_position_owned(),
+        }
+    }
+
+    fn next_move_and_position_owned(&mut self) -> Option<DecodeResult<(Move, VariantPosition)>> {
+        match self {
+            Decoder::Naive(decoder) => decoder.next_move_and_position_owned(),
+            Decoder::CompactIndex(decoder) => decoder.next_move_and_position_owned(),
+            Decoder::Huffman(decoder) => decoder.next_move_and_position_owned(),
+        }
+    }
 }
 
 pub trait Decode {
     /// Decodes the next move and returns it.
     fn next_move(&mut self) -> Option<DecodeResult<Move>>;
     /// Decodes the next move and returns the position after it.
-    fn next_position(&mut self) -> Option<DecodeResult<&Chess>>;
+    fn next_position(&mut self) -> Option<DecodeResult<&VariantPosition>>;
     /// Decodes the next move and returns it along with the position after it.
-    fn next_move_and_position(&mut self) -> Option<DecodeResult<(Move, &Chess)>>;
+    fn next_move_and_position(&mut self) -> Option<DecodeResult<(Move, &VariantPosition)>>;
+
+    /// Like `next_position`, but returns the position by value. Decoders that do not keep a
+    /// `VariantPosition` themselves override this to build it only once per ply.
+    fn next_position_owned(&mut self) -> Option<DecodeResult<VariantPosition>> {
+        self.next_position().map(|r| r.cloned())
+    }
+
+    /// Like `next_move_and_position`, but returns the position by value, see
+    /// `next_position_owned`.
+    fn next_move_and_position_owned(&mut self) -> Option<DecodeResult<(Move, VariantPosition)>> {
+        self.next_move_and_position()
+            .map(|r| r.map(|(m, p)| (m, p.clone())))
+    }
+
+    /// Decodes the next `ply` moves and returns the position after the last one, so on a new
+    /// decoder this is the position after ply `ply`. The positions in between are not cloned,
+    /// and the rest of the game is not decoded. Returns `None` if the game ends before (or if
+    /// `ply` is 0, see `Decoder::position_at` for the start position).
+    fn seek_to_ply(&mut self, ply: usize) -> Option<DecodeResult<&VariantPosition>> {
+        if ply == 0 {
+            return None;
+        }
+        for _ in 1..ply {
+            if let Err(e) = self.next_move()? {
+                return Some(Err(e));
+            }
+        }
+        self.next_position()
+    }
 
     /// Converts the decoder into an iterator over moves.
     fn into_iter_moves(self) -> impl Iterator<Item = DecodeResult<Move>>
@@ -333,19 +987,19 @@ pub trait Decode {
     {
         struct MoveIter<T> {
             decoder: T,
-            error: bool,
+            error: Option<DecodeError>,
         }
         impl<T: Decode> Iterator for MoveIter<T> {
             type Item = DecodeResult<Move>;
 
             fn next(&mut self) -> Option<Self::Item> {
-                if self.error {
-                    return Some(Err(DecodeError {}));
+                if let Some(e) = &self.error {
+                    return Some(Err(e.clone()));
                 }
 
                 let item = self.decoder.next_move();
-                if let Some(Err(_)) = item {
-                    self.error = true;
+                if let Some(Err(e)) = &item {
+                    self.error = Some(e.clone());
                 }
                 item
             }
@@ -353,30 +1007,30 @@ pub trait Decode {
 
         MoveIter {
             decoder: self,
-            error: false,
+            error: None,
         }
     }
 
     /// Converts the decoder into an iterator over positions.
-    fn into_iter_positions(self) -> impl Iterator<Item = DecodeResult<Chess>>
+    fn into_iter_positions(self) -> impl Iterator<Item = DecodeResult<VariantPosition>>
     where
         Self: Sized,
     {
         struct PosIter<T> {
             decoder: T,
-            error: bool,
+            error: Option<DecodeError>,
         }
         impl<T: Decode> Iterator for PosIter<T> {
-            type Item = DecodeResult<Chess>;
+            type Item = DecodeResult<VariantPosition>;
 
             fn next(&mut self) -> Option<Self::Item> {
-                if self.error {
-                    return Some(Err(DecodeError {}));
+                if let Some(e) = &self.error {
+                    return Some(Err(e.clone()));
                 }
 
-                let item = self.decoder.next_position().map(|res| res.cloned());
-                if let Some(Err(_)) = item {
-                    self.error = true;
+                let item = self.decoder.next_position_owned();
+                if let Some(Err(e)) = &item {
+                    self.error = Some(e.clone());
                 }
                 item
             }
@@ -384,33 +1038,32 @@ pub trait Decode {
 
         PosIter {
             decoder: self,
-            error: false,
+            error: None,
         }
     }
 
     /// Converts the decoder into an iterator over moves and positions.
-    fn into_iter_moves_and_positions(self) -> impl Iterator<Item = DecodeResult<(Move, Chess)>>
+    fn into_iter_moves_and_positions(
+        self,
+    ) -> impl Iterator<Item = DecodeResult<(Move, VariantPosition)>>
     where
         Self: Sized,
     {
         struct MovePosIter<T> {
             decoder: T,
-            error: bool,
+            error: Option<DecodeError>,
         }
         impl<T: Decode> Iterator for MovePosIter<T> {
-            type Item = DecodeResult<(Move, Chess)>;
+            type Item = DecodeResult<(Move, VariantPosition)>;
 
             fn next(&mut self) -> Option<Self::Item> {
-                if self.error {
-                    return Some(Err(DecodeError {}));
+                if let Some(e) = &self.error {
+                    return Some(Err(e.clone()));
                 }
 
-                let item = self
-                    .decoder
-                    .next_move_and_position()
-                    .map(|r| r.map(|(m, p)| (m, p.clone())));
-                if let Some(Err(_)) = item {
-                    self.error = true;
+                let item = self.decoder.next_move_and_position_owned();
+                if let Some(Err(e)) = &item {
+                    self.error = Some(e.clone());
                 }
                 item
             }
@@ -418,43 +1071,226 @@ pub trait Decode {
 
         MovePosIter {
             decoder: self,
-            error: false,
+            error: None,
         }
     }
 }
 
 /// Error type for decoding failures.
-#[derive(Clone, Debug)]
-pub struct DecodeError {}
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodeError {
+    kind: DecodeErrorKind,
+    ply: usize,
+    compression_level: CompressionLevel,
+}
+
+/// Reason why a move could not be decoded, see `DecodeError::kind`.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeErrorKind {
+    /// The data ends in the middle of a move.
+    TruncatedData = 0,
+    /// The data refers to a piece, square or move index that does not exist.
+    InvalidIndex = 1,
+    /// The decoded move is not legal in the position.
+    IllegalMove = 2,
+    /// The data is corrupt in another way, e.g. an invalid Huffman code or keyframe.
+    InvalidData = 3,
+}
+
+impl DecodeError {
+    pub(crate) fn new(
+        kind: DecodeErrorKind,
+        ply: usize,
+        compression_level: CompressionLevel,
+    ) -> Self {
+        DecodeError {
+            kind,
+            ply,
+            compression_level,
+        }
+    }
+
+    /// Returns the reason of the failure.
+    #[must_use]
+    pub fn kind(&self) -> DecodeErrorKind {
+        self.kind
+    }
+
+    /// Returns the ply of the move that could not be decoded (1 for the first move of the game).
+    #[must_use]
+    pub fn ply(&self) -> usize {
+        self.ply
+    }
+
+    /// Returns the compression level of the encoded game.
+    #[must_use]
+    pub fn compression_level(&self) -> CompressionLevel {
+        self.compression_level
+    }
+}
 
 impl std::error::Error for DecodeError {}
 
 impl fmt::Display for DecodeError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Cannot decode invalid game data")
+        write!(
+            f,
+            "Cannot decode invalid game data at ply {} ({:?} compression level): {}",
+            self.ply,
+            self.compression_level,
+            match self.kind {
+                DecodeErrorKind::TruncatedData => "data ends in the middle of a move",
+                DecodeErrorKind::InvalidIndex => "invalid piece, square or move index",
+                DecodeErrorKind::IllegalMove => "illegal move",
+                DecodeErrorKind::InvalidData => "invalid data",
+            }
+        )
     }
 }
 
-/// Error type for decoding failures.
-#[derive(Debug)]
+/// Error type for encoding failures.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct EncodeError {
-    inner: Box<dyn std::fmt::Debug + Send + Sync>,
+    reason: EncodeErrorReason,
+    /// The ply (1 for the first move of the game) and the token of a move given as text.
+    token: Option<(usize, String)>,
+}
+
+/// Reason why a move could not be encoded, see `EncodeError::kind`.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncodeErrorKind {
+    /// The text of a move could not be parsed as UCI or SAN.
+    InvalidToken = 0,
+    /// The move is not legal in the position.
+    IllegalMove = 1,
+    /// The move cannot be represented by the encoder, e.g. a variant move for the High
+    /// compression level.
+    UnsupportedMove = 2,
+}
+
+/// Details of an encoding failure, see `EncodeError::reason`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncodeErrorReason {
+    /// The token is not a UCI move.
+    InvalidUci,
+    /// The token is not a SAN move.
+    InvalidSan,
+    /// The UCI move is not legal in the position.
+    IllegalUci(UciMove),
+    /// The SAN move is illegal or ambiguous in the position.
+    IllegalSan(San, SanError),
+    /// The encoder cannot represent the move.
+    UnsupportedMove(Move),
 }
 
 impl std::error::Error for EncodeError {}
 
 impl fmt::Display for EncodeError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Failed to encode move: {:?}", self.inner)
+        write!(f, "Failed to encode move")?;
+        if let Some((ply, token)) = &self.token {
+            write!(f, " {token} at ply {ply}")?;
+        }
+        match &self.reason {
+            EncodeErrorReason::InvalidUci => write!(f, ": invalid UCI move"),
+            EncodeErrorReason::InvalidSan => write!(f, ": invalid SAN move"),
+            EncodeErrorReason::IllegalUci(uci) => write!(f, ": illegal move {uci}"),
+            EncodeErrorReason::IllegalSan(san, SanError::AmbiguousSan) => {
+                write!(f, ": ambiguous move {san}")
+            }
+            EncodeErrorReason::IllegalSan(san, SanError::IllegalSan) => {
+                write!(f, ": illegal move {san}")
+            }
+            EncodeErrorReason::UnsupportedMove(m) => {
+                write!(f, ": move {m} is not supported by the compression level")
+            }
+        }
     }
 }
 
 impl EncodeError {
-    fn from_inner<E: std::fmt::Debug + Send + Sync + 'static>(err: E) -> Self {
+    fn new(reason: EncodeErrorReason) -> Self {
         EncodeError {
-            inner: Box::new(err),
+            reason,
+            token: None,
         }
     }
+
+    /// Creates an error for a move that the encoder cannot represent.
+    fn unsupported(m: Move) -> Self {
+        EncodeError::new(EncodeErrorReason::UnsupportedMove(m))
+    }
+
+    /// Attaches the ply and token of the move that failed to encode.
+    fn at(mut self, ply: usize, token: &str) -> Self {
+        self.token = Some((ply, token.to_owned()));
+        self
+    }
+
+    /// Returns the reason of the failure.
+    #[must_use]
+    pub fn kind(&self) -> EncodeErrorKind {
+        match self.reason {
+            EncodeErrorReason::InvalidUci | EncodeErrorReason::InvalidSan => {
+                EncodeErrorKind::InvalidToken
+            }
+            EncodeErrorReason::IllegalUci(_) | EncodeErrorReason::IllegalSan(..) => {
+                EncodeErrorKind::IllegalMove
+            }
+            EncodeErrorReason::UnsupportedMove(_) => EncodeErrorKind::UnsupportedMove,
+        }
+    }
+
+    /// Returns the details of the failure: the offending move, and why it was rejected.
+    #[must_use]
+    pub fn reason(&self) -> &EncodeErrorReason {
+        &self.reason
+    }
+
+    /// Returns the ply of the move that failed to encode (1 for the first move of the game), if
+    /// the move was given as text.
+    #[must_use]
+    pub fn ply(&self) -> Option<usize> {
+        self.token.as_ref().map(|(ply, _)| *ply)
+    }
+
+    /// Returns the text of the move that failed to encode, if the move was given as text.
+    #[must_use]
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_ref().map(|(_, token)| token.as_str())
+    }
+}
+
+/// Parses `uci` as a legal move in `pos`.
+fn parse_uci_move<P: Position>(pos: &P, uci: &str) -> Result<Move, EncodeError> {
+    let uci = uci
+        .parse::<UciMove>()
+        .map_err(|_| EncodeError::new(EncodeErrorReason::InvalidUci))?;
+    uci.to_move(pos)
+        .map_err(|_| EncodeError::new(EncodeErrorReason::IllegalUci(uci)))
+}
+
+/// Parses `san` as a legal move in `pos`. Check and checkmate suffixes and move annotations
+/// (`!`, `?`) are ignored.
+fn parse_san_move<P: Position>(pos: &P, san: &str) -> Result<Move, EncodeError> {
+    let san = san
+        .trim_end_matches(['!', '?'])
+        .parse::<SanPlus>()
+        .map_err(|_| EncodeError::new(EncodeErrorReason::InvalidSan))?
+        .san;
+    san.to_move(pos)
+        .map_err(|e| EncodeError::new(EncodeErrorReason::IllegalSan(san, e)))
+}
+
+/// Returns true for movetext tokens that are not moves: game results and numeric annotation
+/// glyphs (`$1`).
+fn is_movetext_filler(token: &str) -> bool {
+    matches!(token, "1-0" | "0-1" | "1/2-1/2" | "*")
+        || token
+            .strip_prefix('$')
+            .is_some_and(|nag| !nag.is_empty() && nag.bytes().all(|b| b.is_ascii_digit()))
 }
 
 /// Result type for decoding operations.
@@ -463,16 +1299,26 @@ pub type DecodeResult<T> = Result<T, DecodeError>;
 #[cfg(test)]
 mod tests {
     use quickcheck_macros::quickcheck;
-    use shakmaty::{Chess, Move, Position};
+    use shakmaty::{
+        CastlingMode, Chess, Move, Position,
+        fen::Fen,
+        san::SanError,
+        uci::UciMove,
+        variant::{Variant, VariantPosition},
+    };
 
     use crate::Decode;
 
-    use super::{CompressionLevel, Decoder, Encode, EncodedGame, Encoder};
+    use super::{
+        CompressionLevel, DecodeError, DecodeErrorKind, Decoder, Encode, EncodeErrorKind,
+        EncodeErrorReason, EncodedGame, EncodedGameConstructionError, Encoder, FLAG_ANNOTATIONS,
+        FLAG_START_POSITION, FLAG_VARIANT_SHIFT, FORMAT_VERSION,
+    };
 
     fn random_games_consistency(move_ids: Vec<u16>, level: CompressionLevel) -> bool {
-        let mut pos = Chess::default();
+        let mut pos = VariantPosition::default();
         let mut moves: Vec<Move> = vec![];
-        let mut positions: Vec<Chess> = vec![];
+        let mut positions: Vec<VariantPosition> = vec![];
 
         for m in move_ids {
             let legal_moves = pos.legal_moves();
@@ -508,7 +1354,7 @@ mod tests {
         let restored_moves: Vec<Move> = decoder.into_iter_moves().map(|m| m.unwrap()).collect();
 
         let decoder2 = Decoder::new(&restored);
-        let restored_positions: Vec<Chess> =
+        let restored_positions: Vec<VariantPosition> =
             decoder2.into_iter_positions().map(|p| p.unwrap()).collect();
 
         if moves != restored_moves {
@@ -537,19 +1383,699 @@ mod tests {
         random_games_consistency(move_ids, CompressionLevel::High)
     }
 
+    fn position_at_consistency(
+        move_ids: Vec<u16>,
+        level: CompressionLevel,
+        keyframe_interval: Option<u16>,
+    ) -> bool {
+        let start = VariantPosition::default();
+        let mut pos = start.clone();
+        let mut positions = vec![start];
+        let mut encoder = Encoder::new(level);
+        for m in move_ids {
+            let legal_moves = pos.legal_moves();
+            if legal_moves.is_empty() {
+                break;
+            }
+            let choice = legal_moves[m as usize % legal_moves.len()];
+            pos.play_unchecked(choice);
+            encoder.encode_move(choice).unwrap();
+            positions.push(pos.clone());
+        }
+
+        let mut encoded = encoder.finish();
+        if let Some(interval) = keyframe_interval {
+            let bytes = encoded.with_keyframes(interval).unwrap().into_bytes();
+            encoded = EncodedGame::from_owned_bytes(bytes).unwrap();
+            if encoded.keyframe_interval().is_none() && positions.len() > interval as usize {
+                panic!("keyframes missing after round trip");
+            }
+            let moves: Vec<Move> = Decoder::new(&encoded)
+                .into_iter_moves()
+                .map(|m| m.unwrap())
+                .collect();
+            if moves.len() != positions.len() - 1 {
+                panic!("moves.len() != positions.len() - 1");
+            }
+        }
+
+        let len = positions.len() as i32;
+        for index in -len - 2..len + 2 {
+            let expected = if index >= 0 {
+                positions.get(index as usize)
+            } else {
+                usize::try_from(len + index)
+                    .ok()
+                    .and_then(|i| positions.get(i))
+            };
+            let actual = Decoder::new(&encoded).position_at(index).unwrap();
+            if actual.as_ref() != expected {
+                panic!("position_at({index}) != positions[{index}]");
+            }
+        }
+        for index in [i32::MIN, i32::MAX] {
+            if Decoder::new(&encoded).position_at(index).unwrap().is_some() {
+                panic!("position_at({index}) is not None");
+            }
+        }
+
+        true
+    }
+
+    #[quickcheck]
+    fn position_at_consistency_low(move_ids: Vec<u16>) -> bool {
+        position_at_consistency(move_ids, CompressionLevel::Low, None)
+    }
+
+    #[quickcheck]
+    fn keyframes_position_at_consistency_low(move_ids: Vec<u16>, interval: u8) -> bool {
+        let interval = u16::from(interval % 16) + 1;
+        position_at_consistency(move_ids, CompressionLevel::Low, Some(interval))
+    }
+
+    #[quickcheck]
+    fn position_at_consistency_medium(move_ids: Vec<u16>) -> bool {
+        position_at_consistency(move_ids, CompressionLevel::Medium, None)
+    }
+
+    #[quickcheck]
+    fn keyframes_position_at_consistency_medium(move_ids: Vec<u16>, interval: u8) -> bool {
+        let interval = u16::from(interval % 16) + 1;
+        position_at_consistency(move_ids, CompressionLevel::Medium, Some(interval))
+    }
+
+    #[quickcheck]
+    fn position_at_consistency_high(move_ids: Vec<u16>) -> bool {
+        position_at_consistency(move_ids, CompressionLevel::High, None)
+    }
+
+    #[quickcheck]
+    fn keyframes_position_at_consistency_high(move_ids: Vec<u16>, interval: u8) -> bool {
+        let interval = u16::from(interval % 16) + 1;
+        position_at_consistency(move_ids, CompressionLevel::High, Some(interval))
+    }
+
+    fn random_games_from_position_consistency(
+        variant: Variant,
+        setup_move_ids: Vec<u16>,
+        move_ids: Vec<u16>,
+        level: CompressionLevel,
+    ) -> bool {
+        let mut start = VariantPosition::new(variant);
+        for m in setup_move_ids {
+            let legal_moves = start.legal_moves();
+            if legal_moves.is_empty() {
+                break;
+            }
+            start.play_unchecked(legal_moves[m as usize % legal_moves.len()]);
+        }
+
+        let mut pos = start.clone();
+        let mut moves: Vec<Move> = vec![];
+        let mut positions: Vec<VariantPosition> = vec![];
+        for m in move_ids {
+            let legal_moves = pos.legal_moves();
+            if legal_moves.is_empty() {
+                break;
+            }
+            let choice = legal_moves[m as usize % legal_moves.len()];
+            pos.play_unchecked(choice);
+            moves.push(choice);
+            positions.push(pos.clone());
+        }
+
+        let mut encoder = Encoder::with_start_position(level, start.clone());
+        for &m in &moves {
+            encoder.encode_move(m).unwrap();
+        }
+        let bytes = encoder.finish().into_bytes();
+
+        let restored = EncodedGame::from_bytes(&bytes).unwrap();
+        let restored_start = restored.start_position();
+        if restored_start != start || restored_start.fullmoves() != start.fullmoves() {
+            panic!("restored_start != start");
+        }
+
+        let owned = EncodedGame::from_owned_bytes(bytes.clone()).unwrap();
+        if owned.start_position() != start {
+            panic!("owned.start_position() != start");
+        }
+
+        let decoder = Decoder::new(&restored);
+        let restored_moves: Vec<Move> = decoder.into_iter_moves().map(|m| m.unwrap()).collect();
+        if moves != restored_moves {
+            panic!("restored_moves != moves");
+        }
+
+        let decoder2 = Decoder::new(&restored);
+        let restored_positions: Vec<VariantPosition> =
+            decoder2.into_iter_positions().map(|p| p.unwrap()).collect();
+        if positions != restored_positions {
+            panic!("restored_positions != positions");
+        }
+
+        true
+    }
+
+    #[quickcheck]
+    fn random_games_from_position_consistency_low(
+        setup_move_ids: Vec<u16>,
+        move_ids: Vec<u16>,
+    ) -> bool {
+        random_games_from_position_consistency(
+            Variant::Chess,
+            setup_move_ids,
+            move_ids,
+            CompressionLevel::Low,
+        )
+    }
+
+    #[quickcheck]
+    fn random_games_from_position_consistency_medium(
+        setup_move_ids: Vec<u16>,
+        move_ids: Vec<u16>,
+    ) -> bool {
+        random_games_from_position_consistency(
+            Variant::Chess,
+            setup_move_ids,
+            move_ids,
+            CompressionLevel::Medium,
+        )
+    }
+
+    #[quickcheck]
+    fn random_games_from_position_consistency_high(
+        setup_move_ids: Vec<u16>,
+        move_ids: Vec<u16>,
+    ) -> bool {
+        random_games_from_position_consistency(
+            Variant::Chess,
+            setup_move_ids,
+            move_ids,
+            CompressionLevel::High,
+        )
+    }
+
+    const VARIANTS: [Variant; 7] = [
+        Variant::Atomic,
+        Variant::Antichess,
+        Variant::KingOfTheHill,
+        Variant::ThreeCheck,
+        Variant::Crazyhouse,
+        Variant::RacingKings,
+        Variant::Horde,
+    ];
+
+    #[quickcheck]
+    fn random_variant_games_consistency_low(
+        variant_id: u8,
+        setup_move_ids: Vec<u16>,
+        move_ids: Vec<u16>,
+    ) -> bool {
+        random_games_from_position_consistency(
+            VARIANTS[variant_id as usize % VARIANTS.len()],
+            setup_move_ids,
+            move_ids,
+            CompressionLevel::Low,
+        )
+    }
+
+    #[quickcheck]
+    fn random_variant_games_consistency_medium(
+        variant_id: u8,
+        setup_move_ids: Vec<u16>,
+        move_ids: Vec<u16>,
+    ) -> bool {
+        random_games_from_position_consistency(
+            VARIANTS[variant_id as usize % VARIANTS.len()],
+            setup_move_ids,
+            move_ids,
+            CompressionLevel::Medium,
+        )
+    }
+
+    #[quickcheck]
+    fn random_variant_games_consistency_high(
+        variant_id: u8,
+        setup_move_ids: Vec<u16>,
+        move_ids: Vec<u16>,
+    ) -> bool {
+        random_games_from_position_consistency(
+            VARIANTS[variant_id as usize % VARIANTS.len()],
+            setup_move_ids,
+            move_ids,
+            CompressionLevel::High,
+        )
+    }
+
+    /// Returns Chess960 starting position number `n` (Scharnagl numbering).
+    fn chess960_start_position(n: u16) -> Chess {
+        let mut rank: [Option<char>; 8] = [None; 8];
+        let n = (n % 960) as usize;
+        rank[(n % 4) * 2 + 1] = Some('b');
+        rank[((n / 4) % 4) * 2] = Some('b');
+
+        let mut place_on_nth_empty = |piece: char, i: usize| {
+            let (file, _) = rank
+                .iter()
+                .enumerate()
+                .filter(|(_, p)| p.is_none())
+                .nth(i)
+                .unwrap();
+            rank[file] = Some(piece);
+        };
+        place_on_nth_empty('q', (n / 16) % 6);
+        let knights = [
+            (0, 0),
+            (0, 1),
+            (0, 2),
+            (0, 3),
+            (1, 1),
+            (1, 2),
+            (1, 3),
+            (2, 2),
+            (2, 3),
+            (3, 3),
+        ][n / 96];
+        // the second knight is placed after the first, so its index shifts by one
+        place_on_nth_empty('n', knights.0);
+        place_on_nth_empty('n', knights.1);
+        place_on_nth_empty('r', 0);
+        place_on_nth_empty('k', 0);
+        place_on_nth_empty('r', 0);
+
+        let black: String = rank.iter().map(|p| p.unwrap()).collect();
+        let fen: Fen = format!(
+            "{black}/pppppppp/8/8/8/8/PPPPPPPP/{} w KQkq - 0 1",
+            black.to_uppercase()
+        )
+        .parse()
+        .unwrap();
+        fen.into_position(CastlingMode::Chess960).unwrap()
+    }
+
+    fn random_chess960_games_consistency(
+        start_id: u16,
+        move_ids: Vec<u16>,
+        level: CompressionLevel,
+    ) -> bool {
+        let start = VariantPosition::from(chess960_start_position(start_id));
+        let mut pos = start.clone();
+        let mut moves: Vec<Move> = vec![];
+        let mut positions: Vec<VariantPosition> = vec![];
+
+        for m in move_ids {
+            let legal_moves = pos.legal_moves();
+            if legal_moves.is_empty() {
+                break;
+            }
+
+            let i = m as usize % legal_moves.len();
+            let choice = legal_moves[i];
+            pos.play_unchecked(choice);
+            moves.push(choice);
+            positions.push(pos.clone());
+        }
+
+        let mut encoder = Encoder::with_start_position(level, start.clone());
+        for &m in &moves {
+            encoder.encode_move(m).unwrap();
+        }
+
+        let bytes = encoder.finish().into_bytes();
+        let restored = EncodedGame::from_bytes(&bytes).unwrap();
+        if restored.castling_mode() != CastlingMode::Chess960 {
+            panic!("restored.castling_mode() != CastlingMode::Chess960");
+        }
+        if restored.start_position() != start {
+            panic!("restored.start_position() != start");
+        }
+
+        let decoder = Decoder::new(&restored);
+        let restored_moves: Vec<Move> = decoder.into_iter_moves().map(|m| m.unwrap()).collect();
+
+        let decoder2 = Decoder::new(&restored);
+        let restored_positions: Vec<VariantPosition> =
+            decoder2.into_iter_positions().map(|p| p.unwrap()).collect();
+
+        if moves != restored_moves {
+            panic!("restored_moves != moves");
+        }
+
+        if positions != restored_positions {
+            panic!("restored_positions != positions");
+        }
+
+        true
+    }
+
+    #[quickcheck]
+    fn random_chess960_games_consistency_low(start_id: u16, move_ids: Vec<u16>) -> bool {
+        random_chess960_games_consistency(start_id, move_ids, CompressionLevel::Low)
+    }
+
+    #[quickcheck]
+    fn random_chess960_games_consistency_medium(start_id: u16, move_ids: Vec<u16>) -> bool {
+        random_chess960_games_consistency(start_id, move_ids, CompressionLevel::Medium)
+    }
+
+    #[quickcheck]
+    fn random_chess960_games_consistency_high(start_id: u16, move_ids: Vec<u16>) -> bool {
+        random_chess960_games_consistency(start_id, move_ids, CompressionLevel::High)
+    }
+
+    #[test]
+    fn chess960_castling_test() {
+        let fen: Fen = "1r2k1r1/pppppppp/8/8/8/8/PPPPPPPP/1R2K1R1 w KQkq - 0 1"
+            .parse()
+            .unwrap();
+        let start: Chess = fen.into_position(CastlingMode::Chess960).unwrap();
+
+        for level in [CompressionLevel::Low, CompressionLevel::Medium] {
+            let mut pos = start.clone();
+            let mut encoder = Encoder::with_start_position(level, start.clone());
+            for uci in ["e1g1", "e8b8"] {
+                let m = uci.parse::<UciMove>().unwrap().to_move(&pos).unwrap();
+                assert!(m.is_castle());
+                pos.play_unchecked(m);
+                encoder.encode_move(m).unwrap();
+            }
+            let bytes = encoder.finish().into_bytes();
+            let restored = EncodedGame::from_bytes(&bytes).unwrap();
+            assert_eq!(
+                Decoder::new(&restored).into_uci_string().unwrap(),
+                "e1g1 e8b8"
+            );
+            assert_eq!(
+                Decoder::new(&restored).into_pgn_string().unwrap(),
+                "1. O-O O-O-O"
+            );
+        }
+    }
+
+    #[test]
+    fn chess960_standard_array_test() {
+        let start = chess960_start_position(518);
+        assert_eq!(start, Chess::new());
+
+        let encoded = Encoder::with_start_position(CompressionLevel::Low, start).finish();
+        let bytes = encoded.into_bytes();
+        let restored = EncodedGame::from_bytes(&bytes).unwrap();
+        assert_eq!(restored.castling_mode(), CastlingMode::Chess960);
+    }
+
+    fn variant_uci_round_trip(variant: Variant, fen: &str, ucis: &[&str], level: CompressionLevel) {
+        let fen: Fen = fen.parse().unwrap();
+        let start =
+            VariantPosition::from_setup(variant, fen.into_setup(), CastlingMode::Standard).unwrap();
+
+        let mut pos = start.clone();
+        let mut encoder = Encoder::with_start_position(level, start);
+        for uci in ucis {
+            let m = uci.parse::<UciMove>().unwrap().to_move(&pos).unwrap();
+            pos.play_unchecked(m);
+            encoder.encode_move(m).unwrap();
+        }
+        let bytes = encoder.finish().into_bytes();
+        let restored = EncodedGame::from_bytes(&bytes).unwrap();
+        assert_eq!(restored.variant(), variant);
+        assert_eq!(
+            Decoder::new(&restored).into_uci_string().unwrap(),
+            ucis.join(" ")
+        );
+    }
+
+    #[test]
+    fn crazyhouse_drops_test() {
+        for level in [CompressionLevel::Low, CompressionLevel::Medium] {
+            variant_uci_round_trip(
+                Variant::Crazyhouse,
+                "rnbqkb1r/pppppppp/8/8/8/8/PPPPPPPP/RNB1KBNR[Qn] w KQkq - 0 1",
+                &["Q@a4", "N@f6", "e2e4", "f6e4", "a4e4", "P@d5"],
+                level,
+            );
+        }
+    }
+
+    #[test]
+    fn antichess_king_promotion_test() {
+        for level in [CompressionLevel::Low, CompressionLevel::Medium] {
+            variant_uci_round_trip(
+                Variant::Antichess,
+                "8/3P4/8/8/8/8/3p4/n7 w - - 0 1",
+                &["d7d8k", "d2d1k", "d8e8", "a1b3"],
+                level,
+            );
+        }
+    }
+
+    #[test]
+    fn variant_pgn_test() {
+        let start = VariantPosition::new(Variant::Crazyhouse);
+        let mut pos = start.clone();
+        let mut encoder = Encoder::with_start_position(CompressionLevel::High, start);
+        for uci in [
+            "e2e4", "d7d5", "e4d5", "d8d5", "b1c3", "d5a5", "d2d4", "P@e4",
+        ] {
+            let m = uci.parse::<UciMove>().unwrap().to_move(&pos).unwrap();
+            pos.play_unchecked(m);
+            encoder.encode_move(m).unwrap();
+        }
+        let encoded = encoder.finish();
+        assert_eq!(encoded.compression_level, CompressionLevel::Medium);
+
+        let bytes = encoded.into_bytes();
+        let restored = EncodedGame::from_bytes(&bytes).unwrap();
+        assert_eq!(restored.variant(), Variant::Crazyhouse);
+        let pgn = Decoder::new(&restored).into_pgn_string().unwrap();
+        assert_eq!(pgn, "1. e4 d5 2. exd5 Qxd5 3. Nc3 Qa5 4. d4 @e4");
+    }
+
+    #[test]
+    fn start_position_pgn_test() {
+        let fen: Fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3"
+            .parse()
+            .unwrap();
+        let start: Chess = fen.into_position(CastlingMode::Standard).unwrap();
+
+        let mut pos = start.clone();
+        let mut encoder = Encoder::with_start_position(CompressionLevel::High, start);
+        for uci in ["f1b5", "a7a6", "b5a4"] {
+            let m = uci.parse::<UciMove>().unwrap().to_move(&pos).unwrap();
+            pos.play_unchecked(m);
+            encoder.encode_move(m).unwrap();
+        }
+        let encoded = encoder.finish();
+        assert_eq!(encoded.compression_level, CompressionLevel::Medium);
+
+        let bytes = encoded.into_bytes();
+        let restored = EncodedGame::from_bytes(&bytes).unwrap();
+        let pgn = Decoder::new(&restored).into_pgn_string().unwrap();
+        assert_eq!(pgn, "3. Bb5 a6 4. Ba4");
+
+        let restored = restored.recompress(CompressionLevel::Low).unwrap();
+        let bytes = restored.into_bytes();
+        let restored = EncodedGame::from_bytes(&bytes).unwrap();
+        assert_eq!(restored.compression_level, CompressionLevel::Low);
+        let pgn = Decoder::new(&restored).into_pgn_string().unwrap();
+        assert_eq!(pgn, "3. Bb5 a6 4. Ba4");
+    }
+
+    #[test]
+    fn start_position_black_to_move_pgn_test() {
+        let fen: Fen = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"
+            .parse()
+            .unwrap();
+        let start: Chess = fen.into_position(CastlingMode::Standard).unwrap();
+
+        let mut pos = start.clone();
+        let mut encoder = Encoder::with_start_position(CompressionLevel::Low, start);
+        for uci in ["c7c5", "g1f3"] {
+            let m = uci.parse::<UciMove>().unwrap().to_move(&pos).unwrap();
+            pos.play_unchecked(m);
+            encoder.encode_move(m).unwrap();
+        }
+        let bytes = encoder.finish().into_bytes();
+        let restored = EncodedGame::from_bytes(&bytes).unwrap();
+        let pgn = Decoder::new(&restored).into_pgn_string().unwrap();
+        assert_eq!(pgn, "1... c5 2. Nf3");
+    }
+
+    #[test]
+    fn header_version_test() {
+        let start = VariantPosition::new(Variant::Atomic);
+        let mut encoder = Encoder::with_start_position(CompressionLevel::Low, start.clone());
+        let m = "e2e4".parse::<UciMove>().unwrap().to_move(&start).unwrap();
+        encoder.encode_move(m).unwrap();
+        let bytes = encoder.finish().into_bytes();
+        assert_eq!(bytes[bytes.len() - 1], 0b1100_0000 | FORMAT_VERSION);
+        assert_eq!(
+            bytes[bytes.len() - 2],
+            FLAG_START_POSITION | 2 << FLAG_VARIANT_SHIFT
+        );
+        assert!(EncodedGame::from_bytes(&bytes).is_ok());
+
+        let mut newer = bytes.clone();
+        *newer.last_mut().unwrap() = 0b1100_0000 | (FORMAT_VERSION + 1);
+        assert_eq!(
+            EncodedGame::from_bytes(&newer).unwrap_err(),
+            EncodedGameConstructionError::UnsupportedVersion
+        );
+
+        let mut annotated = bytes.clone();
+        let flags_index = annotated.len() - 2;
+        annotated[flags_index] |= FLAG_ANNOTATIONS;
+        assert_eq!(
+            EncodedGame::from_owned_bytes(annotated).unwrap_err(),
+            EncodedGameConstructionError::UnsupportedVersion
+        );
+
+        let mut unversioned = bytes;
+        *unversioned.last_mut().unwrap() = 0b1100_0000;
+        assert_eq!(
+            EncodedGame::from_bytes(&unversioned).unwrap_err(),
+            EncodedGameConstructionError::InvalidData
+        );
+    }
+
+    #[quickcheck]
+    fn text_round_trip(move_ids: Vec<u16>) -> bool {
+        let mut pos = Chess::default();
+        let mut encoder = Encoder::new(CompressionLevel::Low);
+        for m in move_ids {
+            let legal_moves = pos.legal_moves();
+            if legal_moves.is_empty() {
+                break;
+            }
+            let choice = legal_moves[m as usize % legal_moves.len()];
+            pos.play_unchecked(choice);
+            encoder.encode_move(choice).unwrap();
+        }
+        let encoded = encoder.finish();
+        let uci = Decoder::new(&encoded).into_uci_string().unwrap();
+        let pgn = Decoder::new(&encoded).into_pgn_string().unwrap();
+
+        for level in [
+            CompressionLevel::Low,
+            CompressionLevel::Medium,
+            CompressionLevel::High,
+        ] {
+            let from_uci = EncodedGame::from_uci_str(&uci, level).unwrap();
+            assert_eq!(Decoder::new(&from_uci).into_uci_string().unwrap(), uci);
+            let from_san = EncodedGame::from_san_movetext(&pgn, level).unwrap();
+            assert_eq!(Decoder::new(&from_san).into_pgn_string().unwrap(), pgn);
+        }
+        true
+    }
+
+    #[test]
+    fn text_encode_errors_test() {
+        let err =
+            EncodedGame::from_uci_str("e2e4 e7e5 e1e2 e8e8", CompressionLevel::Medium).unwrap_err();
+        assert_eq!(err.ply(), Some(4));
+        assert_eq!(err.token(), Some("e8e8"));
+        assert_eq!(err.kind(), EncodeErrorKind::IllegalMove);
+        assert_eq!(
+            err.reason(),
+            &EncodeErrorReason::IllegalUci("e8e8".parse().unwrap())
+        );
+        assert_eq!(
+            err.to_string(),
+            "Failed to encode move e8e8 at ply 4: illegal move e8e8"
+        );
+
+        let err = EncodedGame::from_uci_str("e2e4 x", CompressionLevel::Low).unwrap_err();
+        assert_eq!(err.kind(), EncodeErrorKind::InvalidToken);
+        assert_eq!(err.reason(), &EncodeErrorReason::InvalidUci);
+        assert_eq!(err.ply(), Some(2));
+        assert_eq!(err.token(), Some("x"));
+
+        let err =
+            EncodedGame::from_san_movetext("1. e4 e5 2. Ke3", CompressionLevel::High).unwrap_err();
+        assert_eq!(err.ply(), Some(3));
+        assert_eq!(err.token(), Some("Ke3"));
+        assert_eq!(
+            err.reason(),
+            &EncodeErrorReason::IllegalSan("Ke3".parse().unwrap(), SanError::IllegalSan)
+        );
+
+        let err = EncodedGame::from_san_movetext(
+            "1. a4 a5 2. h4 h5 3. Ra3 Ra6 4. Rhh3 Rhh6 5. Re3",
+            CompressionLevel::Low,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.reason(),
+            &EncodeErrorReason::IllegalSan("Re3".parse().unwrap(), SanError::AmbiguousSan)
+        );
+        assert_eq!(
+            err.to_string(),
+            "Failed to encode move Re3 at ply 9: ambiguous move Re3"
+        );
+
+        let encoded = EncodedGame::from_san_movetext(
+            "1.e4 c5 $1 2. Nf3! d6 3. d4 cxd4 4. Nxd4+? 1-0",
+            CompressionLevel::Medium,
+        )
+        .unwrap();
+        let uci = Decoder::new(&encoded).into_uci_string().unwrap();
+        assert_eq!(uci, "e2e4 c7c5 g1f3 d7d6 d2d4 c5d4 f3d4");
+
+        let start = VariantPosition::new(Variant::Crazyhouse);
+        let mut encoder = Encoder::with_start_position(CompressionLevel::Low, start);
+        for san in ["e4", "d5", "exd5", "Qxd5", "Nc3", "Qa5", "d4", "@e4"] {
+            encoder.encode_san(san).unwrap();
+        }
+        let err = encoder.encode_uci("P@e4").unwrap_err();
+        assert_eq!(err.ply(), Some(9));
+        let uci = Decoder::new(&encoder.finish()).into_uci_string().unwrap();
+        assert_eq!(uci, "e2e4 d7d5 e4d5 d8d5 b1c3 d5a5 d2d4 P@e4");
+    }
+
+    #[test]
+    fn decode_errors_test() {
+        let encoded = EncodedGame::from_uci_str("e2e4 e7e5 g1f3", CompressionLevel::Low).unwrap();
+        let mut bytes = encoded.into_bytes();
+        // turn the second move into e7e4, which is illegal
+        bytes[3] = 28;
+        let restored = EncodedGame::from_bytes(&bytes).unwrap();
+        let errors: Vec<DecodeError> = Decoder::new(&restored)
+            .into_iter_moves()
+            .skip(1)
+            .take(2)
+            .map(|m| m.unwrap_err())
+            .collect();
+        assert_eq!(errors[0], errors[1]);
+        assert_eq!(errors[0].kind(), DecodeErrorKind::IllegalMove);
+        assert_eq!(errors[0].ply(), 2);
+        assert_eq!(errors[0].compression_level(), CompressionLevel::Low);
+
+        // restore e7e5 and drop the second byte of the last move
+        bytes[3] = 36;
+        bytes.remove(bytes.len() - 2);
+        let restored = EncodedGame::from_bytes(&bytes).unwrap();
+        let err = Decoder::new(&restored).into_uci_string().unwrap_err();
+        assert_eq!(err.kind(), DecodeErrorKind::TruncatedData);
+        assert_eq!(err.ply(), 3);
+
+        let encoded = EncodedGame::from_uci_str("e2e4 e7e5", CompressionLevel::Medium).unwrap();
+        let mut bytes = encoded.into_bytes();
+        bytes.insert(0, 0xFF);
+        let restored = EncodedGame::from_bytes(&bytes).unwrap();
+        let err = Decoder::new(&restored).into_uci_string().unwrap_err();
+        assert_eq!(err.compression_level(), CompressionLevel::Medium);
+    }
+
     #[quickcheck]
     fn no_decode_panics(data: Vec<u8>) -> bool {
-        match EncodedGame::from_bytes(&data) {
-            Ok(encoded) => {
-                let mut decoder = Decoder::new(&encoded);
-                while let Some(m) = decoder.next_move() {
-                    assert!(m.is_ok() || m.is_err());
-                    if m.is_err() {
-                        break;
-                    }
+        if let Ok(encoded) = EncodedGame::from_bytes(&data) {
+            let mut decoder = Decoder::new(&encoded);
+            while let Some(m) = decoder.next_move() {
+                assert!(m.is_ok() || m.is_err());
+                if m.is_err() {
+                    break;
                 }
             }
-            Err(_) => {}
         }
         true
     }
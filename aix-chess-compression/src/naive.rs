@@ -1,21 +1,51 @@
 use std::borrow::Cow;
 
 use crate::{
-    CompressionLevel, Decode, DecodeError, DecodeResult, Encode, EncodeError, EncodedGame,
-    EncodedGameContent,
+    CompressionLevel, Decode, DecodeError, DecodeErrorKind, DecodeResult, Encode, EncodeError,
+    EncodedGame, EncodedGameContent, Keyframes,
 };
-use shakmaty::{Chess, Move, Position, Square, uci::UciMove};
+use shakmaty::{Move, Position, Role, Square, uci::UciMove, variant::VariantPosition};
 
+const DROP_MARKER: u8 = 0b1000_0000;
+const KING_PROMOTION_MARKER: u8 = 0b0100_0000;
+
+/// Encodes every move as two bytes: the from square and flags in the first, the to square and the
+/// promotion piece in the second. The upper bits of the second byte are unused by regular moves,
+/// so they mark the moves that only occur in variants:
+/// - `0b10`: a drop; the first byte holds the dropped role instead of a from square.
+/// - `0b01` (without the promotion flag): a promotion to a king (Antichess).
 pub struct NaiveEncoder {
     result: Vec<u8>,
+    chess: VariantPosition,
+    start_position: Option<VariantPosition>,
 }
 
 impl NaiveEncoder {
     pub fn new() -> Self {
         Self {
             result: Vec::with_capacity(40),
+            chess: VariantPosition::default(),
+            start_position: None,
         }
     }
+
+    pub fn with_start_position(pos: VariantPosition) -> Self {
+        Self {
+            result: Vec::with_capacity(40),
+            chess: pos.clone(),
+            start_position: Some(pos),
+        }
+    }
+
+    /// Returns the position after the moves encoded so far.
+    pub(crate) fn position(&self) -> &VariantPosition {
+        &self.chess
+    }
+
+    /// Returns the number of moves encoded so far.
+    pub(crate) fn plies(&self) -> usize {
+        self.result.len() / 2
+    }
 }
 
 impl Default for NaiveEncoder {
@@ -26,12 +56,14 @@ impl Default for NaiveEncoder {
 
 impl Encode for NaiveEncoder {
     fn encode_move(&mut self, m: Move) -> Result<(), EncodeError> {
-        let from: u8 = m
-            .from()
-            .ok_or(EncodeError {
-                inner: Box::new("missing from square in NaiveEncoder::encode_move"),
-            })?
-            .into();
+        if let Move::Put { role, to } = m {
+            self.result.push(role as u8);
+            self.result.push(u8::from(to) | DROP_MARKER);
+            self.chess.play_unchecked(m);
+            return Ok(());
+        }
+
+        let from: u8 = m.from().ok_or(EncodeError::unsupported(m))?.into();
         let to: u8 = m.to().into();
 
         let mut b1 = from;
@@ -41,19 +73,22 @@ impl Encode for NaiveEncoder {
             b1 |= 0b1000_0000;
         }
 
-        if let Some(promotion) = m.promotion() {
+        if m.promotion() == Some(Role::King) {
+            b2 |= KING_PROMOTION_MARKER;
+        } else if let Some(promotion) = m.promotion() {
             b1 |= 0b0100_0000;
             b2 |= match promotion {
-                shakmaty::Role::Queen => 0b0000_0000,
-                shakmaty::Role::Rook => 0b0100_0000,
-                shakmaty::Role::Bishop => 0b1000_0000,
-                shakmaty::Role::Knight => 0b1100_0000,
+                Role::Queen => 0b0000_0000,
+                Role::Rook => 0b0100_0000,
+                Role::Bishop => 0b1000_0000,
+                Role::Knight => 0b1100_0000,
                 _ => panic!("Invalid promotion piece"),
             };
         }
 
         self.result.push(b1);
         self.result.push(b2);
+        self.chess.play_unchecked(m);
 
         Ok(())
     }
@@ -62,6 +97,8 @@ impl Encode for NaiveEncoder {
         EncodedGame {
             content: EncodedGameContent::Bytes(Cow::Owned(self.result)),
             compression_level: CompressionLevel::Low,
+            start_position: self.start_position,
+            keyframes: None,
         }
     }
 }
@@ -69,21 +106,53 @@ impl Encode for NaiveEncoder {
 pub struct NaiveDecoder<'a> {
     encoded: &'a [u8],
     index: usize,
-    chess: Chess,
+    chess: VariantPosition,
+    pub(crate) start_position: Option<&'a VariantPosition>,
+    pub(crate) keyframes: Option<&'a Keyframes>,
 }
 
 impl<'a> NaiveDecoder<'a> {
-    pub(crate) fn new(encoded: &'a EncodedGameContent<'a>) -> Self {
+    pub(crate) fn new(
+        encoded: &'a EncodedGameContent<'a>,
+        start_position: Option<&'a VariantPosition>,
+        keyframes: Option<&'a Keyframes>,
+    ) -> Self {
         if let EncodedGameContent::Bytes(enc) = encoded {
             Self {
                 encoded: enc,
                 index: 0,
-                chess: Chess::new(),
+                chess: start_position.cloned().unwrap_or_default(),
+                start_position,
+                keyframes,
             }
         } else {
             panic!("NaiveDecoder only accepts EncodedGameRef::Bytes");
         }
     }
+
+    /// Returns the byte offset of the next move.
+    pub(crate) fn offset(&self) -> usize {
+        self.index
+    }
+
+    /// Continues decoding from byte `offset`, where the position is `pos` after ply `ply`.
+    pub(crate) fn jump(
+        &mut self,
+        offset: usize,
+        ply: usize,
+        pos: &VariantPosition,
+    ) -> DecodeResult<()> {
+        if offset > self.encoded.len() || offset != 2 * ply {
+            return Err(self.error(DecodeErrorKind::InvalidData, ply));
+        }
+        self.index = offset;
+        self.chess = pos.clone();
+        Ok(())
+    }
+
+    fn error(&self, kind: DecodeErrorKind, ply: usize) -> DecodeError {
+        DecodeError::new(kind, ply, CompressionLevel::Low)
+    }
 }
 
 impl Decode for NaiveDecoder<'_> {
@@ -92,8 +161,10 @@ impl Decode for NaiveDecoder<'_> {
             return None;
         }
 
+        // every move takes two bytes
+        let ply = self.index / 2 + 1;
         if self.index + 1 == self.encoded.len() {
-            return Some(Err(DecodeError {}));
+            return Some(Err(self.error(DecodeErrorKind::TruncatedData, ply)));
         }
 
         let b1 = self.encoded[self.index];
@@ -102,42 +173,55 @@ impl Decode for NaiveDecoder<'_> {
         let from = unsafe { Square::new_unchecked(u32::from(b1 & 0b0011_1111)) };
         let to = unsafe { Square::new_unchecked(u32::from(b2 & 0b0011_1111)) };
 
-        let promotion = if b1 & 0b0100_0000 != 0 {
-            match b2 & 0b1100_0000 {
-                0b0000_0000 => Some(shakmaty::Role::Queen),
-                0b0100_0000 => Some(shakmaty::Role::Rook),
-                0b1000_0000 => Some(shakmaty::Role::Bishop),
-                0b1100_0000 => Some(shakmaty::Role::Knight),
-                _ => unreachable!(),
+        let uci = if b2 & 0b1100_0000 == DROP_MARKER && b1 & 0b0100_0000 == 0 {
+            match Role::try_from(b1) {
+                Ok(role) => UciMove::Put { role, to },
+                Err(_) => return Some(Err(self.error(DecodeErrorKind::InvalidIndex, ply))),
             }
         } else {
-            None
-        };
-
-        let uci = UciMove::Normal {
-            from,
-            to,
-            promotion,
+            UciMove::Normal {
+                from,
+                to,
+                promotion: naive_promotion(b1, b2),
+            }
         };
-        let r = uci.to_move(&self.chess).map_err(|_| DecodeError {});
-        Some(r.map(|m| {
+        let r = uci
+            .to_move(&self.chess)
+            .map_err(|_| self.error(DecodeErrorKind::IllegalMove, ply));
+        Some(r.inspect(|&m| {
             self.chess.play_unchecked(m); // uci.to_move already checks legality
             self.index += 2;
-            m
         }))
     }
 
-    fn next_move_and_position(&mut self) -> Option<DecodeResult<(Move, &Chess)>> {
+    fn next_move_and_position(&mut self) -> Option<DecodeResult<(Move, &VariantPosition)>> {
         let maybe_next = self.next_move();
         maybe_next.map(|next| next.map(|m| (m, &self.chess)))
     }
 
-    fn next_position(&mut self) -> Option<DecodeResult<&Chess>> {
+    fn next_position(&mut self) -> Option<DecodeResult<&VariantPosition>> {
         let maybe_next = self.next_move();
         maybe_next.map(|next| next.map(|_| &self.chess))
     }
 }
 
+/// Decodes the promotion piece of a regular (non-drop) move.
+fn naive_promotion(b1: u8, b2: u8) -> Option<Role> {
+    if b1 & 0b0100_0000 != 0 {
+        match b2 & 0b1100_0000 {
+            0b0000_0000 => Some(Role::Queen),
+            0b0100_0000 => Some(Role::Rook),
+            0b1000_0000 => Some(Role::Bishop),
+            0b1100_0000 => Some(Role::Knight),
+            _ => unreachable!(),
+        }
+    } else if b2 & 0b1100_0000 == KING_PROMOTION_MARKER {
+        Some(Role::King)
+    } else {
+        None
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{CompressionLevel, Decoder, EncodedGame};
@@ -0,0 +1,575 @@
+use std::{fmt, io};
+
+use shakmaty::{
+    CastlingMode, Color, EnPassantMode, Position,
+    fen::Fen,
+    san::{San, SanPlus, Suffix},
+    variant::Variant,
+};
+
+use crate::{Annotation, Annotations, Decode, DecodeError, DecodeErrorKind, Decoder, EncodedGame};
+
+/// Maximum length of a line of PGN movetext.
+const LINE_WIDTH: usize = 80;
+
+/// Tags of the Seven Tag Roster, in the order they are written, with their default values.
+const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+
+/// Largest number of moves to mate that the evals representation holds, see `eval_to_string`.
+const MAX_MATE: i16 = 512;
+
+/// Writes an encoded game as PGN, including its tags, `[%eval]` and `[%clk]` comments and
+/// annotations (NAGs, comments and variations).
+///
+/// The tags of the Seven Tag Roster are always written (with `?` placeholders if missing),
+/// followed by the other tags in ASCII order. The `FEN`, `SetUp` and `Variant` tags are derived
+/// from the game itself. The movetext is wrapped at 80 columns and ends with the `Result` tag.
+///
+/// Clocks and evals use the representation of the `clocks_white`, `clocks_black` and `evals`
+/// columns: clock times in seconds after each move of one side, and evaluations after each ply
+/// (see `eval_to_centipawns` and `eval_to_mate` in the DuckDB extension). Clock times in
+/// milliseconds, as in the `clocks_white_ms` and `clocks_black_ms` columns, replace them.
+pub struct PgnWriter<'a> {
+    game: &'a EncodedGame<'a>,
+    tags: Vec<(String, String)>,
+    clocks_white: &'a [u16],
+    clocks_black: &'a [u16],
+    clocks_white_ms: &'a [Option<u64>],
+    clocks_black_ms: &'a [Option<u64>],
+    evals: &'a [i16],
+    annotations: Option<&'a Annotations>,
+}
+
+impl<'a> PgnWriter<'a> {
+    /// Creates a writer for `game` without tags, clocks, evals or annotations.
+    #[must_use]
+    pub fn new(game: &'a EncodedGame<'a>) -> Self {
+        PgnWriter {
+            game,
+            tags: vec![],
+            clocks_white: &[],
+            clocks_black: &[],
+            clocks_white_ms: &[],
+            clocks_black_ms: &[],
+            evals: &[],
+            annotations: None,
+        }
+    }
+
+    /// Adds tags (e.g. `("White", "Magnus")`). A later tag with the same name replaces an
+    /// earlier one.
+    #[must_use]
+    pub fn with_tags<K, V>(mut self, tags: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        for (key, value) in tags {
+            let key = key.into();
+            let value = value.into();
+            match self.tags.iter_mut().find(|(k, _)| *k == key) {
+                Some(tag) => tag.1 = value,
+                None => self.tags.push((key, value)),
+            }
+        }
+        self
+    }
+
+    /// Sets the clock times (in seconds) after each move of white and black.
+    #[must_use]
+    pub fn with_clocks(mut self, white: &'a [u16], black: &'a [u16]) -> Self {
+        self.clocks_white = white;
+        self.clocks_black = black;
+        self
+    }
+
+    /// Sets the clock times in milliseconds after each move of white and black, `None` for moves
+    /// without a clock. A side with clock times in milliseconds has its clock times in seconds
+    /// ignored.
+    #[must_use]
+    pub fn with_clocks_ms(mut self, white: &'a [Option<u64>], black: &'a [Option<u64>]) -> Self {
+        self.clocks_white_ms = white;
+        self.clocks_black_ms = black;
+        self
+    }
+
+    /// Sets the evaluations after each ply.
+    #[must_use]
+    pub fn with_evals(mut self, evals: &'a [i16]) -> Self {
+        self.evals = evals;
+        self
+    }
+
+    /// Sets the annotations of the game, written after the `[%eval]` and `[%clk]` comments.
+    #[must_use]
+    pub fn with_annotations(mut self, annotations: &'a Annotations) -> Self {
+        self.annotations = Some(annotations);
+        self
+    }
+
+    /// Decodes the game and returns its movetext on a single line, without tags and result.
+    pub fn to_movetext_string(&self) -> Result<String, DecodeError> {
+        let mut s = String::new();
+        self.push_movetext(&mut |token| {
+            if !s.is_empty() {
+                s.push(' ');
+            }
+            s.push_str(token);
+        })?;
+        Ok(s)
+    }
+
+    /// Decodes the game and writes it as PGN to `out`, followed by an empty line.
+    pub fn write<W: io::Write>(&self, out: &mut W) -> Result<(), PgnWriteError> {
+        let pgn = self.to_pgn_string()?;
+        out.write_all(pgn.as_bytes())?;
+        Ok(())
+    }
+
+    /// Decodes the game and returns it as PGN, followed by an empty line.
+    pub fn to_pgn_string(&self) -> Result<String, DecodeError> {
+        let mut s = String::new();
+        let result = self.tag("Result").unwrap_or("*").to_owned();
+
+        for (key, default) in SEVEN_TAG_ROSTER {
+            push_tag(&mut s, key, self.tag(key).unwrap_or(default));
+        }
+        let mut other_tags: Vec<(&str, &str)> = self
+            .tags
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .filter(|(k, _)| {
+                !SEVEN_TAG_ROSTER.iter().any(|(tag, _)| tag == k)
+                    && !matches!(*k, "FEN" | "SetUp" | "Variant")
+            })
+            .collect();
+        let start = self.game.start_position();
+        let fen;
+        if self.game.start_position.is_some() {
+            fen = Fen::from_position(&start, EnPassantMode::Legal).to_string();
+            other_tags.push(("FEN", &fen));
+            other_tags.push(("SetUp", "1"));
+        }
+        if let Some(variant) = variant_tag(self.game.variant(), self.game.castling_mode()) {
+            other_tags.push(("Variant", variant));
+        }
+        other_tags.sort_by_key(|(k, _)| *k);
+        for (key, value) in other_tags {
+            push_tag(&mut s, key, value);
+        }
+        s.push('\n');
+
+        let mut line = MovetextLine::new(&mut s);
+        self.push_movetext(&mut |token| line.push(token))?;
+        line.push(&result);
+        s.push_str("\n\n");
+
+        Ok(s)
+    }
+
+    /// Pushes the movetext tokens of the game, without the result.
+    fn push_movetext(&self, push: &mut dyn FnMut(&str)) -> Result<(), DecodeError> {
+        let (mut white_moves, mut black_moves) = (0, 0);
+        let mut comment = |ply: usize, turn: Color| {
+            let (moves, clocks, clocks_ms) = if turn == Color::White {
+                (&mut white_moves, self.clocks_white, self.clocks_white_ms)
+            } else {
+                (&mut black_moves, self.clocks_black, self.clocks_black_ms)
+            };
+            *moves += 1;
+            let clock = if clocks_ms.is_empty() {
+                clocks
+                    .get(*moves - 1)
+                    .map(|&seconds| clock_to_string(u64::from(seconds) * 1000))
+            } else {
+                clocks_ms
+                    .get(*moves - 1)
+                    .copied()
+                    .flatten()
+                    .map(clock_to_string)
+            };
+            let mut tokens = vec![];
+            if let Some(&eval) = self.evals.get(ply) {
+                tokens.push("[%eval".to_owned());
+                tokens.push(format!("{}]", eval_to_string(eval)));
+            }
+            if let Some(clock) = clock {
+                tokens.push("[%clk".to_owned());
+                tokens.push(format!("{clock}]"));
+            }
+            tokens
+        };
+        push_moves(self.game, self.annotations, push, &mut comment)
+    }
+
+    fn tag(&self, key: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Pushes the movetext tokens of `game` and its annotations, without the result.
+/// `comment(ply, turn)` returns the tokens of a comment to write right after the move at the
+/// 0-based `ply`, played by `turn`, before its other annotations.
+fn push_moves(
+    game: &EncodedGame,
+    annotations: Option<&Annotations>,
+    push: &mut dyn FnMut(&str),
+    comment: &mut dyn FnMut(usize, Color) -> Vec<String>,
+) -> Result<(), DecodeError> {
+    let empty = Annotations::new();
+    let annotations = annotations.unwrap_or(&empty);
+    let mut pos = game.start_position();
+    let mut after_comment = push_annotations(game, annotations, 0, &[], push)?;
+    for (ply, r) in Decoder::new(game)
+        .into_iter_moves_and_positions()
+        .enumerate()
+    {
+        let (m, next_pos) = r?;
+        let san = San::from_move(&pos, m);
+        let suffix = Suffix::from_position(&next_pos);
+        let san_plus = SanPlus { san, suffix };
+        // keep move numbers on the same line as their move
+        if pos.turn() == Color::White {
+            push(&format!("{}. {san_plus}", pos.fullmoves()));
+        } else if ply == 0 || after_comment {
+            push(&format!("{}... {san_plus}", pos.fullmoves()));
+        } else {
+            push(&san_plus.to_string());
+        }
+
+        let comment = comment(ply, pos.turn());
+        let anchor = u16::try_from(ply + 1).unwrap_or(u16::MAX);
+        after_comment = push_annotations(game, annotations, anchor, &comment, push)?;
+        pos = next_pos;
+    }
+
+    Ok(())
+}
+
+/// Pushes the annotations at `ply`: NAGs, then a comment with `comment_tokens` (if any) and the
+/// other comments and variations in order. Returns whether a comment or variation was written.
+fn push_annotations(
+    game: &EncodedGame,
+    annotations: &Annotations,
+    ply: u16,
+    comment_tokens: &[String],
+    push: &mut dyn FnMut(&str),
+) -> Result<bool, DecodeError> {
+    for annotation in annotations.at(ply) {
+        if let Annotation::Nag(nag) = annotation {
+            push(&format!("${nag}"));
+        }
+    }
+
+    let mut written = !comment_tokens.is_empty();
+    if written {
+        push("{");
+        comment_tokens.iter().for_each(|t| push(t));
+        push("}");
+    }
+
+    for annotation in annotations.at(ply) {
+        match annotation {
+            Annotation::Nag(_) => continue,
+            Annotation::Comment(text) => {
+                // a closing brace would end the comment early, so drop it like python-chess
+                push("{");
+                text.replace('}', "")
+                    .split_whitespace()
+                    .for_each(&mut *push);
+                push("}");
+            }
+            Annotation::Variation(bytes, nested) => {
+                let variation = EncodedGame::from_bytes(bytes).map_err(|_| {
+                    DecodeError::new(
+                        DecodeErrorKind::InvalidData,
+                        ply.into(),
+                        game.compression_level,
+                    )
+                })?;
+                push("(");
+                push_moves(&variation, Some(nested), push, &mut |_, _| vec![])?;
+                push(")");
+            }
+        }
+        written = true;
+    }
+
+    Ok(written)
+}
+
+/// Builds movetext out of tokens, wrapping lines so that they do not exceed `LINE_WIDTH`.
+struct MovetextLine<'s> {
+    out: &'s mut String,
+    len: usize,
+}
+
+impl<'s> MovetextLine<'s> {
+    fn new(out: &'s mut String) -> Self {
+        MovetextLine { out, len: 0 }
+    }
+
+    fn push(&mut self, token: &str) {
+        if self.len > 0 && self.len + 1 + token.len() > LINE_WIDTH {
+            self.out.push('\n');
+            self.len = 0;
+        }
+        if self.len > 0 {
+            self.out.push(' ');
+            self.len += 1;
+        }
+        self.out.push_str(token);
+        self.len += token.len();
+    }
+}
+
+fn push_tag(s: &mut String, key: &str, value: &str) {
+    s.push('[');
+    s.push_str(key);
+    s.push_str(" \"");
+    for c in value.chars() {
+        if c == '"' || c == '\\' {
+            s.push('\\');
+        }
+        s.push(c);
+    }
+    s.push_str("\"]\n");
+}
+
+/// Returns the value of the `Variant` tag for a game, `None` for standard chess.
+fn variant_tag(variant: Variant, castling_mode: CastlingMode) -> Option<&'static str> {
+    Some(match variant {
+        Variant::Chess if castling_mode == CastlingMode::Chess960 => "Chess960",
+        Variant::Chess => return None,
+        Variant::Crazyhouse => "Crazyhouse",
+        Variant::Atomic => "Atomic",
+        Variant::KingOfTheHill => "King of the Hill",
+        Variant::ThreeCheck => "Three-check",
+        Variant::Antichess => "Antichess",
+        Variant::Horde => "Horde",
+        Variant::RacingKings => "Racing Kings",
+    })
+}
+
+/// Formats an eval as in a `[%eval]` comment: pawns with two decimals, or `#n` for mate in `n`
+/// (negative if black mates). The largest values represent a mate for white (`i16::MAX` is mate
+/// in 1), the smallest values a mate for black (`i16::MIN` is mate in 1).
+fn eval_to_string(eval: i16) -> String {
+    if eval > i16::MAX - MAX_MATE {
+        format!("#{}", i16::MAX - eval + 1)
+    } else if eval < i16::MIN + MAX_MATE {
+        format!("#-{}", i32::from(eval) - i32::from(i16::MIN) + 1)
+    } else {
+        let sign = if eval < 0 { "-" } else { "" };
+        let cp = eval.unsigned_abs();
+        format!("{sign}{}.{:02}", cp / 100, cp % 100)
+    }
+}
+
+/// Formats a clock time in milliseconds as in a `[%clk]` comment: `h:mm:ss`, followed by the
+/// fraction of a second if any, e.g. `0:00:05.3`.
+fn clock_to_string(ms: u64) -> String {
+    let seconds = ms / 1000;
+    let mut s = format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    );
+    if !ms.is_multiple_of(1000) {
+        s.push_str(format!(".{:03}", ms % 1000).trim_end_matches('0'));
+    }
+    s
+}
+
+/// Error type for writing PGN.
+#[derive(Debug)]
+pub enum PgnWriteError {
+    Decode(DecodeError),
+    Io(io::Error),
+}
+
+impl std::error::Error for PgnWriteError {}
+
+impl fmt::Display for PgnWriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PgnWriteError::Decode(e) => write!(f, "Failed to write PGN: {e}"),
+            PgnWriteError::Io(e) => write!(f, "Failed to write PGN: {e}"),
+        }
+    }
+}
+
+impl From<DecodeError> for PgnWriteError {
+    fn from(e: DecodeError) -> Self {
+        PgnWriteError::Decode(e)
+    }
+}
+
+impl From<io::Error> for PgnWriteError {
+    fn from(e: io::Error) -> Self {
+        PgnWriteError::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shakmaty::{CastlingMode, Chess, fen::Fen};
+
+    use super::{PgnWriter, eval_to_string};
+    use crate::{Annotation, Annotations, CompressionLevel, Decoder, Encode, EncodedGame, Encoder};
+
+    #[test]
+    fn write_test() {
+        let game = EncodedGame::from_uci_str(
+            "e2e4 e7e5 g1f3 b8c6 f1b5 a7a6 b5a4 g8f6 e1g1 f8e7 f1e1 b7b5 a4b3 d7d6 c2c3 e8g8",
+            CompressionLevel::High,
+        )
+        .unwrap();
+        let clocks_white = [180, 178, 175, 170, 168, 160, 155, 150];
+        let clocks_black = [180, 179, 179, 177, 176, 3725, 174];
+        let evals = [17, 19, 20, -35, i16::MAX, i16::MIN + 2];
+        let pgn = PgnWriter::new(&game)
+            .with_tags([
+                ("White", "Player \"A\""),
+                ("Result", "1/2-1/2"),
+                ("WhiteElo", "1500"),
+                ("Event", "Rated Blitz game"),
+                ("Annotator", "?"),
+            ])
+            .with_clocks(&clocks_white, &clocks_black)
+            .with_evals(&evals)
+            .to_pgn_string()
+            .unwrap();
+        let expected = r#"[Event "Rated Blitz game"]
+[Site "?"]
+[Date "????.??.??"]
+[Round "?"]
+[White "Player \"A\""]
+[Black "?"]
+[Result "1/2-1/2"]
+[Annotator "?"]
+[WhiteElo "1500"]
+
+1. e4 { [%eval 0.17] [%clk 0:03:00] } 1... e5 { [%eval 0.19] [%clk 0:03:00] }
+2. Nf3 { [%eval 0.20] [%clk 0:02:58] } 2... Nc6 { [%eval -0.35] [%clk 0:02:59] }
+3. Bb5 { [%eval #1] [%clk 0:02:55] } 3... a6 { [%eval #-3] [%clk 0:02:59] }
+4. Ba4 { [%clk 0:02:50] } 4... Nf6 { [%clk 0:02:57] } 5. O-O { [%clk 0:02:48] }
+5... Be7 { [%clk 0:02:56] } 6. Re1 { [%clk 0:02:40] } 6... b5 { [%clk 1:02:05] }
+7. Bb3 { [%clk 0:02:35] } 7... d6 { [%clk 0:02:54] } 8. c3 { [%clk 0:02:30] }
+8... O-O 1/2-1/2
+
+"#;
+        assert_eq!(pgn, expected);
+        assert!(pgn.lines().all(|line| line.len() <= 80));
+    }
+
+    #[test]
+    fn write_clocks_ms_test() {
+        let game = EncodedGame::from_uci_str("e2e4 e7e5 g1f3 b8c6", CompressionLevel::Low).unwrap();
+        let clocks_white_ms = [Some(5300), None];
+        let clocks_black_ms = [Some(90_000_000), Some(179_250)];
+        let movetext = PgnWriter::new(&game)
+            .with_clocks(&[180, 178], &[180, 179])
+            .with_clocks_ms(&clocks_white_ms, &clocks_black_ms)
+            .to_movetext_string()
+            .unwrap();
+        assert_eq!(
+            movetext,
+            "1. e4 { [%clk 0:00:05.3] } 1... e5 { [%clk 25:00:00] } 2. Nf3 Nc6 { [%clk 0:02:59.25] }"
+        );
+
+        // clocks in seconds are used for the side without clocks in milliseconds
+        let movetext = PgnWriter::new(&game)
+            .with_clocks(&[180, 178], &[180, 179])
+            .with_clocks_ms(&[], &clocks_black_ms)
+            .to_movetext_string()
+            .unwrap();
+        assert!(movetext.starts_with("1. e4 { [%clk 0:03:00] } 1... e5 { [%clk 25:00:00] }"));
+    }
+
+    #[test]
+    fn write_start_position_test() {
+        let fen: Fen = "4k3/8/8/8/8/8/4P3/4K3 b - - 0 40".parse().unwrap();
+        let start: Chess = fen.into_position(CastlingMode::Standard).unwrap();
+        let mut encoder = Encoder::with_start_position(CompressionLevel::Low, start);
+        encoder.encode_uci("e8d7").unwrap();
+        encoder.encode_uci("e2e4").unwrap();
+        let game = encoder.finish();
+        let pgn = PgnWriter::new(&game).to_pgn_string().unwrap();
+        assert!(pgn.contains("[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 40\"]\n[SetUp \"1\"]\n"));
+        assert!(pgn.ends_with("\n\n40... Kd7 41. e4 *\n\n"));
+    }
+
+    #[test]
+    fn annotations_test() {
+        let game =
+            EncodedGame::from_uci_str("e2e4 e7e5 g1f3 b8c6", CompressionLevel::Medium).unwrap();
+
+        // the sideline replaces 1... e5, so it starts after 1. e4
+        let after_e4 = Decoder::new(&game).position_at(1).unwrap().unwrap();
+        let mut encoder = Encoder::with_start_position(CompressionLevel::Medium, after_e4);
+        encoder.encode_uci("c7c5").unwrap();
+        encoder.encode_uci("g1f3").unwrap();
+        let sicilian = encoder.finish().into_bytes();
+        let mut sideline = Annotations::new();
+        sideline.push(2, Annotation::Nag(1));
+
+        let mut annotations = Annotations::new();
+        annotations.push(0, Annotation::Comment("Open game".to_owned()));
+        annotations.push(1, Annotation::Nag(1));
+        annotations.push(2, Annotation::Variation(sicilian, sideline));
+        annotations.push(2, Annotation::Comment("The  classical\nreply".to_owned()));
+        annotations.push(3, Annotation::Nag(14));
+
+        let evals = [20];
+        let writer = PgnWriter::new(&game)
+            .with_evals(&evals)
+            .with_annotations(&annotations);
+        assert_eq!(
+            writer.to_movetext_string().unwrap(),
+            "{ Open game } 1. e4 $1 { [%eval 0.20] } 1... e5 ( 1... c5 2. Nf3 $1 ) \
+             { The classical reply } 2. Nf3 $14 Nc6"
+        );
+        assert!(
+            writer
+                .to_pgn_string()
+                .unwrap()
+                .ends_with("2. Nf3 $14 Nc6 *\n\n")
+        );
+    }
+
+    #[test]
+    fn comment_braces_test() {
+        let game = EncodedGame::from_uci_str("e2e4", CompressionLevel::Medium).unwrap();
+        let mut annotations = Annotations::new();
+        annotations.push(1, Annotation::Comment("{nested} } comment}".to_owned()));
+
+        let writer = PgnWriter::new(&game).with_annotations(&annotations);
+        assert_eq!(
+            writer.to_movetext_string().unwrap(),
+            "1. e4 { {nested comment }"
+        );
+    }
+
+    #[test]
+    fn eval_to_string_test() {
+        assert_eq!(eval_to_string(0), "0.00");
+        assert_eq!(eval_to_string(-5), "-0.05");
+        assert_eq!(eval_to_string(1234), "12.34");
+        assert_eq!(eval_to_string(i16::MAX - 511), "#512");
+        assert_eq!(eval_to_string(i16::MIN), "#-1");
+        assert_eq!(eval_to_string(i16::MIN + 511), "#-512");
+    }
+}
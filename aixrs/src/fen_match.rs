@@ -0,0 +1,68 @@
+use aix_chess_compression::{Decode, DecodeResult, Decoder, EncodedGame};
+use shakmaty::fen::{Fen, ParseFenError};
+use shakmaty::variant::VariantPosition;
+use shakmaty::{EnPassantMode, Position, Setup};
+
+/// What a position has to share with the FEN to match it.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Mode {
+    /// The piece placement.
+    Placement,
+    /// The piece placement and the side to move.
+    PlacementAndTurn,
+    /// Everything but the halfmove clock and the fullmove number: also castling rights, the
+    /// en passant square and, for variants, pockets and remaining checks.
+    Full,
+}
+
+pub const MODES: [Mode; 3] = [Mode::Placement, Mode::PlacementAndTurn, Mode::Full];
+
+/// A position to look for in games, unlike `Subfen` it has to be reached exactly.
+pub struct FenPattern {
+    setup: Setup,
+    mode: Mode,
+}
+
+impl FenPattern {
+    pub fn parse(fen: &[u8], mode: Mode) -> Result<FenPattern, ParseFenError> {
+        let setup = Fen::from_ascii(fen)?.into_setup();
+        Ok(FenPattern { setup, mode })
+    }
+
+    pub fn matches(&self, pos: &VariantPosition) -> bool {
+        if pos.board() != &self.setup.board {
+            return false;
+        }
+        if self.mode >= Mode::PlacementAndTurn && pos.turn() != self.setup.turn {
+            return false;
+        }
+        if self.mode >= Mode::Full {
+            // The FEN may have been written with an en passant square after every double pawn
+            // push, or only if the capture is legal: both match.
+            let ep_square = match self.setup.ep_square {
+                Some(_) => pos.ep_square(EnPassantMode::Always),
+                None => pos.ep_square(EnPassantMode::Legal),
+            };
+            return ep_square == self.setup.ep_square
+                && pos.castles().castling_rights() == self.setup.castling_rights
+                && pos.pockets() == self.setup.pockets.as_ref()
+                && pos.remaining_checks() == self.setup.remaining_checks.as_ref();
+        }
+        true
+    }
+
+    /// Returns the ply of the first position of the game that matches, 0 being the start
+    /// position, or `None` if the game never reaches the position.
+    pub fn first_ply(&self, game: &EncodedGame) -> DecodeResult<Option<u16>> {
+        if self.matches(&game.start_position()) {
+            return Ok(Some(0));
+        }
+        let decoder = Decoder::new(game);
+        for (ply, position) in decoder.into_iter_positions().enumerate() {
+            if self.matches(&position?) {
+                return Ok(Some(ply as u16 + 1));
+            }
+        }
+        Ok(None)
+    }
+}
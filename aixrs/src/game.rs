@@ -1,97 +1,66 @@
-use super::{decode_bytes, decode_bytes_positions};
-use crate::board_into_bitboards;
+use super::position_at;
 use crate::ffi::{Bitboards, Game, MoveDetails};
-use aix_chess_compression::{Decode, Decoder, EncodedGame};
+use crate::{board_into_bitboards, position_hash};
+use aix_chess_compression::{Annotations, Decode, Decoder, EncodedGame, PgnWriter};
 use diplomat_runtime::DiplomatWrite;
 use shakmaty::fen::Fen;
-use shakmaty::{Chess, Color, EnPassantMode, Position, Setup};
+use shakmaty::{EnPassantMode, Position};
 use std::fmt::Write;
 
-pub fn pieces_at_position(data: &[u8], pos: i32) -> Result<Bitboards, crate::ffi::DecodeError> {
-    let positions = decode_bytes_positions(data)?;
-
-    let index = if pos >= 0 {
-        pos
-    } else {
-        positions.len() as i32 + pos + 1
-    };
-
-    if index < 0 || index as usize > positions.len() {
-        Err(crate::ffi::DecodeError::NoErrorNoValue)
-    } else {
-        Ok(if index == 0 {
-            board_into_bitboards(&Chess::new().board())
-        } else {
-            board_into_bitboards(positions[index as usize - 1].board())
-        })
-    }
+pub fn pieces_at_position(data: &[u8], pos: i32) -> Result<Bitboards, crate::ffi::DecodeFailure> {
+    Ok(board_into_bitboards(position_at(data, pos)?.board()))
 }
 
 pub fn board_at_position(
     data: &[u8],
     pos: i32,
     out: &mut [i8],
-) -> Result<(), crate::ffi::DecodeError> {
-    let positions_result = decode_bytes(data).map(|(_, p)| p);
-
-    positions_result.and_then(|positions| {
-        let index = if pos >= 0 {
-            pos
-        } else {
-            positions.len() as i32 + pos + 1
-        };
-
-        if index < 0 || index as usize > positions.len() {
-            Err(crate::ffi::DecodeError::NoErrorNoValue)
-        } else {
-            let setup = if index == 0 {
-                Setup::default()
-            } else {
-                positions[index as usize - 1]
-                    .clone()
-                    .to_setup(EnPassantMode::Always)
-            };
-
-            for (sq, p) in setup.board {
-                out[sq as usize] = p.char() as i8;
-            }
-
-            Ok(())
-        }
-    })
+) -> Result<(), crate::ffi::DecodeFailure> {
+    let setup = position_at(data, pos)?.to_setup(EnPassantMode::Always);
+    for (sq, p) in setup.board {
+        out[sq as usize] = p.char() as i8;
+    }
+
+    Ok(())
 }
 
 pub fn fen_at_position(
     data: &[u8],
     pos: i32,
     out: &mut DiplomatWrite,
-) -> Result<(), crate::ffi::DecodeError> {
-    let positions_result = decode_bytes(data).map(|(_, p)| p);
-
-    positions_result.and_then(|positions| {
-        let index = if pos >= 0 {
-            pos
-        } else {
-            positions.len() as i32 + pos + 1
-        };
-
-        if index < 0 || index as usize > positions.len() {
-            Err(crate::ffi::DecodeError::NoErrorNoValue)
-        } else {
-            let fen = if index == 0 {
-                let pos = Chess::default();
-                Fen::from_position(&pos, EnPassantMode::Always)
-            } else {
-                Fen::from_position(&positions[index as usize - 1], EnPassantMode::Always)
-            }
-            .to_string();
-            write!(out, "{fen}").expect("fen_at_position: write to DiplomatWrite failed");
-            Ok(())
-        }
-    })
+) -> Result<(), crate::ffi::DecodeFailure> {
+    let fen = Fen::from_position(&position_at(data, pos)?, EnPassantMode::Always).to_string();
+    write!(out, "{fen}").expect("fen_at_position: write to DiplomatWrite failed");
+    Ok(())
+}
+
+pub fn zobrist_at_position(data: &[u8], pos: i32) -> Result<u64, crate::ffi::DecodeFailure> {
+    Ok(position_hash(&position_at(data, pos)?))
+}
+
+/// Writes the hashes of the positions of the game to `out`, starting with the start position,
+/// until `out` is full. Returns the number of hashes written.
+pub fn position_hashes(data: &[u8], out: &mut [u64]) -> Result<usize, crate::ffi::DecodeFailure> {
+    let encoded = EncodedGame::from_bytes(data)?;
+    let Some((first, rest)) = out.split_first_mut() else {
+        return Ok(0);
+    };
+    *first = position_hash(&encoded.start_position());
+
+    let decoder = Decoder::new(&encoded);
+    let mut written = 1;
+    for (hash, position) in rest.iter_mut().zip(decoder.into_iter_positions()) {
+        *hash = position_hash(&position?);
+        written += 1;
+    }
+
+    Ok(written)
 }
 
-pub fn to_uci_string(data: &[u8], out: &mut DiplomatWrite) -> Result<(), crate::ffi::DecodeError> {
+pub fn to_uci_string(
+    data: &[u8],
+    out: &mut DiplomatWrite,
+) -> Result<(), crate::ffi::DecodeFailure> {
     let encoded = EncodedGame::from_bytes(data)?;
     let decoder = Decoder::new(&encoded);
     let uci_string = decoder.into_uci_string()?;
@@ -99,7 +68,10 @@ pub fn to_uci_string(data: &[u8], out: &mut DiplomatWrite) -> Result<(), crate::
     Ok(())
 }
 
-pub fn to_pgn_string(data: &[u8], out: &mut DiplomatWrite) -> Result<(), crate::ffi::DecodeError> {
+pub fn to_pgn_string(
+    data: &[u8],
+    out: &mut DiplomatWrite,
+) -> Result<(), crate::ffi::DecodeFailure> {
     let encoded = EncodedGame::from_bytes(data)?;
     let decoder = Decoder::new(&encoded);
     let pgn_string = decoder.into_pgn_string()?;
@@ -107,17 +79,28 @@ pub fn to_pgn_string(data: &[u8], out: &mut DiplomatWrite) -> Result<(), crate::
     Ok(())
 }
 
-pub fn moved_pieces(data: &[u8], out: &mut DiplomatWrite) -> Result<(), crate::ffi::DecodeError> {
+pub fn to_annotated_pgn_string(
+    data: &[u8],
+    annotations: &[u8],
+    out: &mut DiplomatWrite,
+) -> Result<(), crate::ffi::DecodeFailure> {
     let encoded = EncodedGame::from_bytes(data)?;
+    let annotations = Annotations::from_bytes(annotations)?;
+    let movetext = PgnWriter::new(&encoded)
+        .with_annotations(&annotations)
+        .to_movetext_string()?;
+    write!(out, "{movetext}").unwrap();
+    Ok(())
+}
+
+pub fn moved_pieces(data: &[u8], out: &mut DiplomatWrite) -> Result<(), crate::ffi::DecodeFailure> {
+    let encoded = EncodedGame::from_bytes(data)?;
+    let first_turn = encoded.start_position().turn();
     let decoder = Decoder::new(&encoded);
     for (i, m) in decoder.into_iter_moves().enumerate() {
         let piece = m?
             .role()
-            .of(if i % 2 == 0 {
-                Color::White
-            } else {
-                Color::Black
-            })
+            .of(if i % 2 == 0 { first_turn } else { !first_turn })
             .char();
         write!(out, "{piece}").unwrap();
     }
@@ -125,7 +108,7 @@ pub fn moved_pieces(data: &[u8], out: &mut DiplomatWrite) -> Result<(), crate::f
     Ok(())
 }
 
-pub fn from_bytes(data: &'_ [u8]) -> Result<Box<Game<'_>>, crate::ffi::DecodeError> {
+pub fn from_bytes(data: &'_ [u8]) -> Result<Box<Game<'_>>, crate::ffi::DecodeFailure> {
     Ok(Box::new(Game(EncodedGame::from_bytes(data)?)))
 }
 
@@ -136,14 +119,15 @@ fn castling_king_dest(king: shakmaty::Square, rook: shakmaty::Square) -> shakmat
 
 pub fn move_details_iterator<'a>(
     encoded: &'a EncodedGame,
-) -> impl Iterator<Item = Result<MoveDetails, crate::ffi::DecodeError>> + 'a {
+) -> impl Iterator<Item = Result<MoveDetails, crate::ffi::DecodeFailure>> + 'a {
     let decoder = Decoder::new(encoded);
     decoder
         .into_iter_moves_and_positions()
         .enumerate()
         .map(|(ply, r)| {
             r.map(|(m, pos)| {
-                let from = m.from().expect("from() should always be Some(...)") as u8;
+                // drops (Crazyhouse) have no from square, 64 is written as an empty string
+                let from = m.from().map_or(64, |sq| sq as u8);
                 let to = match m {
                     shakmaty::Move::Normal { to, .. }
                     | shakmaty::Move::EnPassant { to, .. }
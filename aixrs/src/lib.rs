@@ -1,26 +1,27 @@
-use aix_chess_compression::{CompressionLevel, Decode, Decoder, EncodedGame};
+use aix_chess_compression::{CompressionLevel, Decoder, EncodedGame};
 use ffi::Bitboards;
-use shakmaty::{Board, Chess, Move};
+use shakmaty::zobrist::Zobrist64;
+use shakmaty::{variant::VariantPosition, Board, EnPassantMode, Position};
 
+mod fen_match;
 mod game;
+mod opening_tree;
+mod pawn_structure;
 mod scoutfish;
 mod subfen;
 
-fn decode_bytes(bytes: &[u8]) -> Result<(Vec<Move>, Vec<Chess>), ffi::DecodeError> {
-    let encoded = EncodedGame::from_bytes(bytes).unwrap();
+fn position_at(bytes: &[u8], index: i32) -> Result<VariantPosition, ffi::DecodeFailure> {
+    let encoded = EncodedGame::from_bytes(bytes)?;
     let decoder = Decoder::new(&encoded);
     decoder
-        .decode_all_moves_and_positions()
-        .map_err(|e| e.into())
+        .position_at(index)?
+        .ok_or(ffi::DecodeError::NoErrorNoValue.into())
 }
 
-fn decode_bytes_positions(bytes: &[u8]) -> Result<Vec<Chess>, crate::ffi::DecodeError> {
-    let encoded = EncodedGame::from_bytes(bytes)?;
-    let decoder = Decoder::new(&encoded);
-    let result = decoder
-        .into_iter_positions()
-        .collect::<Result<Vec<_>, _>>()?;
-    Ok(result)
+/// Returns the 64-bit Zobrist hash of a position, computed with the Polyglot keys. En passant
+/// squares are only included if an en passant capture is legal.
+fn position_hash(pos: &VariantPosition) -> u64 {
+    pos.zobrist_hash::<Zobrist64>(EnPassantMode::Legal).0
 }
 
 fn board_into_bitboards(board: &Board) -> Bitboards {
@@ -54,13 +55,30 @@ const LEVELS: [CompressionLevel; 3] = [
     CompressionLevel::High,
 ];
 
-impl From<aix_chess_compression::DecodeError> for ffi::DecodeError {
-    fn from(_: aix_chess_compression::DecodeError) -> Self {
-        ffi::DecodeError::InvalidDataDuringDecoding
+impl From<aix_chess_compression::DecodeError> for ffi::DecodeFailure {
+    fn from(e: aix_chess_compression::DecodeError) -> Self {
+        let kind = match e.kind() {
+            aix_chess_compression::DecodeErrorKind::TruncatedData => {
+                ffi::DecodeError::TruncatedData
+            }
+            aix_chess_compression::DecodeErrorKind::InvalidIndex => ffi::DecodeError::InvalidIndex,
+            aix_chess_compression::DecodeErrorKind::IllegalMove => ffi::DecodeError::IllegalMove,
+            aix_chess_compression::DecodeErrorKind::InvalidData => {
+                ffi::DecodeError::InvalidDataDuringDecoding
+            }
+        };
+        ffi::DecodeFailure {
+            kind,
+            ply: u32::try_from(e.ply()).unwrap_or(u32::MAX),
+            level: LEVELS
+                .iter()
+                .position(|level| *level == e.compression_level())
+                .unwrap() as u8,
+        }
     }
 }
 
-impl From<aix_chess_compression::EncodedGameConstructionError> for ffi::DecodeError {
+impl From<aix_chess_compression::EncodedGameConstructionError> for ffi::DecodeFailure {
     fn from(e: aix_chess_compression::EncodedGameConstructionError) -> Self {
         match e {
             aix_chess_compression::EncodedGameConstructionError::EmptyData => {
@@ -72,22 +90,38 @@ impl From<aix_chess_compression::EncodedGameConstructionError> for ffi::DecodeEr
             aix_chess_compression::EncodedGameConstructionError::InvalidData => {
                 ffi::DecodeError::InvalidEncodedGameConstructionData
             }
+            aix_chess_compression::EncodedGameConstructionError::UnsupportedVersion => {
+                ffi::DecodeError::UnsupportedVersion
+            }
+        }
+        .into()
+    }
+}
+
+/// For errors that are not about a move of the game.
+impl From<ffi::DecodeError> for ffi::DecodeFailure {
+    fn from(kind: ffi::DecodeError) -> Self {
+        ffi::DecodeFailure {
+            kind,
+            ply: 0,
+            level: 0,
         }
     }
 }
 
 fn optional_result_to_result<T>(
-    option: Option<Result<T, ffi::DecodeError>>,
-) -> Result<T, ffi::DecodeError> {
+    option: Option<Result<T, ffi::DecodeFailure>>,
+) -> Result<T, ffi::DecodeFailure> {
     match option {
         Some(Ok(value)) => Ok(value),
         Some(Err(e)) => Err(e),
-        None => Err(ffi::DecodeError::NoErrorNoValue),
+        None => Err(ffi::DecodeError::NoErrorNoValue.into()),
     }
 }
 
 #[diplomat::bridge]
 mod ffi {
+    use std::fmt::Write as _;
     use std::io::Write;
 
     use aix_chess_compression::EncodedGame;
@@ -103,6 +137,9 @@ mod ffi {
         BincodeError = 7,
         BufferTooSmall = 8,
         CursorWriteError = 9,
+        InvalidPawnCountFormat = 10,
+        InvalidOpenFilesFormat = 11,
+        InvalidCastledFormat = 12,
     }
 
     pub struct Bitboards {
@@ -139,6 +176,20 @@ mod ffi {
         InvalidCompressionLevel = 2,
         InvalidEncodedGameConstructionData = 3,
         InvalidDataDuringDecoding = 4,
+        UnsupportedVersion = 5,
+        TruncatedData = 6,
+        InvalidIndex = 7,
+        IllegalMove = 8,
+    }
+
+    /// A `DecodeError` with the move where decoding failed.
+    pub struct DecodeFailure {
+        pub kind: DecodeError,
+        /// The ply of the move that could not be decoded, or 0 if the error is not about a move.
+        pub ply: u32,
+        /// The compression level of the game if `ply` is set: 0 for low, 1 for medium and 2 for
+        /// high.
+        pub level: u8,
     }
 
     #[diplomat::opaque]
@@ -146,37 +197,54 @@ mod ffi {
 
     #[diplomat::opaque]
     pub struct MoveDetailsIterator<'a>(
-        pub Box<dyn Iterator<Item = Result<MoveDetails, DecodeError>> + 'a>,
+        pub Box<dyn Iterator<Item = Result<MoveDetails, DecodeFailure>> + 'a>,
     );
 
     impl<'a> Game<'a> {
-        pub fn from_bytes(data: &'a [u8]) -> Result<Box<Self>, DecodeError> {
+        pub fn from_bytes(data: &'a [u8]) -> Result<Box<Self>, DecodeFailure> {
             crate::game::from_bytes(data)
         }
-        pub fn pieces_at_position(data: &[u8], pos: i32) -> Result<Bitboards, DecodeError> {
+        pub fn pieces_at_position(data: &[u8], pos: i32) -> Result<Bitboards, DecodeFailure> {
             crate::game::pieces_at_position(data, pos)
         }
-        pub fn board_at_position(data: &[u8], pos: i32, out: &mut [i8]) -> Result<(), DecodeError> {
+        pub fn board_at_position(
+            data: &[u8],
+            pos: i32,
+            out: &mut [i8],
+        ) -> Result<(), DecodeFailure> {
             crate::game::board_at_position(data, pos, out)
         }
         pub fn fen_at_position(
             data: &[u8],
             pos: i32,
             out: &mut DiplomatWrite,
-        ) -> Result<(), DecodeError> {
+        ) -> Result<(), DecodeFailure> {
             crate::game::fen_at_position(data, pos, out)
         }
-        pub fn to_uci_string(data: &[u8], out: &mut DiplomatWrite) -> Result<(), DecodeError> {
+        pub fn zobrist_at_position(data: &[u8], pos: i32) -> Result<u64, DecodeFailure> {
+            crate::game::zobrist_at_position(data, pos)
+        }
+        pub fn position_hashes(data: &[u8], out: &mut [u64]) -> Result<usize, DecodeFailure> {
+            crate::game::position_hashes(data, out)
+        }
+        pub fn to_uci_string(data: &[u8], out: &mut DiplomatWrite) -> Result<(), DecodeFailure> {
             crate::game::to_uci_string(data, out)
         }
-        pub fn to_pgn_string(data: &[u8], out: &mut DiplomatWrite) -> Result<(), DecodeError> {
+        pub fn to_pgn_string(data: &[u8], out: &mut DiplomatWrite) -> Result<(), DecodeFailure> {
             crate::game::to_pgn_string(data, out)
         }
-        pub fn moved_pieces(data: &[u8], out: &mut DiplomatWrite) -> Result<(), DecodeError> {
+        pub fn to_annotated_pgn_string(
+            data: &[u8],
+            annotations: &[u8],
+            out: &mut DiplomatWrite,
+        ) -> Result<(), DecodeFailure> {
+            crate::game::to_annotated_pgn_string(data, annotations, out)
+        }
+        pub fn moved_pieces(data: &[u8], out: &mut DiplomatWrite) -> Result<(), DecodeFailure> {
             crate::game::moved_pieces(data, out)
         }
 
-        pub fn recompress(data: &[u8], level: u8, out: &mut [u8]) -> Result<usize, DecodeError> {
+        pub fn recompress(data: &[u8], level: u8, out: &mut [u8]) -> Result<usize, DecodeFailure> {
             let game = EncodedGame::from_bytes(data)?;
             let recomp = game.recompress(crate::LEVELS[level as usize])?;
             let bytes = recomp.into_bytes();
@@ -194,31 +262,31 @@ mod ffi {
     }
 
     impl<'a> MoveDetailsIterator<'a> {
-        pub fn next(&mut self) -> Result<MoveDetails, DecodeError> {
+        pub fn next(&mut self) -> Result<MoveDetails, DecodeFailure> {
             crate::optional_result_to_result(self.0.next())
         }
 
-        pub fn nth(&mut self, n: i16) -> Result<MoveDetails, DecodeError> {
+        pub fn nth(&mut self, n: i16) -> Result<MoveDetails, DecodeFailure> {
             if n >= 0 {
                 crate::optional_result_to_result(self.0.nth(n as usize))
             } else {
                 let mut collected = self
                     .0
                     .by_ref()
-                    .collect::<Result<Vec<MoveDetails>, DecodeError>>()?;
+                    .collect::<Result<Vec<MoveDetails>, DecodeFailure>>()?;
                 let i = collected.len() as i16 + n;
                 if i >= 0 {
                     let result = collected.swap_remove(i as usize);
                     Ok(result)
                 } else {
-                    Err(DecodeError::NoErrorNoValue)
+                    Err(DecodeError::NoErrorNoValue.into())
                 }
             }
         }
     }
 
     #[cfg_attr(test, derive(Debug, PartialEq))]
-    #[derive(bincode::Encode, bincode::Decode)]
+    #[derive(Default, bincode::Encode, bincode::Decode)]
     pub struct Subfen {
         pub white: u64,
         pub black: u64,
@@ -228,6 +296,21 @@ mod ffi {
         pub bishop: u64,
         pub knight: u64,
         pub pawn: u64,
+        /// Squares with a knight or a bishop.
+        pub minor: u64,
+        pub occupied: u64,
+        pub empty: u64,
+        /// Negated squares, see `subfen::try_parse`: the same masks as above, but the square
+        /// must not match all of them.
+        pub not_white: u64,
+        pub not_black: u64,
+        pub not_king: u64,
+        pub not_queen: u64,
+        pub not_rook: u64,
+        pub not_bishop: u64,
+        pub not_knight: u64,
+        pub not_pawn: u64,
+        pub not_minor: u64,
     }
 
     impl Subfen {
@@ -235,11 +318,100 @@ mod ffi {
             crate::subfen::try_parse(subfen).map_err(|_| ())
         }
 
-        pub fn matches(self, game: &[u8]) -> Result<bool, DecodeError> {
+        pub fn matches(self, game: &[u8]) -> Result<bool, DecodeFailure> {
             crate::subfen::matches(self, game)
         }
     }
 
+    #[diplomat::opaque]
+    pub struct FenPattern(pub crate::fen_match::FenPattern);
+
+    impl FenPattern {
+        pub fn parse(fen: &DiplomatStr, mode: u8) -> Result<Box<FenPattern>, ()> {
+            let mode = *crate::fen_match::MODES.get(usize::from(mode)).ok_or(())?;
+            crate::fen_match::FenPattern::parse(fen, mode)
+                .map(|p| Box::new(FenPattern(p)))
+                .map_err(|_| ())
+        }
+
+        pub fn first_ply(&self, game: &[u8]) -> Result<u16, DecodeFailure> {
+            let game = EncodedGame::from_bytes(game)?;
+            self.0
+                .first_ply(&game)?
+                .ok_or(DecodeError::NoErrorNoValue.into())
+        }
+    }
+
+    pub struct OpeningTreeEntry {
+        pub ply: u16,
+        pub position: u64,
+        pub games: u64,
+        pub white_wins: u64,
+        pub draws: u64,
+        pub black_wins: u64,
+    }
+
+    #[diplomat::opaque]
+    pub struct OpeningTree(pub crate::opening_tree::OpeningTree);
+
+    #[diplomat::opaque]
+    pub struct OpeningTreeEntries(pub Vec<crate::opening_tree::Entry>);
+
+    impl OpeningTree {
+        pub fn create() -> Box<OpeningTree> {
+            Box::new(OpeningTree(Default::default()))
+        }
+
+        pub fn add_game(
+            &mut self,
+            game: &[u8],
+            result: &DiplomatStr,
+            max_ply: u16,
+        ) -> Result<(), DecodeFailure> {
+            let game = EncodedGame::from_bytes(game)?;
+            let result = std::str::from_utf8(result).unwrap_or_default();
+            Ok(self.0.add_game(&game, result, max_ply)?)
+        }
+
+        pub fn merge(&mut self, other: &OpeningTree) {
+            self.0.merge(&other.0)
+        }
+
+        pub fn entries(&self) -> Box<OpeningTreeEntries> {
+            Box::new(OpeningTreeEntries(self.0.entries()))
+        }
+    }
+
+    impl OpeningTreeEntries {
+        pub fn count(&self) -> usize {
+            self.0.len()
+        }
+
+        pub fn get(&self, index: usize) -> OpeningTreeEntry {
+            let entry = &self.0[index];
+            OpeningTreeEntry {
+                ply: entry.ply,
+                position: entry.position,
+                games: entry.counts.games,
+                white_wins: entry.counts.white_wins,
+                draws: entry.counts.draws,
+                black_wins: entry.counts.black_wins,
+            }
+        }
+
+        pub fn fen(&self, index: usize, out: &mut DiplomatWrite) {
+            write!(out, "{}", self.0[index].fen).unwrap();
+        }
+
+        pub fn uci(&self, index: usize, out: &mut DiplomatWrite) {
+            write!(out, "{}", self.0[index].uci).unwrap();
+        }
+
+        pub fn san(&self, index: usize, out: &mut DiplomatWrite) {
+            write!(out, "{}", self.0[index].san).unwrap();
+        }
+    }
+
     #[diplomat::opaque]
     pub struct ScoutfishQuery(pub crate::scoutfish::Query);
 
@@ -255,12 +427,12 @@ mod ffi {
             crate::scoutfish::Query::decode_bytes(data).map(|q| Box::new(ScoutfishQuery(q)))
         }
 
-        pub fn matches(&self, game: &[u8]) -> Result<bool, DecodeError> {
+        pub fn matches(&self, game: &[u8]) -> Result<bool, DecodeFailure> {
             let game = EncodedGame::from_bytes(game)?;
             Ok(self.0.apply(&game, false)?.0)
         }
 
-        pub fn matches_plies(&self, game: &[u8], out: &mut [u32]) -> Result<u32, DecodeError> {
+        pub fn matches_plies(&self, game: &[u8], out: &mut [u32]) -> Result<u32, DecodeFailure> {
             assert_eq!(out.len(), 16);
             let game = EncodedGame::from_bytes(game)?;
             if let Some(plies) = self.0.apply(&game, true)?.1 {
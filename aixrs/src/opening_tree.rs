@@ -0,0 +1,168 @@
+use crate::position_hash;
+use aix_chess_compression::{Decode, DecodeResult, Decoder, EncodedGame};
+use shakmaty::fen::Fen;
+use shakmaty::san::{San, SanPlus, Suffix};
+use shakmaty::uci::UciMove;
+use shakmaty::{Color, EnPassantMode, KnownOutcome};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Number of games that played a move from a position, by result.
+#[derive(Clone, Copy, Default)]
+pub struct Counts {
+    pub games: u64,
+    pub white_wins: u64,
+    pub draws: u64,
+    pub black_wins: u64,
+}
+
+impl Counts {
+    fn add(&mut self, other: Counts) {
+        self.games += other.games;
+        self.white_wins += other.white_wins;
+        self.draws += other.draws;
+        self.black_wins += other.black_wins;
+    }
+
+    /// Counts one game with the given result (`1-0`, `0-1` or `1/2-1/2`). Games with another
+    /// result, like `*`, only count towards `games`.
+    fn one(result: &str) -> Counts {
+        let mut counts = Counts {
+            games: 1,
+            ..Counts::default()
+        };
+        match KnownOutcome::from_str(result.trim()) {
+            Ok(KnownOutcome::Decisive {
+                winner: Color::White,
+            }) => counts.white_wins = 1,
+            Ok(KnownOutcome::Decisive {
+                winner: Color::Black,
+            }) => counts.black_wins = 1,
+            Ok(KnownOutcome::Draw) => counts.draws = 1,
+            Err(_) => {}
+        }
+        counts
+    }
+}
+
+/// A position of the tree and the moves played from it.
+struct Node {
+    /// Lowest ply the position was reached at.
+    ply: u16,
+    fen: String,
+    moves: HashMap<UciMove, MoveNode>,
+}
+
+struct MoveNode {
+    san: String,
+    counts: Counts,
+}
+
+/// A move played from a position of the tree, see `OpeningTree::entries`.
+pub struct Entry {
+    pub ply: u16,
+    pub position: u64,
+    pub fen: String,
+    pub uci: String,
+    pub san: String,
+    pub counts: Counts,
+}
+
+/// Aggregates the moves played from every position of a set of games, up to a maximum ply.
+/// Positions are keyed by their Zobrist hash, so transpositions end up in the same position.
+#[derive(Default)]
+pub struct OpeningTree {
+    nodes: HashMap<u64, Node>,
+}
+
+impl OpeningTree {
+    /// Adds the first `max_ply` moves of a game with the given result.
+    pub fn add_game(&mut self, game: &EncodedGame, result: &str, max_ply: u16) -> DecodeResult<()> {
+        let counts = Counts::one(result);
+        let castling_mode = game.castling_mode();
+        let mut before = game.start_position();
+        let decoder = Decoder::new(game);
+        for (ply, r) in decoder
+            .into_iter_moves_and_positions()
+            .take(max_ply as usize)
+            .enumerate()
+        {
+            let (m, after) = r?;
+            let ply = ply as u16;
+            let node = self
+                .nodes
+                .entry(position_hash(&before))
+                .or_insert_with(|| Node {
+                    ply,
+                    fen: Fen::from_position(&before, EnPassantMode::Legal).to_string(),
+                    moves: HashMap::new(),
+                });
+            node.ply = node.ply.min(ply);
+            node.moves
+                .entry(m.to_uci(castling_mode))
+                .or_insert_with(|| MoveNode {
+                    san: SanPlus {
+                        san: San::from_move(&before, m),
+                        suffix: Suffix::from_position(&after),
+                    }
+                    .to_string(),
+                    counts: Counts::default(),
+                })
+                .counts
+                .add(counts);
+            before = after;
+        }
+
+        Ok(())
+    }
+
+    /// Adds all games of another tree.
+    pub fn merge(&mut self, other: &OpeningTree) {
+        for (hash, other_node) in &other.nodes {
+            let node = self.nodes.entry(*hash).or_insert_with(|| Node {
+                ply: other_node.ply,
+                fen: other_node.fen.clone(),
+                moves: HashMap::new(),
+            });
+            node.ply = node.ply.min(other_node.ply);
+            for (uci, other_move) in &other_node.moves {
+                node.moves
+                    .entry(*uci)
+                    .or_insert_with(|| MoveNode {
+                        san: other_move.san.clone(),
+                        counts: Counts::default(),
+                    })
+                    .counts
+                    .add(other_move.counts);
+            }
+        }
+    }
+
+    /// Returns the moves of all positions, ordered by the ply of the position and then by the
+    /// number of games, most played first.
+    pub fn entries(&self) -> Vec<Entry> {
+        let mut entries: Vec<Entry> = self
+            .nodes
+            .iter()
+            .flat_map(|(hash, node)| {
+                node.moves.iter().map(|(uci, m)| Entry {
+                    ply: node.ply,
+                    position: *hash,
+                    fen: node.fen.clone(),
+                    uci: uci.to_string(),
+                    san: m.san.clone(),
+                    counts: m.counts,
+                })
+            })
+            .collect();
+        entries.sort_by(|a, b| {
+            (a.ply, a.position, b.counts.games, &a.uci).cmp(&(
+                b.ply,
+                b.position,
+                a.counts.games,
+                &b.uci,
+            ))
+        });
+        entries
+    }
+}
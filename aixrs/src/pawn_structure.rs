@@ -0,0 +1,97 @@
+use shakmaty::{Bitboard, Board, CastlingSide, Color, File, Rank};
+
+fn pawns_of(board: &Board, color: Color) -> Bitboard {
+    board.pawns() & board.by_color(color)
+}
+
+fn adjacent_files(file: File) -> Bitboard {
+    [-1, 1]
+        .into_iter()
+        .filter_map(|delta| file.offset(delta))
+        .fold(Bitboard::EMPTY, |files, f| files | Bitboard::from_file(f))
+}
+
+/// Returns the files that have at least one of the pawns, as a mask with bit 0 for the a-file.
+pub fn files_of(pawns: Bitboard) -> u8 {
+    File::ALL
+        .into_iter()
+        .filter(|f| (pawns & Bitboard::from_file(*f)).any())
+        .fold(0, |files, f| files | (1 << u8::from(f)))
+}
+
+/// Returns the ranks in front of a pawn of `color` on `rank`.
+fn ranks_ahead(rank: Rank, color: Color) -> Bitboard {
+    Rank::ALL
+        .into_iter()
+        .filter(|r| match color {
+            Color::White => *r > rank,
+            Color::Black => *r < rank,
+        })
+        .fold(Bitboard::EMPTY, |ranks, r| ranks | Bitboard::from_rank(r))
+}
+
+/// Counts the pawns of a side without pawns of the same side on an adjacent file.
+pub fn isolated_pawns(board: &Board, color: Color) -> u32 {
+    let pawns = pawns_of(board, color);
+    pawns
+        .into_iter()
+        .filter(|sq| (pawns & adjacent_files(sq.file())).is_empty())
+        .count() as u32
+}
+
+/// Counts the files with more than one pawn of a side.
+pub fn doubled_pawns(board: &Board, color: Color) -> u32 {
+    let pawns = pawns_of(board, color);
+    File::ALL
+        .into_iter()
+        .filter(|f| (pawns & Bitboard::from_file(*f)).count() > 1)
+        .count() as u32
+}
+
+/// Counts the pawns of a side without opposing pawns in front of them, on the same or an
+/// adjacent file.
+pub fn passed_pawns(board: &Board, color: Color) -> u32 {
+    let opposing = pawns_of(board, !color);
+    pawns_of(board, color)
+        .into_iter()
+        .filter(|sq| {
+            let files = Bitboard::from_file(sq.file()) | adjacent_files(sq.file());
+            (opposing & files & ranks_ahead(sq.rank(), color)).is_empty()
+        })
+        .count() as u32
+}
+
+/// Counts the groups of adjacent files with pawns of a side.
+pub fn pawn_islands(board: &Board, color: Color) -> u32 {
+    let files = files_of(pawns_of(board, color));
+    // every island starts with a file whose left neighbour has no pawns
+    (files & !(files << 1)).count_ones()
+}
+
+/// Returns the files without pawns, as a mask with bit 0 for the a-file.
+pub fn open_files(board: &Board) -> u8 {
+    !files_of(board.pawns())
+}
+
+/// Returns whether both sides have exactly one bishop, on squares of different colors.
+pub fn opposite_colored_bishops(board: &Board) -> bool {
+    let white = board.bishops() & board.white();
+    let black = board.bishops() & board.black();
+    white.count() == 1
+        && black.count() == 1
+        && (white & Bitboard::DARK_SQUARES).any() != (black & Bitboard::DARK_SQUARES).any()
+}
+
+/// Returns the side a king is castled to, judged only by where it stands: on its back rank, on
+/// the g- or h-file for the king side and on the a-, b- or c-file for the queen side.
+pub fn castled_side(board: &Board, color: Color) -> Option<CastlingSide> {
+    let king = board.king_of(color)?;
+    if king.rank() != color.backrank() {
+        return None;
+    }
+    match king.file() {
+        File::G | File::H => Some(CastlingSide::KingSide),
+        File::A | File::B | File::C => Some(CastlingSide::QueenSide),
+        _ => None,
+    }
+}
@@ -1,9 +1,12 @@
 use std::{io::Write, str::FromStr};
 
 use crate::ffi::{ScoutfishQueryParseError, Subfen};
+use crate::pawn_structure;
 use aix_chess_compression::{Decode, Decoder, EncodedGame};
 use serde::Deserialize;
-use shakmaty::{san::San, Chess, Color, Move, Position};
+use shakmaty::{
+    san::San, variant::VariantPosition, Board, CastlingSide, Color, File, Move, Position,
+};
 
 #[derive(Deserialize)]
 #[serde(untagged, deny_unknown_fields)]
@@ -33,7 +36,7 @@ impl Strings {
 
 #[derive(Deserialize)]
 #[serde(deny_unknown_fields)]
-#[cfg_attr(test, derive(Debug, PartialEq))]
+#[cfg_attr(test, derive(Debug, PartialEq, Default))]
 struct RawRule {
     #[serde(rename = "sub-fen")]
     sub_fen: Option<Strings>,
@@ -47,6 +50,19 @@ struct RawRule {
     captured: Option<String>,
     stm: Option<String>,
     pass: Option<String>,
+    #[serde(rename = "isolated-pawns")]
+    isolated_pawns: Option<Strings>,
+    #[serde(rename = "doubled-pawns")]
+    doubled_pawns: Option<Strings>,
+    #[serde(rename = "passed-pawns")]
+    passed_pawns: Option<Strings>,
+    #[serde(rename = "pawn-islands")]
+    pawn_islands: Option<Strings>,
+    #[serde(rename = "open-files")]
+    open_files: Option<String>,
+    #[serde(rename = "opposite-colored-bishops")]
+    opposite_colored_bishops: Option<bool>,
+    castled: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -59,6 +75,7 @@ struct RawStreak {
 #[derive(Deserialize)]
 #[serde(untagged, deny_unknown_fields)]
 #[cfg_attr(test, derive(Debug, PartialEq))]
+#[allow(clippy::large_enum_variant)]
 enum RawSequenceElement {
     Rule(RawRule),
     Streak(RawStreak),
@@ -74,6 +91,7 @@ struct RawSequence {
 #[derive(Deserialize)]
 #[serde(untagged, deny_unknown_fields)]
 #[cfg_attr(test, derive(Debug, PartialEq))]
+#[allow(clippy::large_enum_variant)]
 enum RawQuery {
     Rule(RawRule),
     Sequence(RawSequence),
@@ -249,6 +267,131 @@ impl PieceFlags {
     }
 }
 
+/// A number of pawns, files or islands, see `PawnCounts`.
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[derive(Copy, Clone, bincode::Encode, bincode::Decode)]
+enum Count {
+    Any,
+    Exactly(u8),
+    AtLeast(u8),
+}
+
+impl Count {
+    fn parse(s: &str) -> Result<Count, ScoutfishQueryParseError> {
+        if s.is_empty() {
+            return Ok(Count::Any);
+        }
+        let (n, at_least) = match s.strip_suffix('+') {
+            Some(n) => (n, true),
+            None => (s, false),
+        };
+        let n = n
+            .parse()
+            .map_err(|_| ScoutfishQueryParseError::InvalidPawnCountFormat)?;
+        Ok(if at_least {
+            Count::AtLeast(n)
+        } else {
+            Count::Exactly(n)
+        })
+    }
+
+    fn matches(self, n: u32) -> bool {
+        match self {
+            Count::Any => true,
+            Count::Exactly(c) => n == u32::from(c),
+            Count::AtLeast(c) => n >= u32::from(c),
+        }
+    }
+}
+
+/// Counts a pawn structure feature of a side, see `pawn_structure`.
+type CountFn = fn(&Board, Color) -> u32;
+
+/// The counts of a pawn structure feature for white and black, written like `1v0`. A count
+/// followed by `+` is a minimum, and an empty count matches anything: `1+v` means that white
+/// has at least one.
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[derive(bincode::Encode, bincode::Decode)]
+pub struct PawnCounts {
+    white: Count,
+    black: Count,
+}
+
+impl PawnCounts {
+    fn parse(s: &str) -> Result<PawnCounts, ScoutfishQueryParseError> {
+        match s.split('v').collect::<Vec<_>>()[..] {
+            [white, black] => Ok(PawnCounts {
+                white: Count::parse(white)?,
+                black: Count::parse(black)?,
+            }),
+            _ => Err(ScoutfishQueryParseError::InvalidPawnCountFormat),
+        }
+    }
+
+    fn matches(&self, board: &Board, count: CountFn) -> bool {
+        self.white.matches(count(board, Color::White))
+            && self.black.matches(count(board, Color::Black))
+    }
+}
+
+/// Parses file letters, like `cd`, into a mask with bit 0 for the a-file.
+fn parse_files(s: &str) -> Result<u8, ScoutfishQueryParseError> {
+    let mut files = 0;
+    for c in s.chars() {
+        let file = File::from_char(c).ok_or(ScoutfishQueryParseError::InvalidOpenFilesFormat)?;
+        files |= 1 << u8::from(file);
+    }
+    if files == 0 {
+        return Err(ScoutfishQueryParseError::InvalidOpenFilesFormat);
+    }
+    Ok(files)
+}
+
+/// The sides the kings are castled to, written like castling rights in a FEN: `Kq` means that
+/// white castled king side and black queen side.
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[derive(bincode::Encode, bincode::Decode)]
+pub struct Castled {
+    white: Option<CastlingSide>,
+    black: Option<CastlingSide>,
+}
+
+impl Castled {
+    fn parse(s: &str) -> Result<Castled, ScoutfishQueryParseError> {
+        let mut castled = Castled {
+            white: None,
+            black: None,
+        };
+
+        for c in s.chars() {
+            let (side, castling_side) = match c {
+                'K' => (&mut castled.white, CastlingSide::KingSide),
+                'Q' => (&mut castled.white, CastlingSide::QueenSide),
+                'k' => (&mut castled.black, CastlingSide::KingSide),
+                'q' => (&mut castled.black, CastlingSide::QueenSide),
+                _ => return Err(ScoutfishQueryParseError::InvalidCastledFormat),
+            };
+            if side.replace(castling_side).is_some() {
+                return Err(ScoutfishQueryParseError::InvalidCastledFormat);
+            }
+        }
+
+        if castled.white.is_none() && castled.black.is_none() {
+            return Err(ScoutfishQueryParseError::InvalidCastledFormat);
+        }
+
+        Ok(castled)
+    }
+
+    fn matches(&self, board: &Board) -> bool {
+        [(self.white, Color::White), (self.black, Color::Black)]
+            .into_iter()
+            .all(|(side, color)| {
+                side.is_none() || side == pawn_structure::castled_side(board, color)
+            })
+    }
+}
+
 #[cfg_attr(test, derive(Debug, PartialEq))]
 #[derive(Copy, Clone, bincode::Encode, bincode::Decode)]
 enum SanSubset {
@@ -313,7 +456,7 @@ impl SanSubset {
     }
 }
 
-#[cfg_attr(test, derive(Debug, PartialEq))]
+#[cfg_attr(test, derive(Debug, PartialEq, Default))]
 #[derive(bincode::Encode, bincode::Decode)]
 pub struct Rule {
     sub_fen: Option<Vec<Subfen>>,
@@ -325,6 +468,14 @@ pub struct Rule {
     captured: Option<PieceFlags>,
     stm: Option<bool>,
     pass: bool,
+    isolated_pawns: Option<Vec<PawnCounts>>,
+    doubled_pawns: Option<Vec<PawnCounts>>,
+    passed_pawns: Option<Vec<PawnCounts>>,
+    pawn_islands: Option<Vec<PawnCounts>>,
+    /// Files that must be open, as a mask with bit 0 for the a-file.
+    open_files: Option<u8>,
+    opposite_colored_bishops: Option<bool>,
+    castled: Option<Castled>,
 }
 
 #[cfg_attr(test, derive(Debug, PartialEq))]
@@ -334,7 +485,7 @@ pub struct Streak(Vec<Rule>);
 #[cfg_attr(test, derive(Debug, PartialEq))]
 #[derive(bincode::Encode, bincode::Decode)]
 pub enum SequenceElement {
-    Rule(Rule),
+    Rule(Box<Rule>),
     Streak(Streak),
 }
 
@@ -364,7 +515,7 @@ enum StreakFlow {
 #[cfg_attr(test, derive(Debug, PartialEq))]
 #[derive(bincode::Encode, bincode::Decode)]
 pub enum Query {
-    Rule(Rule),
+    Rule(Box<Rule>),
     Sequence(Sequence),
     Streak(Streak),
 }
@@ -418,10 +569,33 @@ impl Rule {
                 None => None,
             },
             pass: raw.pass.is_some(),
+            isolated_pawns: raw
+                .isolated_pawns
+                .as_ref()
+                .map(|c| c.process(PawnCounts::parse))
+                .transpose()?,
+            doubled_pawns: raw
+                .doubled_pawns
+                .as_ref()
+                .map(|c| c.process(PawnCounts::parse))
+                .transpose()?,
+            passed_pawns: raw
+                .passed_pawns
+                .as_ref()
+                .map(|c| c.process(PawnCounts::parse))
+                .transpose()?,
+            pawn_islands: raw
+                .pawn_islands
+                .as_ref()
+                .map(|c| c.process(PawnCounts::parse))
+                .transpose()?,
+            open_files: raw.open_files.as_deref().map(parse_files).transpose()?,
+            opposite_colored_bishops: raw.opposite_colored_bishops,
+            castled: raw.castled.as_deref().map(Castled::parse).transpose()?,
         })
     }
 
-    pub fn apply(&self, mv_opt: Option<Move>, pos: &Chess) -> bool {
+    pub fn apply(&self, mv_opt: Option<Move>, pos: &VariantPosition) -> bool {
         if let Some(stm) = self.stm {
             if stm != (pos.turn() == Color::White) {
                 return false;
@@ -591,6 +765,38 @@ impl Rule {
             }
         }
 
+        let pawn_counts: [(_, CountFn); 4] = [
+            (&self.isolated_pawns, pawn_structure::isolated_pawns),
+            (&self.doubled_pawns, pawn_structure::doubled_pawns),
+            (&self.passed_pawns, pawn_structure::passed_pawns),
+            (&self.pawn_islands, pawn_structure::pawn_islands),
+        ];
+        for (counts, count) in pawn_counts {
+            if let Some(counts) = counts {
+                if !counts.iter().any(|c| c.matches(board, count)) {
+                    return false;
+                }
+            }
+        }
+
+        if let Some(open_files) = self.open_files {
+            if pawn_structure::open_files(board) & open_files != open_files {
+                return false;
+            }
+        }
+
+        if let Some(opposite_colored_bishops) = self.opposite_colored_bishops {
+            if pawn_structure::opposite_colored_bishops(board) != opposite_colored_bishops {
+                return false;
+            }
+        }
+
+        if let Some(castled) = &self.castled {
+            if !castled.matches(board) {
+                return false;
+            }
+        }
+
         true
     }
 }
@@ -601,7 +807,7 @@ impl Sequence {
         for e in &raw.sequence {
             match e {
                 RawSequenceElement::Rule(r) => {
-                    elements.push(SequenceElement::Rule(Rule::frow_raw(r)?));
+                    elements.push(SequenceElement::Rule(Box::new(Rule::frow_raw(r)?)));
                 }
                 RawSequenceElement::Streak(s) => {
                     elements.push(SequenceElement::Streak(Streak::from_raw(s)?));
@@ -614,7 +820,7 @@ impl Sequence {
     fn apply(
         &self,
         mv_opt: Option<Move>,
-        pos: &Chess,
+        pos: &VariantPosition,
         state: &mut SequenceState,
         ply: u16,
     ) -> SequenceFlow {
@@ -679,7 +885,12 @@ impl Streak {
         Ok(Streak(rules))
     }
 
-    fn apply(&self, mv_opt: Option<Move>, pos: &Chess, state: &mut StreakState) -> StreakFlow {
+    fn apply(
+        &self,
+        mv_opt: Option<Move>,
+        pos: &VariantPosition,
+        state: &mut StreakState,
+    ) -> StreakFlow {
         let len = self.0.len();
         for i in (0..len).rev() {
             if state.check_index[i] {
@@ -708,7 +919,7 @@ impl Streak {
 impl Query {
     fn from_raw(raw: &RawQuery) -> Result<Query, ScoutfishQueryParseError> {
         match raw {
-            RawQuery::Rule(r) => Ok(Query::Rule(Rule::frow_raw(r)?)),
+            RawQuery::Rule(r) => Ok(Query::Rule(Box::new(Rule::frow_raw(r)?))),
             RawQuery::Sequence(s) => Ok(Query::Sequence(Sequence::from_raw(s)?)),
             RawQuery::Streak(s) => Ok(Query::Streak(Streak::from_raw(s)?)),
         }
@@ -733,9 +944,8 @@ impl Query {
                     cursor
                         .write(&bytes)
                         .map_err(|_| ScoutfishQueryParseError::CursorWriteError)
-                        .map(|w| {
+                        .inspect(|&w| {
                             assert_eq!(w, bytes.len());
-                            w
                         })
                 })
         })
@@ -751,9 +961,9 @@ impl Query {
         &self,
         game: &EncodedGame,
         return_plies: bool,
-    ) -> Result<(bool, Option<Vec<u16>>), crate::ffi::DecodeError> {
+    ) -> Result<(bool, Option<Vec<u16>>), crate::ffi::DecodeFailure> {
         let decoder = Decoder::new(game);
-        let mut pos_opt = Some(Chess::new());
+        let mut pos_opt = Some(game.start_position());
 
         let mut sequence_state = if let Query::Sequence(_) = self {
             Some(SequenceState {
@@ -786,7 +996,7 @@ impl Query {
 
             match self {
                 Query::Rule(r) => {
-                    if r.apply(mv, &pos) {
+                    if r.apply(mv, pos) {
                         return Ok((true, if return_plies { Some(vec![ply]) } else { None }));
                     }
                 }
@@ -817,7 +1027,7 @@ impl Query {
                 }
                 Query::Streak(s) => match s.apply(
                     mv,
-                    &pos,
+                    pos,
                     streak_state
                         .as_mut()
                         .expect("streak_state cannot be None if Query::Streak matches"),
@@ -865,6 +1075,7 @@ mod tests {
                 captured: None,
                 stm: None,
                 pass: None,
+                ..Default::default()
             })
         );
     }
@@ -885,6 +1096,7 @@ mod tests {
                 captured: None,
                 stm: None,
                 pass: None,
+                ..Default::default()
             })
         );
     }
@@ -904,13 +1116,14 @@ mod tests {
                 captured: None,
                 stm: None,
                 pass: None,
+                ..Default::default()
             })
         );
 
         let processed = Query::from_raw(&query).unwrap();
         assert_eq!(
             processed,
-            Query::Rule(Rule {
+            Query::Rule(Box::new(Rule {
                 sub_fen: None,
                 material: None,
                 imbalance: None,
@@ -920,7 +1133,8 @@ mod tests {
                 captured: None,
                 stm: None,
                 pass: false,
-            })
+                ..Default::default()
+            }))
         );
     }
 
@@ -943,6 +1157,7 @@ mod tests {
                 captured: None,
                 stm: None,
                 pass: None,
+                ..Default::default()
             })
         );
     }
@@ -965,13 +1180,14 @@ mod tests {
                 captured: None,
                 stm: None,
                 pass: None,
+                ..Default::default()
             })
         );
 
         let processed = Query::from_raw(&query).unwrap();
         assert_eq!(
             processed,
-            Query::Rule(Rule {
+            Query::Rule(Box::new(Rule {
                 sub_fen: None,
                 material: Some(vec![
                     ColoredPieceCounts {
@@ -1010,7 +1226,8 @@ mod tests {
                 captured: None,
                 stm: None,
                 pass: false,
-            })
+                ..Default::default()
+            }))
         );
     }
 
@@ -1032,13 +1249,14 @@ mod tests {
                 captured: None,
                 stm: None,
                 pass: None,
+                ..Default::default()
             })
         );
 
         let processed = Query::from_raw(&query).unwrap();
         assert_eq!(
             processed,
-            Query::Rule(Rule {
+            Query::Rule(Box::new(Rule {
                 sub_fen: None,
                 material: None,
                 imbalance: Some(vec![
@@ -1077,7 +1295,8 @@ mod tests {
                 captured: None,
                 stm: None,
                 pass: false,
-            })
+                ..Default::default()
+            }))
         );
     }
 
@@ -1096,13 +1315,14 @@ mod tests {
                 captured: None,
                 stm: None,
                 pass: None,
+                ..Default::default()
             })
         );
 
         let processed = Query::from_raw(&query).unwrap();
         assert_eq!(
             processed,
-            Query::Rule(Rule {
+            Query::Rule(Box::new(Rule {
                 sub_fen: None,
                 material: None,
                 imbalance: Some(vec![ColoredPieceCounts {
@@ -1125,7 +1345,8 @@ mod tests {
                 captured: None,
                 stm: None,
                 pass: false,
-            })
+                ..Default::default()
+            }))
         );
     }
 
@@ -1147,13 +1368,14 @@ mod tests {
                 captured: None,
                 stm: None,
                 pass: None,
+                ..Default::default()
             })
         );
 
         let processed = Query::from_raw(&query).unwrap();
         assert_eq!(
             processed,
-            Query::Rule(Rule {
+            Query::Rule(Box::new(Rule {
                 sub_fen: None,
                 material: None,
                 imbalance: None,
@@ -1166,7 +1388,8 @@ mod tests {
                 captured: None,
                 stm: None,
                 pass: false,
-            })
+                ..Default::default()
+            }))
         );
     }
 
@@ -1185,13 +1408,14 @@ mod tests {
                 captured: Some("QR".to_string()),
                 stm: Some("black".to_string()),
                 pass: None,
+                ..Default::default()
             })
         );
 
         let processed = Query::from_raw(&query).unwrap();
         assert_eq!(
             processed,
-            Query::Rule(Rule {
+            Query::Rule(Box::new(Rule {
                 sub_fen: None,
                 material: None,
                 imbalance: None,
@@ -1208,7 +1432,8 @@ mod tests {
                 }),
                 stm: Some(false),
                 pass: false,
-            })
+                ..Default::default()
+            }))
         );
     }
 
@@ -1227,13 +1452,14 @@ mod tests {
                 captured: None,
                 stm: None,
                 pass: Some("".to_string()),
+                ..Default::default()
             })
         );
 
         let processed = Query::from_raw(&query).unwrap();
         assert_eq!(
             processed,
-            Query::Rule(Rule {
+            Query::Rule(Box::new(Rule {
                 sub_fen: None,
                 material: None,
                 imbalance: None,
@@ -1243,8 +1469,97 @@ mod tests {
                 captured: None,
                 stm: None,
                 pass: true,
+                ..Default::default()
+            }))
+        );
+    }
+
+    #[test]
+    fn test_parse_rule11() {
+        let query = RawQuery::parse(
+            br#"{ "isolated-pawns": ["1v0", "0v1"], "passed-pawns": "1+v", "pawn-islands": "v2",
+                "open-files": "cd", "opposite-colored-bishops": true, "castled": "Kq" }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            query,
+            RawQuery::Rule(RawRule {
+                sub_fen: None,
+                material: None,
+                imbalance: None,
+                white_move: None,
+                black_move: None,
+                moved: None,
+                captured: None,
+                stm: None,
+                pass: None,
+                isolated_pawns: Some(Strings::Multiple(vec![
+                    "1v0".to_string(),
+                    "0v1".to_string()
+                ])),
+                doubled_pawns: None,
+                passed_pawns: Some(Strings::Single("1+v".to_string())),
+                pawn_islands: Some(Strings::Single("v2".to_string())),
+                open_files: Some("cd".to_string()),
+                opposite_colored_bishops: Some(true),
+                castled: Some("Kq".to_string()),
             })
         );
+
+        let processed = Query::from_raw(&query).unwrap();
+        assert_eq!(
+            processed,
+            Query::Rule(Box::new(Rule {
+                sub_fen: None,
+                material: None,
+                imbalance: None,
+                white_move: None,
+                black_move: None,
+                moved: None,
+                captured: None,
+                stm: None,
+                pass: false,
+                isolated_pawns: Some(vec![
+                    PawnCounts {
+                        white: Count::Exactly(1),
+                        black: Count::Exactly(0),
+                    },
+                    PawnCounts {
+                        white: Count::Exactly(0),
+                        black: Count::Exactly(1),
+                    },
+                ]),
+                doubled_pawns: None,
+                passed_pawns: Some(vec![PawnCounts {
+                    white: Count::AtLeast(1),
+                    black: Count::Any,
+                }]),
+                pawn_islands: Some(vec![PawnCounts {
+                    white: Count::Any,
+                    black: Count::Exactly(2),
+                }]),
+                open_files: Some(0b1100),
+                opposite_colored_bishops: Some(true),
+                castled: Some(Castled {
+                    white: Some(CastlingSide::KingSide),
+                    black: Some(CastlingSide::QueenSide),
+                }),
+            }))
+        );
+    }
+
+    #[test]
+    fn test_parse_rule12() {
+        for query in [
+            br#"{ "doubled-pawns": "1" }"#.as_slice(),
+            br#"{ "doubled-pawns": "xv1" }"#,
+            br#"{ "open-files": "i" }"#,
+            br#"{ "castled": "KQ" }"#,
+            br#"{ "castled": "" }"#,
+        ] {
+            let raw = RawQuery::parse(query).unwrap();
+            assert!(Query::from_raw(&raw).is_err());
+        }
     }
 
     #[test]
@@ -1269,6 +1584,7 @@ mod tests {
                         captured: None,
                         stm: None,
                         pass: None,
+                        ..Default::default()
                     }),
                     RawSequenceElement::Rule(RawRule {
                         sub_fen: Some(Strings::Single("8/8/8/8/2B5/8/8/8".to_string())),
@@ -1280,6 +1596,7 @@ mod tests {
                         captured: None,
                         stm: None,
                         pass: None,
+                        ..Default::default()
                     }),
                     RawSequenceElement::Rule(RawRule {
                         sub_fen: Some(Strings::Single("8/8/8/8/8/5B2/8/8".to_string())),
@@ -1291,6 +1608,7 @@ mod tests {
                         captured: None,
                         stm: None,
                         pass: None,
+                        ..Default::default()
                     }),
                 ]
             })
@@ -1316,6 +1634,7 @@ mod tests {
                         captured: None,
                         stm: None,
                         pass: None,
+                        ..Default::default()
                     },
                     RawRule {
                         sub_fen: None,
@@ -1327,6 +1646,7 @@ mod tests {
                         captured: None,
                         stm: None,
                         pass: None,
+                        ..Default::default()
                     },
                     RawRule {
                         sub_fen: None,
@@ -1338,6 +1658,7 @@ mod tests {
                         captured: None,
                         stm: None,
                         pass: None,
+                        ..Default::default()
                     },
                 ]
             })
@@ -1363,6 +1684,7 @@ mod tests {
                         captured: Some("".to_string()),
                         stm: None,
                         pass: None,
+                        ..Default::default()
                     },
                     RawRule {
                         sub_fen: None,
@@ -1374,6 +1696,7 @@ mod tests {
                         captured: Some("Q".to_string()),
                         stm: Some("white".to_string()),
                         pass: None,
+                        ..Default::default()
                     },
                     RawRule {
                         sub_fen: None,
@@ -1385,6 +1708,7 @@ mod tests {
                         captured: Some("".to_string()),
                         stm: None,
                         pass: None,
+                        ..Default::default()
                     },
                 ]
             })
@@ -1411,6 +1735,7 @@ mod tests {
                         captured: None,
                         stm: None,
                         pass: None,
+                        ..Default::default()
                     },
                     RawRule {
                         sub_fen: None,
@@ -1422,6 +1747,7 @@ mod tests {
                         captured: None,
                         stm: None,
                         pass: Some("".to_string()),
+                        ..Default::default()
                     },
                     RawRule {
                         sub_fen: None,
@@ -1433,6 +1759,7 @@ mod tests {
                         captured: None,
                         stm: None,
                         pass: None,
+                        ..Default::default()
                     },
                 ]
             })
@@ -1462,6 +1789,7 @@ mod tests {
                         captured: None,
                         stm: None,
                         pass: None,
+                        ..Default::default()
                     }),
                     RawSequenceElement::Streak(RawStreak {
                         streak: vec![
@@ -1475,6 +1803,7 @@ mod tests {
                                 captured: None,
                                 stm: None,
                                 pass: None,
+                                ..Default::default()
                             },
                             RawRule {
                                 sub_fen: None,
@@ -1486,6 +1815,7 @@ mod tests {
                                 captured: None,
                                 stm: None,
                                 pass: None,
+                                ..Default::default()
                             },
                             RawRule {
                                 sub_fen: None,
@@ -1497,6 +1827,7 @@ mod tests {
                                 captured: None,
                                 stm: None,
                                 pass: None,
+                                ..Default::default()
                             },
                         ]
                     }),
@@ -1510,6 +1841,7 @@ mod tests {
                         captured: None,
                         stm: None,
                         pass: None,
+                        ..Default::default()
                     }),
                 ]
             })
@@ -1,22 +1,152 @@
 use crate::ffi::Subfen;
 use aix_chess_compression::{Decode, Decoder, EncodedGame};
-use shakmaty::{fen::ParseFenError, Board, Position};
+use shakmaty::{
+    fen::ParseFenError, Bitboard, Board, Color, File, Piece, Position, Rank, Role, Square,
+};
 
+/// Parses a sub-FEN: the piece placement part of a FEN, where a piece requires that piece on
+/// its square and a digit skips that many squares. Besides pieces, a square can be:
+///
+/// - `?`: anything, like `1`.
+/// - `-`: an empty square.
+/// - `*`: any piece.
+/// - `A`/`a`: any white/black piece.
+/// - `M`/`m`: a white/black minor piece (knight or bishop).
+///
+/// A `!` before a piece, `A`, `M`, `*` or `-` negates it: `!P` means no white pawn, `!*` is the
+/// same as `-`.
 pub fn try_parse(subfen: &[u8]) -> Result<Subfen, ParseFenError> {
-    let board = Board::from_ascii_board_fen(subfen)?;
-    Ok(Subfen {
-        white: board.white().0,
-        black: board.black().0,
-        king: board.kings().0,
-        queen: board.queens().0,
-        rook: board.rooks().0,
-        bishop: board.bishops().0,
-        knight: board.knights().0,
-        pawn: board.pawns().0,
-    })
+    let mut parsed = Subfen::default();
+
+    let mut rank = 7i8;
+    let mut file = 0i8;
+
+    let mut iter = subfen.iter().copied().peekable();
+
+    while let Some(ch) = iter.next() {
+        if ch == b'/' && file == 8 {
+            file = 0;
+            rank -= 1;
+            if rank < 0 {
+                return Err(ParseFenError::InvalidBoard);
+            }
+            continue;
+        }
+        if (b'1'..=b'8').contains(&ch) {
+            file += (ch - b'0') as i8;
+            if file > 8 {
+                return Err(ParseFenError::InvalidBoard);
+            }
+            continue;
+        }
+
+        let (Ok(f), Ok(r)) = (File::try_from(file), Rank::try_from(rank)) else {
+            return Err(ParseFenError::InvalidBoard);
+        };
+        let sq = Bitboard::from(Square::from_coords(f, r));
+        file += 1;
+
+        let negated = ch == b'!';
+        let ch = if negated {
+            iter.next().ok_or(ParseFenError::InvalidBoard)?
+        } else {
+            ch
+        };
+
+        match (ch, negated) {
+            (b'?', false) => {}
+            (b'-', false) | (b'*', true) => parsed.empty |= sq.0,
+            (b'*', false) | (b'-', true) => parsed.occupied |= sq.0,
+            (b'A' | b'a' | b'M' | b'm', _) => {
+                let color = Color::from_white(ch.is_ascii_uppercase());
+                let mut masks = Masks::of(&mut parsed, negated);
+                *masks.color(color) |= sq.0;
+                if ch.eq_ignore_ascii_case(&b'M') {
+                    *masks.minor |= sq.0;
+                }
+            }
+            _ => {
+                let piece = Piece::from_char(char::from(ch)).ok_or(ParseFenError::InvalidBoard)?;
+                let mut masks = Masks::of(&mut parsed, negated);
+                *masks.color(piece.color) |= sq.0;
+                *masks.role(piece.role) |= sq.0;
+                // promoted pieces are accepted like in a FEN, but not distinguished
+                if !negated && iter.peek() == Some(&b'~') {
+                    iter.next();
+                }
+            }
+        }
+    }
+
+    if rank == 0 && file == 8 {
+        Ok(parsed)
+    } else {
+        Err(ParseFenError::InvalidBoard)
+    }
+}
+
+/// The masks of a sub-FEN that a token sets, either the required or the negated ones.
+struct Masks<'a> {
+    white: &'a mut u64,
+    black: &'a mut u64,
+    king: &'a mut u64,
+    queen: &'a mut u64,
+    rook: &'a mut u64,
+    bishop: &'a mut u64,
+    knight: &'a mut u64,
+    pawn: &'a mut u64,
+    minor: &'a mut u64,
 }
 
-pub fn matches(subfen: Subfen, game: &[u8]) -> Result<bool, crate::ffi::DecodeError> {
+impl<'a> Masks<'a> {
+    fn of(subfen: &'a mut Subfen, negated: bool) -> Masks<'a> {
+        if negated {
+            Masks {
+                white: &mut subfen.not_white,
+                black: &mut subfen.not_black,
+                king: &mut subfen.not_king,
+                queen: &mut subfen.not_queen,
+                rook: &mut subfen.not_rook,
+                bishop: &mut subfen.not_bishop,
+                knight: &mut subfen.not_knight,
+                pawn: &mut subfen.not_pawn,
+                minor: &mut subfen.not_minor,
+            }
+        } else {
+            Masks {
+                white: &mut subfen.white,
+                black: &mut subfen.black,
+                king: &mut subfen.king,
+                queen: &mut subfen.queen,
+                rook: &mut subfen.rook,
+                bishop: &mut subfen.bishop,
+                knight: &mut subfen.knight,
+                pawn: &mut subfen.pawn,
+                minor: &mut subfen.minor,
+            }
+        }
+    }
+
+    fn color(&mut self, color: Color) -> &mut u64 {
+        match color {
+            Color::White => self.white,
+            Color::Black => self.black,
+        }
+    }
+
+    fn role(&mut self, role: Role) -> &mut u64 {
+        match role {
+            Role::King => self.king,
+            Role::Queen => self.queen,
+            Role::Rook => self.rook,
+            Role::Bishop => self.bishop,
+            Role::Knight => self.knight,
+            Role::Pawn => self.pawn,
+        }
+    }
+}
+
+pub fn matches(subfen: Subfen, game: &[u8]) -> Result<bool, crate::ffi::DecodeFailure> {
     let encoded = EncodedGame::from_bytes(game)?;
     let decoder = Decoder::new(&encoded);
     for position in decoder.into_iter_positions() {
@@ -31,7 +161,8 @@ pub fn matches(subfen: Subfen, game: &[u8]) -> Result<bool, crate::ffi::DecodeEr
 }
 
 pub fn matches_board(subfen: &Subfen, board: &Board) -> bool {
-    (board.white().0 & subfen.white) == subfen.white
+    let minors = board.knights().0 | board.bishops().0;
+    let required = (board.white().0 & subfen.white) == subfen.white
         && (board.black().0 & subfen.black) == subfen.black
         && (board.kings().0 & subfen.king) == subfen.king
         && (board.queens().0 & subfen.queen) == subfen.queen
@@ -39,4 +170,34 @@ pub fn matches_board(subfen: &Subfen, board: &Board) -> bool {
         && (board.bishops().0 & subfen.bishop) == subfen.bishop
         && (board.knights().0 & subfen.knight) == subfen.knight
         && (board.pawns().0 & subfen.pawn) == subfen.pawn
+        && (minors & subfen.minor) == subfen.minor
+        && (board.occupied().0 & subfen.occupied) == subfen.occupied
+        && (board.occupied().0 & subfen.empty) == 0;
+    if !required {
+        return false;
+    }
+
+    // a negated square matches if any of its masks does not hold
+    let negated = subfen.not_white
+        | subfen.not_black
+        | subfen.not_king
+        | subfen.not_queen
+        | subfen.not_rook
+        | subfen.not_bishop
+        | subfen.not_knight
+        | subfen.not_pawn
+        | subfen.not_minor;
+    if negated == 0 {
+        return true;
+    }
+    let holds = (board.white().0 | !subfen.not_white)
+        & (board.black().0 | !subfen.not_black)
+        & (board.kings().0 | !subfen.not_king)
+        & (board.queens().0 | !subfen.not_queen)
+        & (board.rooks().0 | !subfen.not_rook)
+        & (board.bishops().0 | !subfen.not_bishop)
+        & (board.knights().0 | !subfen.not_knight)
+        & (board.pawns().0 | !subfen.not_pawn)
+        & (minors | !subfen.not_minor);
+    holds & negated == 0
 }
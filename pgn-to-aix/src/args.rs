@@ -3,9 +3,9 @@ use clap::Parser;
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 pub(super) struct Args {
-    /// Path to the input file, PGN or zstd-compressed PGN (.zst)
-    #[arg(short, long)]
-    pub input: String,
+    /// Paths to the input files, PGN or PGN compressed with zstd, gzip, bzip2 or xz (detected from the file contents). Accepts file name patterns with * and ?, and - for standard input.
+    #[arg(short, long, num_args = 1.., required = true)]
+    pub input: Vec<String>,
 
     /// Path to the output file(s) (without extension)
     #[arg(short, long)]
@@ -19,10 +19,22 @@ pub(super) struct Args {
     #[arg(long, default_value_t = false, action = clap::ArgAction::SetTrue)]
     pub lichess: bool,
 
+    /// Parse the input file as chess.com game archive, with typed columns like --lichess.
+    #[arg(long, default_value_t = false, action = clap::ArgAction::SetTrue)]
+    pub chesscom: bool,
+
     /// Comma-separated list of PGN heaeders to include in the output database. Only relevant when not using --lichess.
     #[arg(long, value_delimiter = ',')]
     pub headers: Option<Vec<String>>,
 
+    /// Path to a JSON schema file that maps PGN tags to typed columns, e.g. {"columns": [{"tag": "WhiteElo", "column": "white_rating", "type": "elo"}]}. Types are varchar (default), integer, elo, date and time_control. Only relevant when not using --lichess or --chesscom.
+    #[arg(long)]
+    pub schema: Option<String>,
+
+    /// Built-in schema for PGN files of a common source. Only relevant when not using --lichess or --chesscom.
+    #[arg(long, value_enum)]
+    pub schema_preset: Option<SchemaPreset>,
+
     /// Skip exporting to Parquet file
     #[arg(long, default_value_t = false, action = clap::ArgAction::SetTrue)]
     pub skip_parquet_export: bool,
@@ -35,13 +47,69 @@ pub(super) struct Args {
     #[arg(long, default_value_t = 19, value_parser=clap::value_parser!(u8).range(1..=22),)]
     pub parquet_compression_level: u8,
 
+    /// Write the games to Parquet files of --games-per-file games in the <OUTPUT>.parquet directory, without a DuckDB file. The games of a file are kept in memory until it is written. Rejected games are written to <OUTPUT>.rejected.parquet.
+    #[arg(long, default_value_t = false, action = clap::ArgAction::SetTrue)]
+    pub direct_parquet: bool,
+
+    /// Number of games per Parquet file with --direct-parquet, which bounds the memory used
+    #[arg(long, default_value_t = 100_000, value_parser = clap::value_parser!(u64).range(1..))]
+    pub games_per_file: u64,
+
+    /// Number of rows per row group of the output Parquet files (DuckDB's default if not set)
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub row_group_size: Option<u32>,
+
+    /// SQL expression to sort the games of the output Parquet file(s) by, e.g. utc_timestamp. With --direct-parquet, every file is sorted separately.
+    #[arg(long)]
+    pub sort_by: Option<String>,
+
+    /// Partition the output Parquet files into <column>=<value> directories by the month of utc_timestamp or the time control class (ultrabullet, bullet, blitz, rapid, classical or correspondence)
+    #[arg(long, value_enum)]
+    pub partition_by: Option<PartitionBy>,
+
     /// Optional DuckDB memory limit in GB
     #[arg(long)]
     pub duckdb_memory_limit_gb: Option<u16>,
 
-    /// Set this flag to continue processing even if an invalid move is encountered in a game, rather than exiting with an error. The game with the invalid move will end right before the invalid move.
+    /// Games with an invalid move are recorded in the rejected_games table. Set this flag to also store them in the games table, ending right before the invalid move.
     #[arg(long, default_value_t = false, action = clap::ArgAction::SetTrue)]
     pub continue_on_invalid_move: bool,
+
+    /// Embed a position snapshot every N plies in movedata, so that positions late in long games can be looked up without decoding all moves before them. Games with keyframes are stored with the Medium compression level when high is used.
+    #[arg(long, value_parser=clap::value_parser!(u16).range(1..))]
+    pub keyframe_interval: Option<u16>,
+
+    /// Number of threads used to parse and encode games. The input is split into chunks of games that are converted in parallel.
+    #[arg(long, default_value_t = 1, value_parser=clap::value_parser!(u16).range(1..))]
+    pub threads: u16,
+
+    /// Append games to the database as soon as their chunk is converted, rather than in input order. Only relevant when --threads is greater than 1.
+    #[arg(long, default_value_t = false, action = clap::ArgAction::SetTrue)]
+    pub unordered: bool,
+
+    /// Continue an interrupted conversion into the existing output DuckDB file, from the last committed batch of games. The input file and options must be the same as for the interrupted conversion.
+    #[arg(long, default_value_t = false, action = clap::ArgAction::SetTrue)]
+    pub resume: bool,
+
+    /// Add the games of the input file to the existing output DuckDB file. With --lichess, games whose lichess_id is already in the database or earlier in the input are skipped.
+    #[arg(long, default_value_t = false, action = clap::ArgAction::SetTrue)]
+    pub append: bool,
+
+    /// Add a source_file column with the input path each game was read from.
+    #[arg(long, default_value_t = false, action = clap::ArgAction::SetTrue)]
+    pub source_file_column: bool,
+
+    /// Store the NAGs, comments and variations of every game in an annotations column. Render them with the to_pgn_annotated function of the DuckDB extension.
+    #[arg(long, default_value_t = false, action = clap::ArgAction::SetTrue)]
+    pub annotations: bool,
+
+    /// Comma-separated list of PGN comment commands to extract into their own columns, next to [%clk] and [%eval]: clk-ms (clocks in milliseconds), emt (elapsed move times), clkc (correspondence clocks), depth (engine depths), csl (highlighted squares) and cal (arrows).
+    #[arg(long, value_delimiter = ',', value_enum)]
+    pub comment_commands: Vec<CommentCommand>,
+
+    /// Write a JSON summary of the conversion to this path: games written, rejected (by reason) and truncated, input bytes, elapsed time, games per second and the movedata size per compression level.
+    #[arg(long)]
+    pub stats_json: Option<String>,
 }
 
 #[derive(clap::ValueEnum, Clone, Debug)]
@@ -51,6 +119,28 @@ pub(super) enum CompressionLevel {
     High,
 }
 
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub(super) enum PartitionBy {
+    Month,
+    TimeControlClass,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub(super) enum SchemaPreset {
+    Twic,
+    Fide,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub(super) enum CommentCommand {
+    ClkMs,
+    Emt,
+    Clkc,
+    Depth,
+    Csl,
+    Cal,
+}
+
 #[derive(clap::ValueEnum, Clone, Debug, PartialEq, Eq)]
 pub(super) enum ParquetCompression {
     Uncompressed,
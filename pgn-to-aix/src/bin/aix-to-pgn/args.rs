@@ -0,0 +1,21 @@
+use clap::Parser;
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Exports an Aix-compatible Parquet or DuckDB file back to PGN", long_about = None)]
+pub(super) struct Args {
+    /// Path to the input file, Parquet (.parquet) or DuckDB (.duckdb) generated by pgn-to-aix
+    #[arg(short, long)]
+    pub input: String,
+
+    /// Path to the output PGN file, zstd-compressed if it has the .zst extension. Writes to standard output if omitted.
+    #[arg(short, long)]
+    pub output: Option<String>,
+
+    /// SQL condition to select the games to export, e.g. "white_rating > 2500"
+    #[arg(long = "where")]
+    pub where_clause: Option<String>,
+
+    /// Compression level for zstd output
+    #[arg(long, default_value_t = 19, value_parser=clap::value_parser!(i32).range(1..=22),)]
+    pub zstd_level: i32,
+}
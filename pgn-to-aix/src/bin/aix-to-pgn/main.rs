@@ -0,0 +1,212 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use aix_chess_compression::{Annotations, EncodedGame, PgnWriter};
+use clap::Parser;
+use duckdb::Connection;
+use duckdb::types::Value;
+
+mod args;
+
+/// Columns written by pgn-to-aix next to the header columns.
+const GAME_COLUMNS: [&str; 16] = [
+    "movedata",
+    "clocks_white",
+    "clocks_black",
+    "evals",
+    "ply_count",
+    "source_file",
+    "annotations",
+    "clocks_white_ms",
+    "clocks_black_ms",
+    "emt_white",
+    "emt_black",
+    "clkc_white",
+    "clkc_black",
+    "eval_depths",
+    "csl",
+    "cal",
+];
+
+/// Number of columns before the tag columns in the export queries.
+const TAGS_OFFSET: usize = 7;
+
+fn main() {
+    let args = args::Args::parse();
+
+    let input_path = std::path::Path::new(&args.input);
+
+    if !input_path.exists() {
+        eprintln!("Input file '{}' does not exist.", input_path.display());
+        std::process::exit(1);
+    }
+
+    let input_literal = format!("'{}'", args.input.replace('\'', "''"));
+    let db = Connection::open_in_memory().unwrap();
+    match input_path.extension().and_then(|s| s.to_str()) {
+        Some("parquet") => db
+            .execute_batch(&format!(
+                "CREATE VIEW games AS FROM read_parquet({input_literal});"
+            ))
+            .unwrap(),
+        Some("duckdb") => db
+            .execute_batch(&format!(
+                "ATTACH {input_literal} AS aix (READ_ONLY); CREATE VIEW games AS FROM aix.games;"
+            ))
+            .unwrap(),
+        _ => {
+            eprintln!("Input file must be a Parquet (.parquet) or DuckDB (.duckdb) file.");
+            std::process::exit(1);
+        }
+    }
+
+    let columns: Vec<String> = db
+        .prepare("SELECT column_name FROM (DESCRIBE games)")
+        .unwrap()
+        .query_map([], |row| row.get(0))
+        .unwrap()
+        .map(|c| c.unwrap())
+        .collect();
+
+    let where_sql = args
+        .where_clause
+        .as_ref()
+        .map(|w| format!("WHERE {w}"))
+        .unwrap_or_default();
+
+    // columns written by pgn-to-aix only with some options are exported as NULL when missing
+    let optional_column = |name: &str, sql_type: &str| {
+        if columns.iter().any(|c| c == name) {
+            name.to_owned()
+        } else {
+            format!("NULL::{sql_type}")
+        }
+    };
+
+    let query = if columns.iter().any(|c| c == "lichess_id") {
+        include_str!("../../sql/export-lichess-database.sql").to_owned()
+    } else if columns.iter().any(|c| c == "chesscom_id") {
+        include_str!("../../sql/export-chesscom-database.sql").to_owned()
+    } else {
+        let headers_sql = columns
+            .iter()
+            .filter(|c| !GAME_COLUMNS.contains(&c.as_str()))
+            .map(|c| {
+                let quoted = format!("\"{}\"", c.replace('"', "\"\""));
+                format!("CAST({quoted} AS VARCHAR) AS {quoted}")
+            })
+            .collect::<Vec<_>>()
+            .join(",\n  ");
+        include_str!("../../sql/export-other-database.sql").replace("$HEADERS", &headers_sql)
+    };
+    let query = query
+        .replace("$ANNOTATIONS", &optional_column("annotations", "BLOB"))
+        .replace(
+            "$CLOCKS_WHITE_MS",
+            &optional_column("clocks_white_ms", "UBIGINT[]"),
+        )
+        .replace(
+            "$CLOCKS_BLACK_MS",
+            &optional_column("clocks_black_ms", "UBIGINT[]"),
+        )
+        .replace("$WHERE", &where_sql);
+
+    let mut out: Box<dyn Write> = match &args.output {
+        Some(output) => {
+            let file = File::create(output).unwrap();
+            if output.ends_with(".zst") {
+                Box::new(
+                    zstd::Encoder::new(file, args.zstd_level)
+                        .unwrap()
+                        .auto_finish(),
+                )
+            } else {
+                Box::new(BufWriter::new(file))
+            }
+        }
+        None => Box::new(BufWriter::new(std::io::stdout().lock())),
+    };
+
+    let mut stmt = db.prepare(query.trim_end().trim_end_matches(';')).unwrap();
+    let mut rows = stmt.query([]).unwrap();
+    let tag_names: Vec<String> = rows
+        .as_ref()
+        .expect("statement was executed")
+        .column_names()
+        .into_iter()
+        .skip(TAGS_OFFSET)
+        .collect();
+
+    let mut count: u32 = 0;
+    while let Some(row) = rows.next().unwrap() {
+        let movedata: Vec<u8> = row.get(0).unwrap();
+        let clocks_white = list_values(row.get(1).unwrap(), |v| match v {
+            Value::USmallInt(clock) => Some(clock),
+            _ => None,
+        });
+        let clocks_black = list_values(row.get(2).unwrap(), |v| match v {
+            Value::USmallInt(clock) => Some(clock),
+            _ => None,
+        });
+        let evals = list_values(row.get(3).unwrap(), |v| match v {
+            Value::SmallInt(eval) => Some(eval),
+            _ => None,
+        });
+        let annotations = row
+            .get::<_, Option<Vec<u8>>>(4)
+            .unwrap()
+            .map(|bytes| {
+                Annotations::from_bytes(&bytes).unwrap_or_else(|e| {
+                    eprintln!("Game {} has invalid annotations: {e}", count + 1);
+                    std::process::exit(1);
+                })
+            })
+            .unwrap_or_default();
+        let clocks_white_ms = list_values(row.get(5).unwrap(), |v| match v {
+            Value::UBigInt(clock) => Some(Some(clock)),
+            _ => Some(None),
+        });
+        let clocks_black_ms = list_values(row.get(6).unwrap(), |v| match v {
+            Value::UBigInt(clock) => Some(Some(clock)),
+            _ => Some(None),
+        });
+
+        let mut tags = vec![];
+        for (i, name) in tag_names.iter().enumerate() {
+            if let Some(value) = row.get::<_, Option<String>>(TAGS_OFFSET + i).unwrap() {
+                tags.push((name.as_str(), value));
+            }
+        }
+
+        let game = EncodedGame::from_bytes(&movedata).unwrap_or_else(|e| {
+            eprintln!("Game {} has invalid movedata: {e}", count + 1);
+            std::process::exit(1);
+        });
+        PgnWriter::new(&game)
+            .with_tags(tags)
+            .with_clocks(&clocks_white, &clocks_black)
+            .with_clocks_ms(&clocks_white_ms, &clocks_black_ms)
+            .with_evals(&evals)
+            .with_annotations(&annotations)
+            .write(&mut out)
+            .unwrap_or_else(|e| {
+                eprintln!("Cannot export game {}: {e}", count + 1);
+                std::process::exit(1);
+            });
+
+        count += 1;
+        if count.is_multiple_of(10000) {
+            eprintln!("{} done", count);
+        }
+    }
+
+    out.flush().unwrap();
+}
+
+/// Converts a `LIST` value into a vector, `NULL` into an empty vector.
+fn list_values<T>(value: Value, f: impl Fn(Value) -> Option<T>) -> Vec<T> {
+    match value {
+        Value::List(values) => values.into_iter().filter_map(f).collect(),
+        _ => vec![],
+    }
+}
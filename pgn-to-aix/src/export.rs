@@ -0,0 +1,115 @@
+use std::path::{Path, PathBuf};
+
+use duckdb::Connection;
+
+/// Options of the Parquet files written by pgn-to-aix.
+#[derive(Clone)]
+pub struct ParquetOptions {
+    /// The `COMPRESSION` (and `COMPRESSION_LEVEL`) options of `COPY`.
+    pub compression: String,
+    pub row_group_size: Option<u32>,
+    /// SQL expression to sort the games of every file by.
+    pub sort_by: Option<String>,
+    pub partition_by: Option<Partition>,
+}
+
+/// Column that the Parquet output is partitioned by, in Hive-style `column=value` directories.
+#[derive(Clone, Copy, Debug)]
+pub enum Partition {
+    /// Month of `utc_timestamp`, e.g. `month=2024-01`.
+    Month,
+    /// Lichess speed of the time control (`bullet`, `blitz`, ...), estimated from
+    /// `time_initial + 40 * time_increment`. Games without a time control are `correspondence`.
+    TimeControlClass,
+}
+
+impl Partition {
+    pub fn column(self) -> &'static str {
+        match self {
+            Partition::Month => "month",
+            Partition::TimeControlClass => "time_control_class",
+        }
+    }
+
+    /// Returns the columns of the `games` table that the partition is computed from.
+    pub fn source_columns(self) -> &'static [&'static str] {
+        match self {
+            Partition::Month => &["utc_timestamp"],
+            Partition::TimeControlClass => &["time_initial", "time_increment"],
+        }
+    }
+
+    fn expression(self) -> &'static str {
+        match self {
+            Partition::Month => "strftime(utc_timestamp, '%Y-%m')",
+            Partition::TimeControlClass => include_str!("sql/time-control-class.sql"),
+        }
+    }
+}
+
+impl ParquetOptions {
+    /// Copies the rows of `table` to `path`: a Parquet file, or a directory of partitions with
+    /// `partition_by`, whose files are named after `file_pattern` (with `{i}` for the index of
+    /// the file in its partition).
+    pub fn copy(&self, db: &Connection, table: &str, path: &Path, file_pattern: &str) {
+        let mut select = "SELECT *".to_owned();
+        let mut options = format!("FORMAT PARQUET, PARQUET_VERSION v2, {}", self.compression);
+        if let Some(partition) = self.partition_by {
+            select += &format!(", {} AS {}", partition.expression(), partition.column());
+            options += &format!(
+                ", PARTITION_BY ({}), OVERWRITE_OR_IGNORE, FILENAME_PATTERN '{file_pattern}'",
+                partition.column()
+            );
+        }
+        select += &format!(" FROM {table}");
+        if let Some(sort_by) = &self.sort_by {
+            select += &format!(" ORDER BY {sort_by}");
+        }
+        if let Some(row_group_size) = self.row_group_size {
+            options += &format!(", ROW_GROUP_SIZE {row_group_size}");
+        }
+
+        db.execute(
+            &format!("COPY ({select}) TO ? ({options})"),
+            [path.to_str().unwrap()],
+        )
+        .unwrap();
+    }
+}
+
+/// Writes the games of a table to numbered Parquet files in a directory, for `--direct-parquet`.
+/// The table holds the games of one file until it is full.
+pub struct ParquetSink {
+    dir: PathBuf,
+    games_per_file: u64,
+    options: ParquetOptions,
+    files: u32,
+}
+
+impl ParquetSink {
+    pub fn new(dir: PathBuf, games_per_file: u64, options: ParquetOptions) -> ParquetSink {
+        ParquetSink {
+            dir,
+            games_per_file,
+            options,
+            files: 0,
+        }
+    }
+
+    pub fn games_per_file(&self) -> u64 {
+        self.games_per_file
+    }
+
+    /// Writes all rows of `table` to the next Parquet file (or the next file of every
+    /// partition).
+    pub fn write(&mut self, db: &Connection, table: &str) {
+        let pattern = format!("part-{:05}-{{i}}", self.files);
+        if self.options.partition_by.is_some() {
+            self.options.copy(db, table, &self.dir, &pattern);
+        } else {
+            let path = self.dir.join(format!("part-{:05}.parquet", self.files));
+            self.options.copy(db, table, &path, &pattern);
+        }
+        self.files += 1;
+    }
+}
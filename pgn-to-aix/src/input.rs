@@ -0,0 +1,203 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Input path that reads from standard input.
+pub const STDIN: &str = "-";
+
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const BZIP2_MAGIC: &[u8] = b"BZh";
+const XZ_MAGIC: &[u8] = &[0xfd, b'7', b'z', b'X', b'Z', 0x00];
+
+/// Expands the input arguments into a list of input paths. Arguments with `*` or `?` in their
+/// file name are matched against the files in their directory, in lexicographic order; other
+/// arguments, and `-` for standard input, are kept as is.
+pub fn expand_inputs(args: &[String]) -> Result<Vec<String>, String> {
+    let mut inputs = vec![];
+    for arg in args {
+        let path = Path::new(arg);
+        let Some(pattern) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .filter(|name| name.contains(['*', '?']))
+        else {
+            if arg != STDIN && !path.exists() {
+                return Err(format!("Input file '{arg}' does not exist."));
+            }
+            inputs.push(arg.clone());
+            continue;
+        };
+
+        let dir = path.parent().filter(|p| !p.as_os_str().is_empty());
+        let entries = std::fs::read_dir(dir.unwrap_or(Path::new(".")))
+            .map_err(|e| format!("Cannot read directory of input pattern '{arg}': {e}"))?;
+        let mut matches = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_ok_and(|t| !t.is_dir()))
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|name| matches_pattern(pattern.as_bytes(), name.as_bytes()))
+            .map(|name| match dir {
+                Some(dir) => dir.join(name).to_string_lossy().into_owned(),
+                None => name,
+            })
+            .collect::<Vec<_>>();
+        if matches.is_empty() {
+            return Err(format!("No input files match '{arg}'."));
+        }
+
+        matches.sort();
+        inputs.append(&mut matches);
+    }
+
+    Ok(inputs)
+}
+
+/// Matches a file name against a pattern where `*` matches any sequence of characters and `?`
+/// matches a single character.
+fn matches_pattern(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((b'*', rest)) => (0..=name.len()).any(|i| matches_pattern(rest, &name[i..])),
+        Some((b'?', rest)) => !name.is_empty() && matches_pattern(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && matches_pattern(rest, &name[1..]),
+    }
+}
+
+/// Opens an input path, or standard input for `-`, and decompresses it according to its magic
+/// bytes: zstd, gzip, bzip2 or xz. The number of bytes read from the input before decompression
+/// is added to `bytes_read`.
+pub fn open_input(input: &str, bytes_read: Arc<AtomicU64>) -> io::Result<Box<dyn Read + Send>> {
+    let raw: Box<dyn Read + Send> = if input == STDIN {
+        Box::new(io::stdin())
+    } else {
+        Box::new(File::open(input)?)
+    };
+
+    let mut reader = BufReader::new(CountingReader {
+        inner: raw,
+        bytes_read,
+    });
+    let magic = reader.fill_buf()?;
+    Ok(if magic.starts_with(ZSTD_MAGIC) {
+        Box::new(zstd::Decoder::with_buffer(reader)?)
+    } else if magic.starts_with(GZIP_MAGIC) {
+        Box::new(flate2::bufread::MultiGzDecoder::new(reader))
+    } else if magic.starts_with(BZIP2_MAGIC) {
+        Box::new(bzip2::bufread::MultiBzDecoder::new(reader))
+    } else if magic.starts_with(XZ_MAGIC) {
+        Box::new(liblzma::bufread::XzDecoder::new_multi_decoder(reader))
+    } else {
+        Box::new(reader)
+    })
+}
+
+/// Returns the size of an input path, or `None` for standard input.
+pub fn input_size(input: &str) -> Option<u64> {
+    if input == STDIN {
+        return None;
+    }
+    std::fs::metadata(input).ok().map(|m| m.len())
+}
+
+/// Counts the bytes read from a reader.
+struct CountingReader<R> {
+    inner: R,
+    bytes_read: Arc<AtomicU64>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.bytes_read.fetch_add(n as u64, Ordering::Relaxed);
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_literal_names() {
+        assert!(matches_pattern(b"games.pgn", b"games.pgn"));
+        assert!(!matches_pattern(b"games.pgn", b"games.pgn.zst"));
+        assert!(!matches_pattern(b"games.pgn", b"games.pg"));
+        assert!(matches_pattern(b"", b""));
+        assert!(!matches_pattern(b"", b"a"));
+    }
+
+    #[test]
+    fn matches_wildcards() {
+        assert!(matches_pattern(b"*.pgn", b"games.pgn"));
+        assert!(matches_pattern(b"*.pgn", b".pgn"));
+        assert!(!matches_pattern(b"*.pgn", b"games.pgn.zst"));
+        assert!(matches_pattern(
+            b"lichess_*_2024-??.pgn.zst",
+            b"lichess_db_2024-01.pgn.zst"
+        ));
+        assert!(!matches_pattern(
+            b"lichess_*_2024-??.pgn.zst",
+            b"lichess_db_2024-1.pgn.zst"
+        ));
+        assert!(matches_pattern(b"a*b*c", b"aXbYbZc"));
+        assert!(!matches_pattern(b"a*b*c", b"aXbYbZ"));
+        assert!(matches_pattern(b"**", b""));
+        assert!(matches_pattern(b"?", b"a"));
+        assert!(!matches_pattern(b"?", b""));
+    }
+
+    #[test]
+    fn decompresses_by_magic_bytes() {
+        let pgn = b"[Event \"a\"]\n\n1. e4 e5 *\n".repeat(100);
+        let dir = std::env::temp_dir().join("pgn-to-aix-input-tests");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut gzip = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        io::Write::write_all(&mut gzip, &pgn).unwrap();
+        let mut bzip2 = bzip2::write::BzEncoder::new(vec![], bzip2::Compression::default());
+        io::Write::write_all(&mut bzip2, &pgn).unwrap();
+        let mut xz = liblzma::write::XzEncoder::new(vec![], 6);
+        io::Write::write_all(&mut xz, &pgn).unwrap();
+        let files = [
+            ("plain", pgn.clone()),
+            ("zstd", zstd::encode_all(&pgn[..], 0).unwrap()),
+            ("gzip", gzip.finish().unwrap()),
+            ("bzip2", bzip2.finish().unwrap()),
+            ("xz", xz.finish().unwrap()),
+        ];
+
+        for (name, contents) in files {
+            let path = dir.join(name);
+            std::fs::write(&path, &contents).unwrap();
+            let bytes_read = Arc::new(AtomicU64::new(0));
+            let mut decompressed = vec![];
+            open_input(path.to_str().unwrap(), Arc::clone(&bytes_read))
+                .unwrap()
+                .read_to_end(&mut decompressed)
+                .unwrap();
+            assert_eq!(decompressed, pgn, "{name}");
+            assert_eq!(
+                bytes_read.load(Ordering::Relaxed),
+                contents.len() as u64,
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn reports_corrupt_input() {
+        let dir = std::env::temp_dir().join("pgn-to-aix-input-tests");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("corrupt.bz2");
+        std::fs::write(&path, b"BZh9 not bzip2 data").unwrap();
+
+        let mut decompressed = vec![];
+        let result = open_input(path.to_str().unwrap(), Arc::new(AtomicU64::new(0)))
+            .unwrap()
+            .read_to_end(&mut decompressed);
+        assert!(result.is_err());
+    }
+}
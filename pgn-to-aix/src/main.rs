@@ -1,20 +1,30 @@
-use std::fs::File;
+use std::io::Read;
 
 use aix_chess_compression::CompressionLevel;
 use clap::Parser;
 use duckdb::Connection;
-use pgn_reader;
 
 mod args;
+mod export;
+mod input;
+mod parallel;
 mod pgn;
+mod progress;
+mod schema;
+
+/// Table that receives the games of an --append conversion before they are added to `games`.
+const STAGING_TABLE: &str = "games_staging";
 
 fn main() {
     let args = args::Args::parse();
 
-    let input_path = std::path::Path::new(&args.input);
+    let inputs = input::expand_inputs(&args.input).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        std::process::exit(1);
+    });
 
-    if !input_path.exists() {
-        eprintln!("Input file '{}' does not exist.", input_path.display());
+    if inputs.iter().filter(|i| *i == input::STDIN).count() > 1 {
+        eprintln!("Standard input ('-') can only be used once as input.");
         std::process::exit(1);
     }
 
@@ -22,108 +32,251 @@ fn main() {
     let output_duckdb = output_path.with_added_extension("duckdb");
     let output_parquet = output_path.with_added_extension("parquet");
 
-    if output_duckdb.exists() {
+    if args.resume && args.append {
+        eprintln!("--resume and --append are mutually exclusive. Please choose one of them.");
+        std::process::exit(1);
+    }
+
+    if args.direct_parquet {
+        if args.resume || args.append || args.skip_parquet_export {
+            eprintln!(
+                "--direct-parquet cannot be combined with --resume, --append or --skip-parquet-export."
+            );
+            std::process::exit(1);
+        }
+
+        if output_parquet.exists() {
+            eprintln!(
+                "Output Parquet directory '{}' already exists.",
+                output_parquet.display()
+            );
+            std::process::exit(1);
+        }
+    }
+
+    if !args.direct_parquet && !args.resume && !args.append && output_duckdb.exists() {
         eprintln!(
-            "Output DuckDB file '{}' already exists.",
+            "Output DuckDB file '{}' already exists. Use --resume to continue an interrupted conversion or --append to add games to it.",
             output_duckdb.display()
         );
         std::process::exit(1);
     }
 
-    if args.lichess && args.headers.is_some() {
-        eprintln!("--headers and --lichess are mutually exclusive. Please choose one of them.");
+    if (args.resume || args.append) && !output_duckdb.exists() {
+        eprintln!(
+            "Output DuckDB file '{}' does not exist.",
+            output_duckdb.display()
+        );
         std::process::exit(1);
     }
 
-    if !args.lichess && args.headers.is_none() {
+    let header_options = [
+        args.lichess,
+        args.chesscom,
+        args.headers.is_some(),
+        args.schema.is_some(),
+        args.schema_preset.is_some(),
+    ];
+    if header_options.iter().filter(|o| **o).count() > 1 {
         eprintln!(
-            "When not using --lichess, you must provide a list of headers to include using --headers."
+            "--lichess, --chesscom, --headers, --schema and --schema-preset are mutually exclusive. Please choose one of them."
         );
         std::process::exit(1);
     }
 
-    if !args.lichess && args.headers.as_ref().unwrap().is_empty() {
+    if !header_options.contains(&true) {
+        eprintln!(
+            "When not using --lichess or --chesscom, you must provide the headers to include using --headers, --schema or --schema-preset."
+        );
+        std::process::exit(1);
+    }
+
+    if args.headers.as_ref().is_some_and(|h| h.is_empty()) {
         eprintln!("The --headers list cannot be empty.");
         std::process::exit(1);
     }
 
-    if let Some(headers) = &args.headers {
-        if headers.iter().any(|h| h.trim().is_empty()) {
-            eprintln!("The --headers list cannot contain empty header names.");
-            std::process::exit(1);
-        }
+    if let Some(headers) = &args.headers
+        && headers.iter().any(|h| h.trim().is_empty())
+    {
+        eprintln!("The --headers list cannot contain empty header names.");
+        std::process::exit(1);
     }
 
-    let db = Connection::open(output_duckdb).unwrap();
+    let db = if args.direct_parquet {
+        Connection::open_in_memory()
+    } else {
+        Connection::open(&output_duckdb)
+    }
+    .unwrap();
 
     if let Some(mem_limit) = args.duckdb_memory_limit_gb {
         db.execute("SET memory_limit = ?", [format!("{}G", mem_limit)])
             .unwrap();
     }
 
-    let proc_headers_list = if args.lichess {
-        db.execute_batch(include_str!("sql/init-lichess-database.sql"))
-            .unwrap();
+    let commands: Vec<pgn::CommentCommand> = args
+        .comment_commands
+        .iter()
+        .map(|command| match command {
+            args::CommentCommand::ClkMs => pgn::CommentCommand::ClockMs,
+            args::CommentCommand::Emt => pgn::CommentCommand::Emt,
+            args::CommentCommand::Clkc => pgn::CommentCommand::Clkc,
+            args::CommentCommand::Depth => pgn::CommentCommand::Depth,
+            args::CommentCommand::Csl => pgn::CommentCommand::Csl,
+            args::CommentCommand::Cal => pgn::CommentCommand::Cal,
+        })
+        .collect();
+
+    let tag_format = if args.lichess {
+        if !args.resume && !args.append {
+            db.execute_batch(include_str!("sql/init-lichess-database.sql"))
+                .unwrap();
+        }
+
+        pgn::TagFormat::Lichess
+    } else if args.chesscom {
+        if !args.resume && !args.append {
+            db.execute_batch(include_str!("sql/init-chesscom-database.sql"))
+                .unwrap();
+        }
 
-        None
+        pgn::TagFormat::Chesscom
     } else {
-        let headers_sql = &args
-            .headers
-            .as_ref()
-            .unwrap()
-            .iter()
-            .map(|h| format!("{} VARCHAR", h))
-            .collect::<Vec<_>>()
-            .join(",\n ");
-
-        let init_sql =
-            include_str!("sql/init-other-database.sql").replace("$HEADERS", &headers_sql);
-        db.execute_batch(&init_sql).unwrap();
-
-        Some(
-            args.headers
-                .as_ref()
-                .unwrap()
-                .iter()
-                .map(|s| s.to_lowercase())
-                .collect::<Vec<_>>(),
-        )
+        let schema = if let Some(headers) = &args.headers {
+            schema::Schema::from_headers(headers)
+        } else if let Some(path) = &args.schema {
+            std::fs::read_to_string(path)
+                .map_err(|e| format!("Cannot read schema file '{path}': {e}"))
+                .and_then(|json| schema::Schema::from_json(&json))
+        } else {
+            Ok(schema::Schema::preset(match args.schema_preset.unwrap() {
+                args::SchemaPreset::Twic => schema::Preset::Twic,
+                args::SchemaPreset::Fide => schema::Preset::Fide,
+            }))
+        };
+        let schema = schema.unwrap_or_else(|e| {
+            eprintln!("{e}");
+            std::process::exit(1);
+        });
+
+        if !args.resume && !args.append {
+            let init_sql = include_str!("sql/init-other-database.sql")
+                .replace("$HEADERS", &schema.columns_sql());
+            db.execute_batch(&init_sql).unwrap();
+        }
+
+        pgn::TagFormat::Custom(schema)
     };
 
-    let app = db.appender("games").unwrap();
-    let mut proc = pgn::PgnProcessor::new(
-        app,
-        match args.compression {
-            args::CompressionLevel::Low => CompressionLevel::Low,
-            args::CompressionLevel::Medium => CompressionLevel::Medium,
-            args::CompressionLevel::High => CompressionLevel::High,
-        },
-        proc_headers_list,
-        args.continue_on_invalid_move,
-    );
+    db.execute_batch(include_str!("sql/init-rejected-games.sql"))
+        .unwrap();
 
-    let file = File::open(input_path).unwrap();
-    let uncompressed: Box<dyn std::io::Read> =
-        if input_path.extension().and_then(|s| s.to_str()) == Some("zst") {
-            Box::new(zstd::Decoder::new(file).unwrap())
+    // (input, bytes read, finished) for every input, in conversion order
+    let (table, progress, game_count) = if args.resume {
+        let progress: Vec<(String, u64, u64, bool)> = if table_exists(&db, "ingest_progress") {
+            db.prepare(
+                "SELECT input, bytes_read, game_count, finished FROM ingest_progress ORDER BY rowid",
+            )
+            .unwrap()
+            .query_map([], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })
+            .unwrap()
+            .map(|p| p.unwrap())
+            .collect()
         } else {
-            Box::new(file)
+            vec![]
         };
 
-    let mut reader = pgn_reader::Reader::new(uncompressed);
-    reader
-        .read_games(&mut proc)
-        .map(|e| e.unwrap())
-        .for_each(drop);
+        if progress.is_empty() {
+            eprintln!(
+                "Output DuckDB file '{}' has no interrupted conversion to resume.",
+                output_duckdb.display()
+            );
+            std::process::exit(1);
+        }
 
-    proc.flush();
-    db.execute("checkpoint", []).unwrap();
+        if !progress.iter().map(|p| &p.0).eq(inputs.iter()) {
+            eprintln!(
+                "The interrupted conversion in '{}' was started from other input files: {}.",
+                output_duckdb.display(),
+                progress
+                    .iter()
+                    .map(|p| format!("'{}'", p.0))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            std::process::exit(1);
+        }
 
-    drop(proc);
+        let game_count = progress.iter().map(|p| p.2).max().unwrap_or(0);
+        println!("Resuming after {game_count} games.");
+        let table = if table_exists(&db, STAGING_TABLE) {
+            STAGING_TABLE
+        } else {
+            "games"
+        };
+        let progress: Vec<(String, u64, bool)> = progress
+            .into_iter()
+            .map(|(input, offset, _, finished)| (input, offset, finished))
+            .collect();
+        (table, progress, game_count)
+    } else {
+        db.execute_batch(include_str!("sql/init-progress.sql"))
+            .unwrap();
+        let unfinished: u64 = db
+            .query_row("SELECT count(*) FROM ingest_progress", [], |row| row.get(0))
+            .unwrap();
+        if unfinished > 0 {
+            eprintln!(
+                "Output DuckDB file '{}' has an interrupted conversion. Use --resume to finish it first.",
+                output_duckdb.display()
+            );
+            std::process::exit(1);
+        }
 
-    if !args.skip_parquet_export {
-        println!("Exporting to Parquet file...");
-        let parquet_compression_str = "COMPRESSION ".to_owned()
+        if args.source_file_column && !column_exists(&db, "games", "source_file") {
+            db.execute_batch("ALTER TABLE games ADD COLUMN source_file VARCHAR;")
+                .unwrap();
+        }
+        if args.annotations && !column_exists(&db, "games", "annotations") {
+            db.execute_batch("ALTER TABLE games ADD COLUMN annotations BLOB;")
+                .unwrap();
+        }
+        for command in &commands {
+            for (column, column_type) in command.columns() {
+                if !column_exists(&db, "games", column) {
+                    db.execute_batch(&format!(
+                        "ALTER TABLE games ADD COLUMN {column} {column_type};"
+                    ))
+                    .unwrap();
+                }
+            }
+        }
+
+        let table = if args.append {
+            db.execute_batch(&format!(
+                "CREATE TABLE {STAGING_TABLE} AS FROM games LIMIT 0;"
+            ))
+            .unwrap();
+            STAGING_TABLE
+        } else {
+            "games"
+        };
+        for input in &inputs {
+            db.execute(
+                "INSERT INTO ingest_progress VALUES (?, 0, 0, false)",
+                [input.as_str()],
+            )
+            .unwrap();
+        }
+        let progress = inputs.iter().map(|i| (i.clone(), 0, false)).collect();
+        (table, progress, 0)
+    };
+
+    let parquet_options = export::ParquetOptions {
+        compression: "COMPRESSION ".to_owned()
             + &(if args.parquet_compression == args::ParquetCompression::Zstd {
                 format!(
                     "{}, COMPRESSION_LEVEL {}",
@@ -132,16 +285,204 @@ fn main() {
                 )
             } else {
                 args.parquet_compression.as_str().to_owned()
-            });
+            }),
+        row_group_size: args.row_group_size,
+        sort_by: args.sort_by.clone(),
+        partition_by: args.partition_by.map(|p| match p {
+            args::PartitionBy::Month => export::Partition::Month,
+            args::PartitionBy::TimeControlClass => export::Partition::TimeControlClass,
+        }),
+    };
+    if let Some(partition) = parquet_options.partition_by {
+        for column in partition.source_columns() {
+            if !column_exists(&db, "games", column) {
+                eprintln!(
+                    "--partition-by {} needs a {column} column, e.g. with --lichess or --chesscom.",
+                    partition.column()
+                );
+                std::process::exit(1);
+            }
+        }
+    }
 
-        let to_parquet_command = format!(
-            "COPY (FROM games) TO ? (FORMAT PARQUET, PARQUET_VERSION v2, {})",
-            parquet_compression_str
-        );
+    // the size of the inputs, unless one of them is standard input
+    let total_bytes = progress
+        .iter()
+        .filter(|(_, _, finished)| !finished)
+        .map(|(input, _, _)| input::input_size(input))
+        .sum::<Option<u64>>();
+    let mut writer = pgn::GameWriter::new(
+        &db,
+        table,
+        tag_format.clone(),
+        game_count,
+        progress::Progress::new(total_bytes, game_count),
+    );
+    if args.direct_parquet {
+        std::fs::create_dir_all(&output_parquet).unwrap();
+        writer = writer.with_parquet_sink(export::ParquetSink::new(
+            output_parquet.clone(),
+            args.games_per_file,
+            parquet_options.clone(),
+        ));
+    }
+    let proc = pgn::PgnProcessor::new(
+        match args.compression {
+            args::CompressionLevel::Low => CompressionLevel::Low,
+            args::CompressionLevel::Medium => CompressionLevel::Medium,
+            args::CompressionLevel::High => CompressionLevel::High,
+        },
+        tag_format,
+        args.continue_on_invalid_move,
+        args.keyframe_interval,
+        args.annotations,
+        commands,
+    );
+
+    for (input, offset, finished) in progress {
+        if finished {
+            continue;
+        }
+
+        let bytes_read = writer.begin_input(&input);
+        if inputs.len() > 1 {
+            println!("Converting {input}...");
+        }
+
+        let mut uncompressed = input::open_input(&input, bytes_read).unwrap_or_else(|e| {
+            eprintln!("Cannot open input file '{input}': {e}");
+            std::process::exit(1);
+        });
+
+        let skipped =
+            std::io::copy(&mut (&mut uncompressed).take(offset), &mut std::io::sink()).unwrap();
+        if skipped != offset {
+            eprintln!("Input file '{input}' is shorter than the converted part.");
+            std::process::exit(1);
+        }
+
+        parallel::convert(
+            uncompressed,
+            offset,
+            &proc,
+            &mut writer,
+            args.threads.into(),
+            !args.unordered,
+        )
+        .unwrap_or_else(|e| {
+            eprintln!("Cannot read input file '{input}': {e}");
+            std::process::exit(1);
+        });
+    }
+    let stats = writer.finish();
+    drop(writer);
+
+    let output_rejected = output_path.with_added_extension("rejected.parquet");
+    let rejected: Vec<(String, u64)> = db
+        .prepare(include_str!("sql/rejected-games-summary.sql"))
+        .unwrap()
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .unwrap()
+        .map(|r| r.unwrap())
+        .collect();
+    if !rejected.is_empty() {
+        if args.direct_parquet {
+            println!("Rejected games (see {}):", output_rejected.display());
+        } else {
+            println!("Rejected games (see the rejected_games table):");
+        }
+        for (reason, count) in rejected {
+            println!("  {reason}: {count} games");
+        }
+        if args.continue_on_invalid_move {
+            println!("Games with an illegal move were stored up to the illegal move.");
+        }
+    }
 
-        db.execute(&to_parquet_command, [output_parquet.to_str().unwrap()])
+    if args.direct_parquet {
+        let any_rejected: bool = db
+            .query_row("SELECT count(*) > 0 FROM rejected_games", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        if any_rejected {
+            let rejected_options = export::ParquetOptions {
+                sort_by: None,
+                partition_by: None,
+                ..parquet_options
+            };
+            rejected_options.copy(&db, "rejected_games", &output_rejected, "data_{i}");
+        }
+        println!("Wrote the games to {}.", output_parquet.display());
+        write_stats(args.stats_json.as_deref(), &stats);
+        return;
+    }
+
+    db.execute_batch("BEGIN TRANSACTION").unwrap();
+    if table == STAGING_TABLE {
+        let staged: u64 = db
+            .query_row(
+                &format!("SELECT count(*) FROM {STAGING_TABLE}"),
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        let appended = if args.lichess {
+            db.execute(include_str!("sql/append-lichess-games.sql"), [])
+                .unwrap()
+        } else if args.chesscom {
+            db.execute(include_str!("sql/append-chesscom-games.sql"), [])
+                .unwrap()
+        } else {
+            db.execute(&format!("INSERT INTO games FROM {STAGING_TABLE}"), [])
+                .unwrap()
+        };
+        db.execute_batch(&format!("DROP TABLE {STAGING_TABLE};"))
             .unwrap();
+        println!(
+            "Appended {appended} games, skipped {} duplicate games.",
+            staged - appended as u64
+        );
+    }
+    db.execute_batch("DROP TABLE ingest_progress; COMMIT;")
+        .unwrap();
+    db.execute("checkpoint", []).unwrap();
+
+    if !args.skip_parquet_export {
+        println!("Exporting to Parquet file...");
+        parquet_options.copy(&db, "games", &output_parquet, "data_{i}");
     } else {
         println!("Skipping Parquet export.");
     }
+    write_stats(args.stats_json.as_deref(), &stats);
+}
+
+/// Writes the statistics of the conversion to the `--stats-json` path, if given.
+fn write_stats(path: Option<&str>, stats: &progress::Stats) {
+    let Some(path) = path else {
+        return;
+    };
+    let json = serde_json::to_string_pretty(stats).unwrap();
+    std::fs::write(path, json + "\n").unwrap_or_else(|e| {
+        eprintln!("Cannot write statistics to '{path}': {e}");
+        std::process::exit(1);
+    });
+}
+
+fn table_exists(db: &Connection, name: &str) -> bool {
+    db.query_row(
+        "SELECT count(*) > 0 FROM duckdb_tables() WHERE table_name = ?",
+        [name],
+        |row| row.get(0),
+    )
+    .unwrap()
+}
+
+fn column_exists(db: &Connection, table: &str, column: &str) -> bool {
+    db.query_row(
+        "SELECT count(*) > 0 FROM duckdb_columns() WHERE table_name = ? AND column_name = ?",
+        [table, column],
+        |row| row.get(0),
+    )
+    .unwrap()
 }
@@ -0,0 +1,403 @@
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, BufReader, Read};
+use std::sync::mpsc::{Receiver, sync_channel};
+use std::sync::{Arc, Condvar, Mutex};
+
+use crate::pgn::{ConvertedGame, GameWriter, PgnProcessor};
+
+/// Number of games in a chunk handed to a worker.
+const CHUNK_GAMES: usize = 1000;
+
+/// Number of chunks that can wait in each channel per worker thread.
+const CHANNEL_CHUNKS_PER_THREAD: usize = 2;
+
+/// A chunk of complete games, and the input offset right after it.
+type Chunk = (Vec<u8>, u64);
+
+/// Converts all games of `input` on `threads` worker threads and appends them with `writer`.
+/// `offset` is the position of `input` in the whole input, when resuming a conversion.
+///
+/// The input is split into chunks at game boundaries, so every worker sees complete games.
+/// With `ordered`, games are appended in input order, which produces the same table as
+/// converting on a single thread. Otherwise games are appended as soon as their chunk is done,
+/// except when a commit is due: then chunks wait for the chunks before them, so that the
+/// committed progress covers exactly the appended games. Chunks are only handed out while they
+/// are less than `2 * threads` chunks ahead of the first chunk that is not appended yet.
+///
+/// On a read error, the games of the chunks before it may have been appended, but the input is
+/// not marked as finished.
+pub fn convert(
+    input: impl Read + Send,
+    offset: u64,
+    processor: &PgnProcessor,
+    writer: &mut GameWriter<'_>,
+    threads: usize,
+    ordered: bool,
+) -> io::Result<()> {
+    let mut chunks = Chunks::new(BufReader::new(input), offset, CHUNK_GAMES);
+    if threads == 1 {
+        let mut processor = processor.clone();
+        for chunk in &mut chunks {
+            let (chunk, end) = chunk?;
+            convert_chunk(&chunk, &mut processor)?
+                .into_iter()
+                .for_each(|row| writer.append(row));
+            writer.chunk_done(end);
+        }
+        writer.finish_input(chunks.offset);
+        return Ok(());
+    }
+
+    let capacity = threads * CHANNEL_CHUNKS_PER_THREAD;
+    let (chunk_tx, chunk_rx) = sync_channel::<(usize, Chunk)>(capacity);
+    let (rows_tx, rows_rx) = sync_channel::<(usize, io::Result<Vec<ConvertedGame>>, u64)>(capacity);
+    // shared by the workers, dropped with the last one so that the splitter stops if they panic
+    let chunk_rx = Arc::new(Mutex::new(chunk_rx));
+    let window = Window::new(capacity);
+
+    std::thread::scope(|s| {
+        let chunks = &mut chunks;
+        let window = &window;
+        let splitter = s.spawn(move || -> io::Result<()> {
+            for (seq, chunk) in chunks.enumerate() {
+                let chunk = chunk?;
+                if !window.wait_for(seq) || chunk_tx.send((seq, chunk)).is_err() {
+                    // the conversion failed, or all workers are gone because one of them panicked
+                    break;
+                }
+            }
+            Ok(())
+        });
+
+        for _ in 0..threads {
+            let chunk_rx = Arc::clone(&chunk_rx);
+            let rows_tx = rows_tx.clone();
+            let mut processor = processor.clone();
+            s.spawn(move || {
+                while let Some((seq, (chunk, end))) = next_chunk(&chunk_rx) {
+                    let rows = convert_chunk(&chunk, &mut processor);
+                    if rows_tx.send((seq, rows, end)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(chunk_rx);
+        drop(rows_tx);
+
+        // returning early drops `rows_rx` and closes the window, which stops the workers and
+        // then the splitter
+        let close = window.close_on_drop();
+        // chunks that are done but not appended yet
+        let mut pending = BTreeMap::new();
+        // input offsets after the chunks that were appended ahead of `next_seq`
+        let mut ahead = BTreeMap::new();
+        let mut next_seq = 0;
+        for (seq, rows, end) in rows_rx {
+            let rows = rows?;
+            if ordered || writer.commit_due() {
+                pending.insert(seq, (rows, end));
+            } else {
+                rows.into_iter().for_each(|row| writer.append(row));
+                ahead.insert(seq, end);
+            }
+
+            loop {
+                let end = if let Some(end) = ahead.remove(&next_seq) {
+                    end
+                } else if let Some((rows, end)) = pending.remove(&next_seq) {
+                    rows.into_iter().for_each(|row| writer.append(row));
+                    end
+                } else {
+                    break;
+                };
+                next_seq += 1;
+                // progress can only be committed when no later chunk was appended yet
+                if ahead.is_empty() {
+                    writer.chunk_done(end);
+                }
+            }
+            window.advance(next_seq);
+        }
+        drop(close);
+        splitter.join().unwrap()
+    })?;
+
+    writer.finish_input(chunks.offset);
+    Ok(())
+}
+
+/// Limits how far ahead of the first chunk that is not appended yet chunks are handed out.
+struct Window {
+    /// The sequence number of the first chunk that is not appended yet, and whether the
+    /// conversion stopped.
+    state: Mutex<(usize, bool)>,
+    changed: Condvar,
+    size: usize,
+}
+
+impl Window {
+    fn new(size: usize) -> Window {
+        Window {
+            state: Mutex::new((0, false)),
+            changed: Condvar::new(),
+            size,
+        }
+    }
+
+    /// Waits until chunk `seq` can be handed out. Returns false if the conversion stopped.
+    fn wait_for(&self, seq: usize) -> bool {
+        let state = self.state.lock().unwrap();
+        let state = self
+            .changed
+            .wait_while(state, |(next_seq, closed)| {
+                !*closed && seq >= *next_seq + self.size
+            })
+            .unwrap();
+        !state.1
+    }
+
+    /// Records that all chunks before `next_seq` are appended.
+    fn advance(&self, next_seq: usize) {
+        self.state.lock().unwrap().0 = next_seq;
+        self.changed.notify_all();
+    }
+
+    /// Returns a guard that stops the conversion when dropped.
+    fn close_on_drop(&self) -> CloseOnDrop<'_> {
+        CloseOnDrop(self)
+    }
+}
+
+struct CloseOnDrop<'a>(&'a Window);
+
+impl Drop for CloseOnDrop<'_> {
+    fn drop(&mut self) {
+        self.0.state.lock().unwrap().1 = true;
+        self.0.changed.notify_all();
+    }
+}
+
+fn next_chunk(chunk_rx: &Mutex<Receiver<(usize, Chunk)>>) -> Option<(usize, Chunk)> {
+    chunk_rx.lock().unwrap().recv().ok()
+}
+
+fn convert_chunk(chunk: &[u8], processor: &mut PgnProcessor) -> io::Result<Vec<ConvertedGame>> {
+    pgn_reader::Reader::new(chunk)
+        .read_games(processor)
+        .collect()
+}
+
+/// Splits PGN text into chunks of `games_per_chunk` complete games.
+///
+/// A game starts at a tag line (a line starting with `[` and a tag name) that follows movetext,
+/// or at the first tag line of the input. Brace comments spanning several lines are skipped, so
+/// a comment line that looks like a tag does not split a game.
+struct Chunks<R> {
+    reader: R,
+    /// Input offset of the end of the last returned chunk.
+    offset: u64,
+    games_per_chunk: usize,
+    line: Vec<u8>,
+    in_movetext: bool,
+    in_comment: bool,
+    done: bool,
+}
+
+impl<R: BufRead> Chunks<R> {
+    fn new(reader: R, offset: u64, games_per_chunk: usize) -> Chunks<R> {
+        Chunks {
+            reader,
+            offset,
+            games_per_chunk,
+            line: vec![],
+            in_movetext: false,
+            in_comment: false,
+            done: false,
+        }
+    }
+
+    /// Returns whether the line in `self.line` starts a new game, and updates the state.
+    fn starts_game(&mut self, first_game: bool) -> bool {
+        let line = self.line.trim_ascii();
+        if !self.in_comment
+            && line.first() == Some(&b'[')
+            && line.get(1).is_some_and(|c| c.is_ascii_alphanumeric())
+        {
+            let starts_game = self.in_movetext || first_game;
+            self.in_movetext = false;
+            return starts_game;
+        }
+
+        if line.is_empty() || line.starts_with(b"%") {
+            return false;
+        }
+
+        self.in_movetext = true;
+        for &c in line {
+            match c {
+                b'{' if !self.in_comment => self.in_comment = true,
+                b'}' if self.in_comment => self.in_comment = false,
+                b';' if !self.in_comment => break,
+                _ => {}
+            }
+        }
+        false
+    }
+}
+
+impl<R: BufRead> Iterator for Chunks<R> {
+    type Item = io::Result<Chunk>;
+
+    fn next(&mut self) -> Option<io::Result<Chunk>> {
+        if self.done {
+            return None;
+        }
+
+        let mut chunk = std::mem::take(&mut self.line);
+        let mut games = usize::from(!chunk.is_empty());
+        loop {
+            self.line.clear();
+            match self.reader.read_until(b'\n', &mut self.line) {
+                Ok(0) => {
+                    self.done = true;
+                    self.offset += chunk.len() as u64;
+                    return (!chunk.is_empty()).then_some(Ok((chunk, self.offset)));
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+
+            if self.starts_game(games == 0) {
+                if games == self.games_per_chunk {
+                    self.offset += chunk.len() as u64;
+                    return Some(Ok((chunk, self.offset)));
+                }
+                games += 1;
+            }
+            chunk.extend_from_slice(&self.line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Splits `pgn` into chunks of one game each.
+    fn split(pgn: &str) -> Vec<String> {
+        let chunks: Vec<_> = Chunks::new(pgn.as_bytes(), 0, 1)
+            .map(|chunk| chunk.unwrap())
+            .collect();
+        let ends: Vec<_> = chunks.iter().map(|(_, end)| *end).collect();
+        let expected_ends: Vec<_> = chunks
+            .iter()
+            .scan(0, |end, (chunk, _)| {
+                *end += chunk.len() as u64;
+                Some(*end)
+            })
+            .collect();
+        assert_eq!(ends, expected_ends);
+        assert_eq!(ends.last().copied().unwrap_or(0), pgn.len() as u64);
+
+        chunks
+            .into_iter()
+            .map(|(chunk, _)| String::from_utf8(chunk).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn splits_at_tags_after_movetext() {
+        let games = [
+            "[Event \"a\"]\n[Site \"?\"]\n\n1. e4 e5 *\n\n",
+            "[Event \"b\"]\n\n1. d4 *\n",
+        ];
+        assert_eq!(split(&games.concat()), games);
+    }
+
+    #[test]
+    fn splits_games_without_blank_lines() {
+        let games = [
+            "[Event \"a\"]\n1. e4 e5 *\n",
+            "[Event \"b\"]\n1. d4 *\n",
+            "[Event \"c\"]\n*\n",
+        ];
+        assert_eq!(split(&games.concat()), games);
+    }
+
+    #[test]
+    fn keeps_tag_lines_in_brace_comments() {
+        let games = [
+            "[Event \"a\"]\n\n1. e4 { a comment\n[Event \"not a game\"]\n} e5 *\n\n",
+            "[Event \"b\"]\n\n1. d4 {\n[Site \"?\"] } *\n",
+        ];
+        assert_eq!(split(&games.concat()), games);
+    }
+
+    #[test]
+    fn ignores_braces_in_rest_of_line_comments() {
+        let games = [
+            "[Event \"a\"]\n\n1. e4 ; not a { comment\n",
+            "[Event \"b\"]\n\n1. d4 {; still a comment\n[Event \"c\"]\n} *\n",
+        ];
+        assert_eq!(split(&games.concat()), games);
+    }
+
+    #[test]
+    fn skips_escape_lines() {
+        let games = [
+            "% header\n[Event \"a\"]\n\n1. e4 *\n% escaped\n\n",
+            "[Event \"b\"]\n% escaped\n[Site \"?\"]\n\n1. d4 *\n",
+        ];
+        assert_eq!(split(&games.concat()), games);
+    }
+
+    #[test]
+    fn counts_games_per_chunk() {
+        let pgn = "[Event \"a\"]\n1. e4 *\n[Event \"b\"]\n1. d4 *\n[Event \"c\"]\n1. c4 *\n";
+        let chunks: Vec<_> = Chunks::new(pgn.as_bytes(), 10, 2)
+            .map(|chunk| chunk.unwrap())
+            .collect();
+        let (first, second) = pgn.split_at(pgn.find("[Event \"c\"]").unwrap());
+        assert_eq!(
+            chunks,
+            [
+                (first.as_bytes().to_vec(), 10 + first.len() as u64),
+                (second.as_bytes().to_vec(), 10 + pgn.len() as u64),
+            ]
+        );
+    }
+
+    #[test]
+    fn returns_read_errors() {
+        struct Failing;
+        impl Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("broken"))
+            }
+        }
+
+        let mut chunks = Chunks::new(BufReader::new(Failing), 0, 1);
+        assert!(chunks.next().unwrap().is_err());
+        assert!(chunks.next().is_none());
+    }
+
+    #[test]
+    fn window_limits_chunks_ahead() {
+        let window = Window::new(2);
+        std::thread::scope(|scope| {
+            let handed_out = scope.spawn(|| (0..4).take_while(|&seq| window.wait_for(seq)).count());
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            assert!(!handed_out.is_finished());
+            window.advance(2);
+            assert_eq!(handed_out.join().unwrap(), 4);
+        });
+
+        let close = window.close_on_drop();
+        drop(close);
+        assert!(!window.wait_for(100));
+    }
+}
@@ -1,23 +1,128 @@
-use aix_chess_compression::{CompressionLevel, Encode, Encoder};
-use duckdb::{Appender, params};
-use lazy_regex::regex_captures;
-use pgn_reader::{SanPlus, Skip, Visitor};
-use shakmaty::san::San;
-use shakmaty::{Chess, Position};
+use aix_chess_compression::{
+    Annotation, Annotations, CompressionLevel, Encode, Encoder, MAX_VARIATION_DEPTH,
+};
+use duckdb::{Appender, Connection, params};
+use lazy_regex::{regex_captures, regex_captures_iter, regex_is_match, regex_replace_all};
+use pgn_reader::{Nag, Outcome, SanPlus, Skip, Visitor};
+use shakmaty::fen::Fen;
+use shakmaty::variant::{Variant, VariantPosition};
+use shakmaty::{CastlingMode, Color, Position};
 use std::collections::HashMap;
+use std::fmt::Write;
 use std::ops::ControlFlow;
+use std::sync::Arc;
+use std::sync::atomic::AtomicU64;
 
-pub struct PgnProcessor<'a> {
-    appender: Appender<'a>,
-    count: u32,
+use crate::export::ParquetSink;
+use crate::progress::{Progress, Stats};
+use crate::schema::{Schema, parse_date};
+
+/// Parses PGN games into `GameRow`s. Cloned for every worker when converting with multiple
+/// threads.
+#[derive(Clone)]
+pub struct PgnProcessor {
     level: CompressionLevel,
-    header_list: Option<Vec<String>>,
+    tag_format: TagFormat,
     continue_on_invalid_move: bool,
+    keyframe_interval: Option<u16>,
+    /// Whether NAGs, comments and variations are kept in the `annotations` column.
+    annotations: bool,
+    /// Comment commands extracted into their own columns, next to `[%clk]` and `[%eval]`.
+    commands: Vec<CommentCommand>,
+}
+
+/// Number of games appended between two commits that record the ingestion progress.
+const COMMIT_GAMES: u32 = 10000;
+
+/// Appends `GameRow`s to a table, in transactions that also record how far the input was read
+/// in the `ingest_progress` table, so that an interrupted conversion can be resumed.
+pub struct GameWriter<'a> {
+    db: &'a Connection,
+    appender: Appender<'a>,
+    rejected_appender: Appender<'a>,
+    count: u64,
+    uncommitted: u32,
+    tag_format: TagFormat,
+    /// Optional columns of the table after the game columns, in table order.
+    extra_columns: Vec<ExtraColumn>,
+    /// The input currently being converted.
+    input: String,
+    table: String,
+    /// With `--direct-parquet`, writes the appended games to Parquet files.
+    parquet: Option<ParquetSink>,
+    /// Number of games in the table that are not written to a Parquet file yet.
+    unwritten: u64,
+    progress: Progress,
+    stats: Stats,
+}
+
+/// Optional columns of the `games` table.
+#[derive(Clone)]
+enum ExtraColumn {
+    SourceFile,
+    Annotations,
+    /// A column of a comment command, by name.
+    Command(&'static str),
+}
+
+/// Result of converting a PGN game.
+pub enum ConvertedGame {
+    Valid(GameRow),
+    /// A game with an invalid move or start position, recorded in the `rejected_games` table.
+    /// With `--continue-on-invalid-move`, the moves before the invalid move are stored too.
+    Rejected(RejectedGame, Option<GameRow>),
+}
+
+/// A converted game, ready to be appended to the `games` table.
+pub struct GameRow {
+    headers: Headers,
+    movedata: Vec<u8>,
+    /// The level `movedata` is encoded with, which can be lower than the requested level.
+    compression_level: CompressionLevel,
+    clocks_white: Option<String>,
+    clocks_black: Option<String>,
+    evals: Option<String>,
+    ply: u16,
+    annotations: Option<Vec<u8>>,
+    /// Values of the comment command columns, by column name. Missing columns are `NULL`.
+    commands: HashMap<&'static str, String>,
+}
+
+/// A game that could not be converted, ready to be appended to the `rejected_games` table.
+pub struct RejectedGame {
+    tags: String,
+    movetext: String,
+    ply: u16,
+    reason: &'static str,
+    message: String,
+}
+
+pub struct Tags {
+    headers: Headers,
+    /// The tag pairs as they appear in the PGN.
+    raw: String,
+    fen: Option<String>,
+    variant: Variant,
+    /// The `Variant` header, if it names a variant that is not supported.
+    unsupported_variant: Option<String>,
+    chess960: bool,
+}
+
+/// How the tags of the games are stored in the `games` table.
+#[derive(Clone)]
+pub enum TagFormat {
+    /// The columns of `init-lichess-database.sql`.
+    Lichess,
+    /// The columns of `init-chesscom-database.sql`.
+    Chesscom,
+    /// The columns of a schema, for other PGN files.
+    Custom(Schema),
 }
 
 #[derive(Debug)]
 pub enum Headers {
-    Lichess(LichessHeaders),
+    Lichess(Box<LichessHeaders>),
+    Chesscom(Box<ChesscomHeaders>),
     Custom(CustomHeaders),
 }
 
@@ -42,26 +147,163 @@ pub struct LichessHeaders {
     utc_time: Option<String>,
 }
 
-pub type CustomHeaders = HashMap<String, String>;
+#[derive(Default, Debug)]
+pub struct ChesscomHeaders {
+    chesscom_id: Option<String>,
+    event: Option<String>,
+    white: Option<String>,
+    black: Option<String>,
+    white_rating: Option<i16>,
+    black_rating: Option<i16>,
+    result: Option<String>,
+    termination: Option<String>,
+    time_control: Option<(u16, u8)>,
+    /// Seconds per move of daily games, whose `TimeControl` is `1/<seconds>`.
+    daily_seconds: Option<u32>,
+    eco: Option<String>,
+    eco_url: Option<String>,
+    utc_date: Option<String>,
+    utc_time: Option<String>,
+    end_date: Option<String>,
+    end_time: Option<String>,
+}
+
+/// Tag values for the columns of the schema, in schema order.
+pub type CustomHeaders = Vec<Option<String>>;
 
-impl<'a> PgnProcessor<'a> {
+impl PgnProcessor {
     pub fn new(
-        appender: Appender<'a>,
         level: CompressionLevel,
-        header_list: Option<Vec<String>>,
+        tag_format: TagFormat,
         continue_on_invalid_move: bool,
-    ) -> PgnProcessor<'a> {
+        keyframe_interval: Option<u16>,
+        annotations: bool,
+        commands: Vec<CommentCommand>,
+    ) -> PgnProcessor {
         PgnProcessor {
-            appender,
-            count: 0,
             level,
-            header_list,
+            tag_format,
             continue_on_invalid_move,
+            keyframe_interval,
+            annotations,
+            commands,
+        }
+    }
+}
+
+impl<'a> GameWriter<'a> {
+    /// Creates a writer that appends to `table`, continuing the game count from `count`.
+    pub fn new(
+        db: &'a Connection,
+        table: &str,
+        tag_format: TagFormat,
+        count: u64,
+        progress: Progress,
+    ) -> GameWriter<'a> {
+        let extra_columns = db
+            .prepare(
+                "SELECT column_name FROM duckdb_columns() WHERE table_name = ? ORDER BY column_index",
+            )
+            .unwrap()
+            .query_map([table], |row| row.get::<_, String>(0))
+            .unwrap()
+            .filter_map(|column| match column.unwrap().as_str() {
+                "source_file" => Some(ExtraColumn::SourceFile),
+                "annotations" => Some(ExtraColumn::Annotations),
+                name => CommentCommand::ALL
+                    .iter()
+                    .flat_map(|command| command.columns())
+                    .find(|(column, _)| *column == name)
+                    .map(|(column, _)| ExtraColumn::Command(column)),
+            })
+            .collect();
+
+        GameWriter {
+            db,
+            appender: db.appender(table).unwrap(),
+            rejected_appender: db.appender("rejected_games").unwrap(),
+            count,
+            uncommitted: 0,
+            tag_format,
+            extra_columns,
+            input: String::new(),
+            table: table.to_owned(),
+            parquet: None,
+            unwritten: 0,
+            progress,
+            stats: Stats::default(),
+        }
+    }
+
+    /// Writes the games to Parquet files with `sink` as they are appended, instead of keeping
+    /// them in the table.
+    pub fn with_parquet_sink(mut self, sink: ParquetSink) -> Self {
+        self.parquet = Some(sink);
+        self
+    }
+
+    /// Writes the games that are not written to a Parquet file yet, with `--direct-parquet`,
+    /// and returns the statistics of the conversion.
+    pub fn finish(&mut self) -> Stats {
+        if self.unwritten > 0 {
+            self.write_parquet_file();
+        }
+        self.progress.end_line();
+        self.stats.finish(&self.progress);
+        std::mem::take(&mut self.stats)
+    }
+
+    fn write_parquet_file(&mut self) {
+        let Some(parquet) = &mut self.parquet else {
+            return;
+        };
+
+        self.appender.flush().unwrap();
+        parquet.write(self.db, &self.table);
+        self.db
+            .execute_batch(&format!("DELETE FROM {};", self.table))
+            .unwrap();
+        self.unwritten = 0;
+    }
+
+    /// Starts appending the games of `input`. Returns the counter of the bytes read from it,
+    /// for `input::open_input`.
+    pub fn begin_input(&mut self, input: &str) -> Arc<AtomicU64> {
+        self.input = input.to_owned();
+        self.db.execute_batch("BEGIN TRANSACTION").unwrap();
+        self.progress.begin_input()
+    }
+
+    /// Called after all games before byte `bytes_read` of the input were appended. Commits them
+    /// once enough games are pending.
+    pub fn chunk_done(&mut self, bytes_read: u64) {
+        if self.uncommitted >= COMMIT_GAMES {
+            self.commit(bytes_read, false);
+            self.db.execute_batch("BEGIN TRANSACTION").unwrap();
         }
     }
 
-    pub fn flush(&mut self) {
+    /// Returns whether the next `chunk_done` commits the pending games.
+    pub fn commit_due(&self) -> bool {
+        self.uncommitted >= COMMIT_GAMES
+    }
+
+    /// Commits all pending games of the input, which was read up to `bytes_read`.
+    pub fn finish_input(&mut self, bytes_read: u64) {
+        self.commit(bytes_read, true);
+    }
+
+    fn commit(&mut self, bytes_read: u64, finished: bool) {
         self.appender.flush().unwrap();
+        self.rejected_appender.flush().unwrap();
+        self.db
+            .execute(
+                "UPDATE ingest_progress SET bytes_read = ?, game_count = ?, finished = ? WHERE input = ?",
+                params![bytes_read, self.count, finished, self.input],
+            )
+            .unwrap();
+        self.db.execute_batch("COMMIT").unwrap();
+        self.uncommitted = 0;
     }
 }
 
@@ -69,22 +311,128 @@ pub struct GameInProcessing<'a> {
     headers: Headers,
     encoder: Encoder<'a>,
     evals: Vec<(u16, i16)>,
-    clocks_white: Vec<(u16, u16)>,
-    clocks_black: Vec<(u16, u16)>,
-    pos: Chess,
+    /// Clock times by ply, `None` for clocks that do not fit in the `clocks_*` columns.
+    clocks_white: Vec<(u16, Option<u16>)>,
+    clocks_black: Vec<(u16, Option<u16>)>,
+    /// Values of the extracted comment commands: the index of the command, the ply, the side
+    /// that made the move and the value as an element of an SQL list.
+    command_values: Vec<(usize, u16, Color, String)>,
+    pos: VariantPosition,
+    ply: u16,
+    raw: RawGame,
+    /// Set when the game is rejected; the moves after it are only recorded in `raw`.
+    rejection: Option<Rejection>,
+    /// Annotations of the mainline, with `--annotations`.
+    annotations: Option<Annotations>,
+    /// Position before the last mainline move, where a variation starts.
+    prev_pos: Option<VariantPosition>,
+    /// Variations being read, innermost last. `None` for skipped variations.
+    variations: Vec<Option<VariationInProcessing<'a>>>,
+}
+
+/// A variation being read, with `--annotations`.
+struct VariationInProcessing<'a> {
+    encoder: Encoder<'a>,
+    pos: VariantPosition,
+    prev_pos: Option<VariantPosition>,
     ply: u16,
+    /// Ply of the parent line that this variation is an alternative to.
+    anchor: u16,
+    annotations: Annotations,
+    /// Set after an illegal move; the rest of the variation is ignored.
+    invalid: bool,
+}
+
+/// The PGN text of a game, kept to record it in `rejected_games`.
+struct RawGame {
+    tags: String,
+    movetext: String,
+    fullmoves: u32,
+    turn: Color,
+    /// Whether the next move needs a move number, also for a black move.
+    needs_number: bool,
+}
+
+struct Rejection {
+    ply: u16,
+    reason: &'static str,
+    message: String,
+}
+
+impl RawGame {
+    fn push(&mut self, token: &str) {
+        if !self.movetext.is_empty() {
+            self.movetext.push(' ');
+        }
+        self.movetext.push_str(token);
+    }
+
+    fn push_san(&mut self, san: &str) {
+        if self.turn == Color::White {
+            self.push(&format!("{}.", self.fullmoves));
+        } else if self.needs_number {
+            self.push(&format!("{}...", self.fullmoves));
+        }
+        self.push(san);
+
+        if self.turn == Color::Black {
+            self.fullmoves += 1;
+        }
+        self.turn = !self.turn;
+        self.needs_number = false;
+    }
 }
 
 impl GameInProcessing<'_> {
-    fn new(headers: Headers, level: CompressionLevel) -> Self {
+    fn new(
+        headers: Headers,
+        raw_tags: String,
+        start_position: VariantPosition,
+        level: CompressionLevel,
+    ) -> Self {
         GameInProcessing {
             headers,
-            encoder: Encoder::new(level),
+            encoder: Encoder::with_start_position(level, start_position.clone()),
             evals: vec![],
             clocks_white: vec![],
             clocks_black: vec![],
-            pos: Chess::new(),
+            command_values: vec![],
+            raw: RawGame {
+                tags: raw_tags,
+                movetext: String::new(),
+                fullmoves: start_position.fullmoves().get(),
+                turn: start_position.turn(),
+                needs_number: true,
+            },
+            pos: start_position,
             ply: 0,
+            rejection: None,
+            annotations: None,
+            prev_pos: None,
+            variations: vec![],
+        }
+    }
+
+    /// Returns whether annotations are currently read into a variation, rather than the
+    /// mainline.
+    fn in_variation(&self) -> bool {
+        !self.variations.is_empty()
+    }
+
+    /// Adds an annotation to the innermost line being read, if annotations are kept.
+    fn annotate(&mut self, annotation: Annotation) {
+        match self.variations.last_mut() {
+            Some(Some(variation)) if !variation.invalid => {
+                variation.annotations.push(variation.ply, annotation)
+            }
+            Some(_) => {}
+            None => {
+                if let Some(annotations) = &mut self.annotations
+                    && self.rejection.is_none()
+                {
+                    annotations.push(self.ply, annotation);
+                }
+            }
         }
     }
 
@@ -116,13 +464,15 @@ impl GameInProcessing<'_> {
             &self.clocks_black
         };
 
-        if clocks.is_empty() {
+        // a list without the out of range clocks would attribute clocks to the wrong moves
+        if clocks.is_empty() || clocks.iter().any(|(_, clock)| clock.is_none()) {
             return None;
         }
 
         let mut index = 1;
         let mut clocks_out = vec![];
         for &(ply, clock) in clocks {
+            let clock = clock?;
             if index != ply {
                 let n = (ply - index) / 2;
                 let last = clocks_out.last().cloned().unwrap_or(0);
@@ -135,22 +485,99 @@ impl GameInProcessing<'_> {
 
         Some(format!("{:?}", clocks_out))
     }
+
+    /// Returns the SQL lists of the comment command columns, by column name. Lists have an
+    /// element for every move (or every move of one side), `NULL` for moves without a value.
+    fn finalize_commands(&self, commands: &[CommentCommand]) -> HashMap<&'static str, String> {
+        let mut columns = HashMap::new();
+        for (i, command) in commands.iter().enumerate() {
+            let values = self.command_values.iter().filter(|(c, ..)| *c == i);
+            match command.columns() {
+                [(column, _)] => {
+                    let list = values.map(|(_, ply, _, value)| (usize::from(*ply) - 1, value));
+                    if let Some(list) = sql_list(list) {
+                        columns.insert(*column, list);
+                    }
+                }
+                columns_by_side => {
+                    for (color, (column, _)) in [Color::White, Color::Black]
+                        .into_iter()
+                        .zip(columns_by_side)
+                    {
+                        // the n-th move of either side is at ply 2n - 1 or 2n
+                        let list = values
+                            .clone()
+                            .filter(|(_, _, side, _)| *side == color)
+                            .map(|(_, ply, _, value)| ((usize::from(*ply) - 1) / 2, value));
+                        if let Some(list) = sql_list(list) {
+                            columns.insert(*column, list);
+                        }
+                    }
+                }
+            }
+        }
+        columns
+    }
 }
 
-impl<'a> Visitor for PgnProcessor<'a> {
-    type Tags = Headers;
-    type Movetext = GameInProcessing<'a>;
-    type Output = ();
+impl Visitor for PgnProcessor {
+    type Tags = Tags;
+    type Movetext = GameInProcessing<'static>;
+    type Output = ConvertedGame;
 
     fn begin_tags(&mut self) -> ControlFlow<Self::Output, Self::Tags> {
-        ControlFlow::Continue(match &self.header_list {
-            Some(_) => Headers::Custom(CustomHeaders::new()),
-            None => Headers::Lichess(LichessHeaders::default()),
+        ControlFlow::Continue(Tags {
+            headers: match &self.tag_format {
+                TagFormat::Lichess => Headers::Lichess(Box::default()),
+                TagFormat::Chesscom => Headers::Chesscom(Box::default()),
+                TagFormat::Custom(schema) => Headers::Custom(vec![None; schema.columns().len()]),
+            },
+            raw: String::new(),
+            fen: None,
+            variant: Variant::Chess,
+            unsupported_variant: None,
+            chess960: false,
         })
     }
 
     fn begin_movetext(&mut self, tags: Self::Tags) -> ControlFlow<Self::Output, Self::Movetext> {
-        ControlFlow::Continue(GameInProcessing::new(tags, self.level))
+        let castling_mode = if tags.chess960 {
+            CastlingMode::Chess960
+        } else {
+            CastlingMode::Standard
+        };
+        let (start_position, rejection) = match tags
+            .fen
+            .as_deref()
+            .map(|fen| parse_start_position(fen, tags.variant, castling_mode))
+        {
+            None => (VariantPosition::new(tags.variant), None),
+            Some(Ok(pos)) => (pos, None),
+            Some(Err(e)) => (
+                VariantPosition::new(tags.variant),
+                Some(Rejection {
+                    ply: 0,
+                    reason: "invalid_fen",
+                    message: format!("Invalid FEN header: {e}"),
+                }),
+            ),
+        };
+        let rejection = match tags.unsupported_variant {
+            Some(variant) => Some(Rejection {
+                ply: 0,
+                reason: "unsupported_variant",
+                message: format!("Unsupported Variant header: {variant}"),
+            }),
+            None => rejection,
+        };
+
+        let mut movetext =
+            GameInProcessing::new(tags.headers, tags.raw, start_position, self.level);
+        movetext.rejection = rejection;
+        if self.annotations {
+            movetext.annotations = Some(Annotations::new());
+        }
+        ControlFlow::Continue(movetext)
     }
 
     fn san(
@@ -158,62 +585,141 @@ impl<'a> Visitor for PgnProcessor<'a> {
         movetext: &mut Self::Movetext,
         san_plus: SanPlus,
     ) -> ControlFlow<Self::Output> {
-        match san_plus
-            .san
-            .to_string()
-            .parse::<San>()
-            .unwrap()
-            .to_move(&movetext.pos)
-        {
+        if let Some(variation) = movetext.variations.last_mut() {
+            if let Some(variation) = variation
+                && !variation.invalid
+            {
+                match san_plus.san.to_move(&variation.pos) {
+                    Ok(m) => {
+                        variation.prev_pos = Some(variation.pos.clone());
+                        variation.pos.play_unchecked(m);
+                        variation.encoder.encode_move(m).unwrap();
+                        variation.ply += 1;
+                    }
+                    // keep the moves before the illegal move
+                    Err(_) => variation.invalid = true,
+                }
+            }
+            return ControlFlow::Continue(());
+        }
+
+        movetext.raw.push_san(&san_plus.to_string());
+        if movetext.rejection.is_some() {
+            return ControlFlow::Continue(());
+        }
+
+        match san_plus.san.to_move(&movetext.pos) {
             Ok(m) => {
+                if movetext.annotations.is_some() {
+                    movetext.prev_pos = Some(movetext.pos.clone());
+                }
                 movetext.pos.play_unchecked(m);
                 movetext.encoder.encode_move(m).unwrap();
                 movetext.ply += 1;
             }
-            Err(_) => {
-                let error_msg = format!(
-                    "Invalid move '{}' at ply {} in game with headers {:?}",
-                    san_plus.san.to_string(),
-                    movetext.ply + 1,
-                    movetext.headers
-                );
-                if self.continue_on_invalid_move {
-                    eprintln!("{error_msg}");
-
-                    let mut swap = GameInProcessing {
-                        headers: Headers::Custom(CustomHeaders::new()),
-                        encoder: Encoder::new(CompressionLevel::Low),
-                        evals: vec![],
-                        clocks_white: vec![],
-                        clocks_black: vec![],
-                        pos: Chess::new(),
-                        ply: 0,
-                    };
-
-                    std::mem::swap(&mut swap, movetext);
-                    self.end_game_inner(swap);
-
-                    return ControlFlow::Break(());
-                } else {
-                    panic!(
-                        "{error_msg}\nCheck the PGN file or if you want to use --continue-on-invalid-move."
-                    );
-                }
+            Err(e) => {
+                movetext.rejection = Some(Rejection {
+                    ply: movetext.ply + 1,
+                    reason: "illegal_move",
+                    message: format!("Invalid move '{san_plus}': {e}"),
+                });
             }
         }
 
         ControlFlow::Continue(())
     }
 
+    fn nag(&mut self, movetext: &mut Self::Movetext, nag: Nag) -> ControlFlow<Self::Output> {
+        if !movetext.in_variation() {
+            movetext.raw.push(&nag.to_string());
+        }
+        movetext.annotate(Annotation::Nag(nag.0));
+        ControlFlow::Continue(())
+    }
+
+    fn outcome(
+        &mut self,
+        movetext: &mut Self::Movetext,
+        outcome: Outcome,
+    ) -> ControlFlow<Self::Output> {
+        movetext.raw.push(&outcome.to_string());
+        ControlFlow::Continue(())
+    }
+
     fn begin_variation(
         &mut self,
-        _movetext: &mut Self::Movetext,
+        movetext: &mut Self::Movetext,
     ) -> ControlFlow<Self::Output, Skip> {
-        ControlFlow::Continue(Skip(true)) // stay in the mainline
+        // a variation starts from the position before the last move of its parent line
+        let start = match movetext.variations.last() {
+            Some(Some(parent)) if !parent.invalid => {
+                parent.prev_pos.clone().map(|pos| (pos, parent.ply))
+            }
+            Some(_) => None,
+            None if movetext.rejection.is_none() => {
+                movetext.prev_pos.clone().map(|pos| (pos, movetext.ply))
+            }
+            None => None,
+        };
+
+        // deeper variations could not be read back
+        let Some((pos, anchor)) =
+            start.filter(|_| self.annotations && movetext.variations.len() < MAX_VARIATION_DEPTH)
+        else {
+            movetext.variations.push(None);
+            return ControlFlow::Continue(Skip(true)); // stay in the mainline
+        };
+
+        movetext.variations.push(Some(VariationInProcessing {
+            encoder: Encoder::with_start_position(self.level, pos.clone()),
+            pos,
+            prev_pos: None,
+            ply: 0,
+            anchor,
+            annotations: Annotations::new(),
+            invalid: false,
+        }));
+        ControlFlow::Continue(Skip(false))
+    }
+
+    fn end_variation(&mut self, movetext: &mut Self::Movetext) -> ControlFlow<Self::Output> {
+        if let Some(Some(variation)) = movetext.variations.pop()
+            && variation.ply > 0
+        {
+            let annotation = Annotation::Variation(
+                variation.encoder.finish().into_bytes(),
+                variation.annotations,
+            );
+            match movetext.variations.last_mut() {
+                Some(Some(parent)) => parent.annotations.push(variation.anchor, annotation),
+                Some(None) => {}
+                None => {
+                    if let Some(annotations) = &mut movetext.annotations {
+                        annotations.push(variation.anchor, annotation);
+                    }
+                }
+            }
+        }
+
+        ControlFlow::Continue(())
     }
 
-    fn end_game(&mut self, movetext: Self::Movetext) -> Self::Output {
-        self.end_game_inner(movetext)
+    fn end_game(&mut self, mut movetext: Self::Movetext) -> Self::Output {
+        let Some(rejection) = movetext.rejection.take() else {
+            return ConvertedGame::Valid(self.end_game_inner(movetext));
+        };
+
+        let rejected = RejectedGame {
+            tags: std::mem::take(&mut movetext.raw.tags),
+            movetext: std::mem::take(&mut movetext.raw.movetext),
+            ply: rejection.ply,
+            reason: rejection.reason,
+            message: rejection.message,
+        };
+        // a game with an invalid start position has no valid moves to store
+        let truncated = (self.continue_on_invalid_move && rejected.ply > 0)
+            .then(|| self.end_game_inner(movetext));
+        ConvertedGame::Rejected(rejected, truncated)
     }
 
     fn tag(
@@ -222,12 +728,67 @@ impl<'a> Visitor for PgnProcessor<'a> {
         key: &[u8],
         value: pgn_reader::RawTag<'_>,
     ) -> ControlFlow<Self::Output> {
-        match tags {
+        writeln!(
+            tags.raw,
+            "[{} \"{}\"]",
+            String::from_utf8_lossy(key),
+            String::from_utf8_lossy(value.as_bytes())
+        )
+        .unwrap();
+
+        if key == b"FEN" {
+            tags.fen = Some(value.decode_utf8_lossy().into_owned());
+        } else if key == b"Variant" {
+            let variant = value.decode_utf8_lossy().to_lowercase();
+            tags.chess960 = matches!(
+                variant.as_str(),
+                "chess960" | "chess 960" | "fischerandom" | "fischer random"
+            );
+            match Variant::from_ascii(value.as_bytes()) {
+                Ok(variant) => tags.variant = variant,
+                Err(_) if tags.chess960 => tags.variant = Variant::Chess,
+                Err(_) => tags.unsupported_variant = Some(value.decode_utf8_lossy().into_owned()),
+            }
+        }
+
+        match &mut tags.headers {
             Headers::Custom(custom_headers) => {
-                let header_list = self.header_list.as_ref().unwrap();
-                let key_str = String::from_utf8_lossy(key).into_owned().to_lowercase();
-                if header_list.contains(&key_str) {
-                    custom_headers.insert(key_str, value.decode_utf8_lossy().into_owned());
+                let TagFormat::Custom(schema) = &self.tag_format else {
+                    unreachable!("Custom headers are only created for a schema");
+                };
+                for (column, header) in schema.columns().iter().zip(custom_headers) {
+                    if column.matches(key) {
+                        *header = Some(value.decode_utf8_lossy().into_owned());
+                    }
+                }
+            }
+            Headers::Chesscom(chesscom_tags) => {
+                let value = value.decode_utf8_lossy().into_owned();
+                match key {
+                    b"Link" => chesscom_tags.chesscom_id = extract_chesscom_id_from_link(&value),
+                    b"Event" => chesscom_tags.event = Some(value),
+                    b"White" => chesscom_tags.white = Some(value),
+                    b"Black" => chesscom_tags.black = Some(value),
+                    b"WhiteElo" => chesscom_tags.white_rating = value.parse().ok(),
+                    b"BlackElo" => chesscom_tags.black_rating = value.parse().ok(),
+                    b"Result" => chesscom_tags.result = Some(value),
+                    b"Termination" => chesscom_tags.termination = Some(value),
+                    b"TimeControl" => {
+                        if let Some((_, seconds)) = value.split_once('/') {
+                            chesscom_tags.daily_seconds = seconds.parse().ok();
+                        } else if let Ok(initial) = value.parse() {
+                            chesscom_tags.time_control = Some((initial, 0));
+                        } else {
+                            chesscom_tags.time_control = parse_time_control(&value);
+                        }
+                    }
+                    b"ECO" => chesscom_tags.eco = Some(value),
+                    b"ECOUrl" => chesscom_tags.eco_url = Some(value),
+                    b"UTCDate" => chesscom_tags.utc_date = Some(value),
+                    b"UTCTime" => chesscom_tags.utc_time = Some(value),
+                    b"EndDate" => chesscom_tags.end_date = Some(value),
+                    b"EndTime" => chesscom_tags.end_time = Some(value),
+                    _ => {}
                 }
             }
             Headers::Lichess(lichess_tags) => match key {
@@ -282,97 +843,375 @@ impl<'a> Visitor for PgnProcessor<'a> {
         comment: pgn_reader::RawComment<'_>,
     ) -> ControlFlow<Self::Output> {
         let cmt = std::str::from_utf8(comment.as_bytes()).unwrap_or("");
+        let text = strip_extracted_commands(cmt, &self.commands);
+        if !text.is_empty() {
+            movetext.annotate(Annotation::Comment(text.to_owned()));
+        }
+        if movetext.in_variation() {
+            return ControlFlow::Continue(());
+        }
+
+        movetext.raw.push(&format!(
+            "{{{}}}",
+            String::from_utf8_lossy(comment.as_bytes())
+        ));
+        movetext.raw.needs_number = true;
+        if movetext.rejection.is_some() {
+            return ControlFlow::Continue(());
+        }
 
         if let Some(eval_cp) = extract_eval_cp_from_comment(cmt) {
             movetext.evals.push((movetext.ply, eval_cp));
         }
 
         if let Some(clock_seconds) = extract_clock_seconds_from_comment(cmt) {
-            if movetext.ply % 2 == 1 {
+            if movetext.pos.turn() == Color::Black {
                 movetext.clocks_white.push((movetext.ply, clock_seconds));
             } else {
                 movetext.clocks_black.push((movetext.ply, clock_seconds));
             }
         }
 
+        if movetext.ply > 0 && !self.commands.is_empty() {
+            let side = !movetext.pos.turn();
+            for (_, [name, args]) in
+                regex_captures_iter!(r"\[%(\w+)\s+([^\]]*)\]", cmt).map(|c| c.extract())
+            {
+                for (i, command) in self.commands.iter().enumerate() {
+                    if let Some(value) = command.extract(name, args) {
+                        movetext.command_values.push((i, movetext.ply, side, value));
+                    }
+                }
+            }
+        }
+
         ControlFlow::Continue(())
     }
 }
 
-impl<'a> PgnProcessor<'a> {
-    fn end_game_inner(
-        &mut self,
-        movetext: <Self as Visitor>::Movetext,
-    ) -> <Self as Visitor>::Output {
-        let clocks_w = movetext.finalize_clocks(true);
-        let clocks_b = movetext.finalize_clocks(false);
+impl PgnProcessor {
+    fn end_game_inner(&self, movetext: <Self as Visitor>::Movetext) -> GameRow {
+        let clocks_white = movetext.finalize_clocks(true);
+        let clocks_black = movetext.finalize_clocks(false);
         let evals = movetext.finalize_evals();
-        let moves = movetext.encoder.finish();
-        let bytes = moves.into_bytes();
-
-        match movetext.headers {
-            Headers::Lichess(headers) => self
-                .appender
-                .append_row(params![
-                    headers.lichess_id,
-                    headers.tournament,
-                    bytes,
-                    clocks_w,
-                    clocks_b,
-                    evals,
-                    movetext.ply,
-                    headers.white,
-                    headers.black,
-                    headers.white_rating,
-                    headers.black_rating,
-                    headers.time_control.map(|c| c.0),
-                    headers.time_control.map(|c| c.1),
-                    headers.result,
-                    headers.termination,
-                    headers.white_rating_diff,
-                    headers.black_rating_diff,
-                    headers.eco,
-                    headers.opening,
-                    headers.white_title,
-                    headers.black_title,
-                    headers.utc_date.and_then(|date| {
-                        headers
-                            .utc_time
-                            .map(|time| format!("{} {}", date.replace(".", "-"), time))
-                    }),
-                ])
-                .unwrap(),
+        let commands = movetext.finalize_commands(&self.commands);
+        let mut moves = movetext.encoder.finish();
+        if let Some(interval) = self.keyframe_interval {
+            moves = moves
+                .with_keyframes(interval)
+                .expect("a game that was just encoded can be decoded");
+        }
+
+        GameRow {
+            headers: movetext.headers,
+            compression_level: moves.compression_level(),
+            movedata: moves.into_bytes(),
+            clocks_white,
+            clocks_black,
+            evals,
+            ply: movetext.ply,
+            commands,
+            annotations: movetext
+                .annotations
+                .filter(|a| !a.is_empty())
+                .map(|a| a.to_bytes()),
+        }
+    }
+}
+
+impl GameWriter<'_> {
+    pub fn append(&mut self, game: ConvertedGame) {
+        match game {
+            ConvertedGame::Valid(row) => self.append_row(row),
+            ConvertedGame::Rejected(rejected, truncated) => {
+                self.stats
+                    .add_rejected(rejected.reason, truncated.is_some());
+                self.rejected_appender
+                    .append_row(params![
+                        self.input,
+                        rejected.tags,
+                        rejected.movetext,
+                        rejected.ply,
+                        rejected.reason,
+                        rejected.message,
+                    ])
+                    .unwrap();
+                if let Some(row) = truncated {
+                    self.append_row(row);
+                }
+            }
+        }
+    }
+
+    fn append_row(&mut self, row: GameRow) {
+        self.stats
+            .add_game(row.compression_level, row.movedata.len());
+        let bytes = row.movedata;
+        let clocks_w = row.clocks_white;
+        let clocks_b = row.clocks_black;
+        let evals = row.evals;
+
+        let mut params_vec: Vec<Box<dyn duckdb::ToSql>> = match row.headers {
+            Headers::Lichess(headers) => vec![
+                Box::new(headers.lichess_id),
+                Box::new(headers.tournament),
+                Box::new(bytes),
+                Box::new(clocks_w),
+                Box::new(clocks_b),
+                Box::new(evals),
+                Box::new(row.ply),
+                Box::new(headers.white),
+                Box::new(headers.black),
+                Box::new(headers.white_rating),
+                Box::new(headers.black_rating),
+                Box::new(headers.time_control.map(|c| c.0)),
+                Box::new(headers.time_control.map(|c| c.1)),
+                Box::new(headers.result),
+                Box::new(headers.termination),
+                Box::new(headers.white_rating_diff),
+                Box::new(headers.black_rating_diff),
+                Box::new(headers.eco),
+                Box::new(headers.opening),
+                Box::new(headers.white_title),
+                Box::new(headers.black_title),
+                Box::new(
+                    headers
+                        .utc_date
+                        .zip(headers.utc_time)
+                        .map(|(date, time)| format!("{} {}", date.replace(".", "-"), time)),
+                ),
+            ],
+            Headers::Chesscom(headers) => vec![
+                Box::new(headers.chesscom_id),
+                Box::new(headers.event),
+                Box::new(bytes),
+                Box::new(clocks_w),
+                Box::new(clocks_b),
+                Box::new(evals),
+                Box::new(row.ply),
+                Box::new(headers.white),
+                Box::new(headers.black),
+                Box::new(headers.white_rating),
+                Box::new(headers.black_rating),
+                Box::new(headers.time_control.map(|c| c.0)),
+                Box::new(headers.time_control.map(|c| c.1)),
+                Box::new(headers.daily_seconds),
+                Box::new(headers.result),
+                Box::new(headers.termination),
+                Box::new(headers.eco),
+                Box::new(headers.eco_url),
+                Box::new(chesscom_timestamp(headers.utc_date, headers.utc_time)),
+                Box::new(chesscom_timestamp(headers.end_date, headers.end_time)),
+            ],
             Headers::Custom(headers) => {
                 let mut params_vec: Vec<Box<dyn duckdb::ToSql>> = vec![];
-                for header in self
-                    .header_list
-                    .as_ref()
-                    .expect("header_list cannot be None for Custom headers")
-                {
-                    params_vec.push(Box::new(headers.get(header)));
+                let TagFormat::Custom(schema) = &self.tag_format else {
+                    unreachable!("Custom headers are only created for a schema");
+                };
+                for (column, header) in schema.columns().iter().zip(headers) {
+                    params_vec.extend(column.values(header));
                 }
 
                 params_vec.push(Box::new(bytes));
                 params_vec.push(Box::new(clocks_w));
                 params_vec.push(Box::new(clocks_b));
                 params_vec.push(Box::new(evals));
-                params_vec.push(Box::new(movetext.ply));
+                params_vec.push(Box::new(row.ply));
+                params_vec
+            }
+        };
 
-                self.appender
-                    .append_row(duckdb::appender_params_from_iter(params_vec))
-                    .unwrap();
+        for column in &self.extra_columns {
+            match column {
+                ExtraColumn::SourceFile => params_vec.push(Box::new(self.input.clone())),
+                ExtraColumn::Annotations => params_vec.push(Box::new(row.annotations.clone())),
+                ExtraColumn::Command(name) => {
+                    params_vec.push(Box::new(row.commands.get(name).cloned()))
+                }
             }
         }
 
+        self.appender
+            .append_row(duckdb::appender_params_from_iter(params_vec))
+            .unwrap();
+
         self.count += 1;
-        if self.count % 10000 == 0 {
-            println!("{} done", self.count);
+        self.uncommitted += 1;
+        self.progress.game_done(self.count);
+
+        self.unwritten += 1;
+        if self
+            .parquet
+            .as_ref()
+            .is_some_and(|p| self.unwritten >= p.games_per_file())
+        {
+            self.write_parquet_file();
         }
     }
 }
 
+fn parse_start_position(
+    fen: &str,
+    variant: Variant,
+    castling_mode: CastlingMode,
+) -> Result<VariantPosition, String> {
+    let fen: Fen = fen.parse().map_err(|e| format!("{e}"))?;
+    VariantPosition::from_setup(variant, fen.into_setup(), castling_mode)
+        .map_err(|e| format!("{e}"))
+}
+
+/// A comment command (`[%name arguments]`) that can be extracted into its own columns.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommentCommand {
+    /// `[%clk]` in milliseconds, keeping fractions of a second and clocks of more than 18 hours.
+    ClockMs,
+    /// Elapsed move time, `[%emt]`, in milliseconds.
+    Emt,
+    /// Correspondence clock, `[%clkc]`, in milliseconds.
+    Clkc,
+    /// Engine search depth, from `[%eval score,depth]` or `[%depth]`.
+    Depth,
+    /// Highlighted squares, `[%csl]`, e.g. `Gd4`.
+    Csl,
+    /// Arrows, `[%cal]`, e.g. `Ge2e4`.
+    Cal,
+}
+
+impl CommentCommand {
+    pub const ALL: [CommentCommand; 6] = [
+        CommentCommand::ClockMs,
+        CommentCommand::Emt,
+        CommentCommand::Clkc,
+        CommentCommand::Depth,
+        CommentCommand::Csl,
+        CommentCommand::Cal,
+    ];
+
+    /// Returns the columns filled by the command and their types: one column with a value per
+    /// move, or a column for White and a column for Black with a value per move of that side.
+    pub fn columns(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            CommentCommand::ClockMs => &[
+                ("clocks_white_ms", "UBIGINT[]"),
+                ("clocks_black_ms", "UBIGINT[]"),
+            ],
+            CommentCommand::Emt => &[("emt_white", "UBIGINT[]"), ("emt_black", "UBIGINT[]")],
+            CommentCommand::Clkc => &[("clkc_white", "UBIGINT[]"), ("clkc_black", "UBIGINT[]")],
+            CommentCommand::Depth => &[("eval_depths", "USMALLINT[]")],
+            CommentCommand::Csl => &[("csl", "VARCHAR[][]")],
+            CommentCommand::Cal => &[("cal", "VARCHAR[][]")],
+        }
+    }
+
+    /// Returns the name of the command in comments.
+    fn name(self) -> &'static str {
+        match self {
+            CommentCommand::ClockMs => "clk",
+            CommentCommand::Emt => "emt",
+            CommentCommand::Clkc => "clkc",
+            CommentCommand::Depth => "depth",
+            CommentCommand::Csl => "csl",
+            CommentCommand::Cal => "cal",
+        }
+    }
+
+    /// Returns the value of a `[%name args]` command as an element of an SQL list, or `None`
+    /// if the command is not extracted by `self` or its arguments are malformed.
+    fn extract(self, name: &str, args: &str) -> Option<String> {
+        let args = args.trim();
+        if self == CommentCommand::Depth && name == "eval" {
+            let (_, depth) = args.split_once(',')?;
+            return depth.trim().parse::<u16>().ok().map(|d| d.to_string());
+        }
+        if name != self.name() {
+            return None;
+        }
+
+        match self {
+            CommentCommand::ClockMs | CommentCommand::Emt | CommentCommand::Clkc => {
+                parse_duration_ms(args).map(|ms| ms.to_string())
+            }
+            CommentCommand::Depth => args.parse::<u16>().ok().map(|d| d.to_string()),
+            CommentCommand::Csl | CommentCommand::Cal => {
+                let items: Vec<&str> = args
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                    .collect();
+                // squares and arrows are alphanumeric, so they need no quoting in the list
+                let valid = items
+                    .iter()
+                    .all(|i| i.bytes().all(|b| b.is_ascii_alphanumeric()));
+                (valid && !items.is_empty()).then(|| format!("[{}]", items.join(", ")))
+            }
+        }
+    }
+}
+
+/// Formats `(index, element)` pairs as an SQL list, with `NULL` for missing indices and the
+/// first element for repeated indices. Returns `None` for no elements.
+fn sql_list<'a>(elements: impl Iterator<Item = (usize, &'a String)>) -> Option<String> {
+    let mut list: Vec<Option<&str>> = vec![];
+    for (index, element) in elements {
+        if list.len() <= index {
+            list.resize(index + 1, None);
+        }
+        list[index].get_or_insert(element);
+    }
+
+    (!list.is_empty()).then(|| {
+        let elements: Vec<&str> = list.into_iter().map(|e| e.unwrap_or("NULL")).collect();
+        format!("[{}]", elements.join(", "))
+    })
+}
+
+/// Parses a duration of the form `h:mm:ss`, `d:hh:mm:ss` or either with a fraction of a
+/// second, e.g. `0:03:25.4`, into milliseconds.
+fn parse_duration_ms(s: &str) -> Option<u64> {
+    let (whole, fraction) = s.split_once('.').unwrap_or((s, ""));
+    let parts = whole
+        .split(':')
+        .map(|part| part.parse::<u64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    let seconds = match parts[..] {
+        [h, m, s] => hms_to_seconds(h, m, s)?,
+        [d, h, m, s] => d
+            .checked_mul(86400)?
+            .checked_add(hms_to_seconds(h, m, s)?)?,
+        _ => return None,
+    };
+
+    if !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let millis = format!("{fraction:0<3}")[..3].parse::<u64>().ok()?;
+    seconds.checked_mul(1000)?.checked_add(millis)
+}
+
+/// Returns `h:m:s` in seconds, or `None` if it overflows.
+fn hms_to_seconds(h: u64, m: u64, s: u64) -> Option<u64> {
+    h.checked_mul(3600)?
+        .checked_add(m.checked_mul(60)?)?
+        .checked_add(s)
+}
+
+/// Removes the `[%clk]` and `[%eval]` commands, and the comment commands extracted with
+/// `commands`, which have their own columns, from a comment.
+fn strip_extracted_commands(comment: &str, commands: &[CommentCommand]) -> String {
+    regex_replace_all!(r"\[%(\w+)\s[^\]]*\]", comment, |whole: &str, name: &str| {
+        if name == "clk" || name == "eval" || commands.iter().any(|c| c.name() == name) {
+            String::new()
+        } else {
+            whole.to_owned()
+        }
+    })
+    .split_whitespace()
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
 fn extract_eval_cp_from_comment(comment: &str) -> Option<i16> {
-    regex_captures!(r"\[%eval (-?\d+\.\d+|#-?\d+)\]", comment)
+    regex_captures!(r"\[%eval (-?\d+\.\d+|#-?\d+)(?:,\d+)?\]", comment)
         .and_then(|(_whole, eval)| eval_capture_to_cp(eval))
 }
 
@@ -390,24 +1229,114 @@ fn eval_capture_to_cp(eval: &str) -> Option<i16> {
     }
 }
 
-fn extract_clock_seconds_from_comment(comment: &str) -> Option<u16> {
-    regex_captures!(r"\[%clk ([0-9]+):([0-9]{2}):([0-9]{2})\]", comment).map(|(_whole, h, m, s)| {
-        h.parse::<u16>().unwrap() * 3600
-            + m.parse::<u16>().unwrap() * 60
-            + s.parse::<u16>().unwrap()
+/// Returns the clock time in seconds of the `[%clk]` command of a comment, `Some(None)` if it
+/// does not fit in a u16 (above ~18 hours, see `CommentCommand::ClockMs`).
+fn extract_clock_seconds_from_comment(comment: &str) -> Option<Option<u16>> {
+    regex_captures!(
+        r"\[%clk ([0-9]+):([0-9]{2}):([0-9]{2})(?:\.[0-9]+)?\]",
+        comment
+    )
+    .map(|(_whole, h, m, s)| {
+        let minutes_seconds = m.parse::<u64>().unwrap() * 60 + s.parse::<u64>().unwrap();
+        h.parse::<u64>()
+            .ok()
+            .and_then(|h| h.checked_mul(3600)?.checked_add(minutes_seconds))
+            .and_then(|seconds| u16::try_from(seconds).ok())
     })
 }
 
-fn parse_time_control(s: &str) -> Option<(u16, u8)> {
+pub fn parse_time_control(s: &str) -> Option<(u16, u8)> {
     if s == "-" {
         return None;
     }
 
-    let parts: Vec<&str> = s.split("+").collect();
-    Some((parts[0].parse().unwrap(), parts[1].parse().unwrap()))
+    // malformed time controls (e.g. "40/7200:3600") are stored as NULL
+    let (initial, increment) = s.split_once('+')?;
+    Some((initial.parse().ok()?, increment.parse().ok()?))
+}
+
+/// Extracts `live/<id>` or `daily/<id>` from the `Link` of a chess.com game, e.g.
+/// `https://www.chess.com/game/live/123456789`.
+fn extract_chesscom_id_from_link(s: &str) -> Option<String> {
+    regex_captures!(
+        r"chess\.com/(?:game/(live|daily)|(live|daily)/game)/(\d+)",
+        s
+    )
+    .map(|(_, kind, old_kind, id)| format!("{kind}{old_kind}/{id}"))
+}
+
+/// Combines the `YYYY.MM.DD` date and `hh:mm:ss` time tags of chess.com games into a timestamp.
+/// Malformed dates or times are stored as `NULL`.
+fn chesscom_timestamp(date: Option<String>, time: Option<String>) -> Option<String> {
+    let date = parse_date(&date?)?;
+    let time = time?;
+    let time = time.trim();
+    regex_is_match!(r"^([01]\d|2[0-3]):[0-5]\d:[0-5]\d$", time).then(|| format!("{date} {time}"))
 }
 
 fn extract_tournament_from_event(s: &str) -> Option<String> {
     regex_captures!(r"lichess\.org/tournament/(\w+)", s)
         .map(|(_whole, tournament)| tournament.to_owned())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_clocks() {
+        assert_eq!(
+            extract_clock_seconds_from_comment("[%clk 0:03:25.4]"),
+            Some(Some(205))
+        );
+        assert_eq!(
+            extract_clock_seconds_from_comment("[%clk 18:12:15]"),
+            Some(Some(65535))
+        );
+        assert_eq!(
+            extract_clock_seconds_from_comment("[%clk 18:12:16]"),
+            Some(None)
+        );
+        assert_eq!(
+            extract_clock_seconds_from_comment("[%clk 99999999999999999999:00:00]"),
+            Some(None)
+        );
+        assert_eq!(extract_clock_seconds_from_comment("[%eval 0.17]"), None);
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration_ms("0:03:25.4"), Some(205_400));
+        assert_eq!(parse_duration_ms("1:00:00:00.25"), Some(86_400_250));
+        assert_eq!(parse_duration_ms("3:25"), None);
+        assert_eq!(
+            parse_duration_ms(&format!("{}:00:00", u64::MAX / 3600 + 1)),
+            None
+        );
+        assert_eq!(
+            parse_duration_ms(&format!("{}:0:00:00", u64::MAX / 86400 + 1)),
+            None
+        );
+        assert_eq!(
+            parse_duration_ms(&format!("0:00:{}", u64::MAX / 1000 + 1)),
+            None
+        );
+    }
+
+    #[test]
+    fn finalizes_clocks() {
+        let mut game = GameInProcessing::new(
+            Headers::Lichess(Box::default()),
+            String::new(),
+            VariantPosition::Chess(shakmaty::Chess::default()),
+            CompressionLevel::Low,
+        );
+        game.clocks_white = vec![(1, Some(180)), (5, Some(170))];
+        game.clocks_black = vec![(2, Some(180)), (4, None)];
+        assert_eq!(
+            game.finalize_clocks(true),
+            Some("[180, 180, 170]".to_owned())
+        );
+        assert_eq!(game.finalize_clocks(false), None);
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::BTreeMap;
+use std::io::{IsTerminal, Write};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use aix_chess_compression::CompressionLevel;
+use serde::Serialize;
+
+/// Minimum time between two redraws of the progress bar.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Number of games between two checks whether the progress bar should be redrawn.
+const CHECK_GAMES: u64 = 100;
+
+/// Number of games between two progress lines when standard error is not a terminal.
+const LINE_GAMES: u64 = 10000;
+
+/// Reports the progress of a conversion: a progress bar on standard error if it is a terminal,
+/// or a line every 10,000 games on standard output otherwise.
+pub struct Progress {
+    start: Instant,
+    last_draw: Option<Instant>,
+    terminal: bool,
+    /// Total size of the inputs, unknown when reading from standard input.
+    total_bytes: Option<u64>,
+    /// Size of the inputs that are completely read.
+    finished_bytes: u64,
+    /// Bytes read from the current input, before decompression.
+    bytes_read: Arc<AtomicU64>,
+    /// Number of games of the conversion that was resumed.
+    resumed_games: u64,
+}
+
+impl Progress {
+    pub fn new(total_bytes: Option<u64>, resumed_games: u64) -> Progress {
+        Progress {
+            start: Instant::now(),
+            last_draw: None,
+            terminal: std::io::stderr().is_terminal(),
+            total_bytes,
+            finished_bytes: 0,
+            bytes_read: Arc::new(AtomicU64::new(0)),
+            resumed_games,
+        }
+    }
+
+    /// Starts reading the next input. Returns the counter of the bytes read from it, for
+    /// `input::open_input`.
+    pub fn begin_input(&mut self) -> Arc<AtomicU64> {
+        self.finished_bytes += self.bytes_read.load(Ordering::Relaxed);
+        self.bytes_read = Arc::new(AtomicU64::new(0));
+        self.end_line();
+        Arc::clone(&self.bytes_read)
+    }
+
+    /// Returns the number of bytes read from all inputs.
+    pub fn bytes_read(&self) -> u64 {
+        self.finished_bytes + self.bytes_read.load(Ordering::Relaxed)
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// Called after a game was appended, with the total number of games.
+    pub fn game_done(&mut self, count: u64) {
+        if !self.terminal {
+            if count.is_multiple_of(LINE_GAMES) {
+                println!("{count} done");
+            }
+            return;
+        }
+
+        if count.is_multiple_of(CHECK_GAMES)
+            && self
+                .last_draw
+                .is_none_or(|last| last.elapsed() >= REDRAW_INTERVAL)
+        {
+            self.draw(count);
+        }
+    }
+
+    /// Ends the line of the progress bar, so that other output starts on a new line.
+    pub fn end_line(&mut self) {
+        if self.last_draw.take().is_some() {
+            eprintln!();
+        }
+    }
+
+    fn draw(&mut self, count: u64) {
+        let elapsed = self.start.elapsed().as_secs_f64();
+        let games_per_second = (count - self.resumed_games) as f64 / elapsed;
+        let bytes_read = self.bytes_read();
+
+        let mut line = format!("\r{}", format_bytes(bytes_read));
+        if let Some(total) = self.total_bytes.filter(|t| *t > 0) {
+            let fraction = (bytes_read as f64 / total as f64).min(1.0);
+            line += &format!(" / {} ({:.1}%)", format_bytes(total), fraction * 100.0);
+            if fraction > 0.0 {
+                let remaining = elapsed * (1.0 - fraction) / fraction;
+                line += &format!(", ETA {}", format_duration(remaining));
+            }
+        }
+        line += &format!(", {count} games, {games_per_second:.0} games/s\x1b[K");
+
+        let mut stderr = std::io::stderr().lock();
+        let _ = stderr
+            .write_all(line.as_bytes())
+            .and_then(|_| stderr.flush());
+        self.last_draw = Some(Instant::now());
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = "B";
+    for next in UNITS {
+        if value < 1024.0 {
+            break;
+        }
+        value /= 1024.0;
+        unit = next;
+    }
+    if unit == "B" {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {unit}")
+    }
+}
+
+fn format_duration(seconds: f64) -> String {
+    let seconds = seconds.round() as u64;
+    format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// Statistics of a conversion, written as JSON with `--stats-json`. Only the games converted in
+/// this run are counted, also when resuming.
+#[derive(Default, Serialize)]
+pub struct Stats {
+    /// Games appended to the `games` table, including truncated games.
+    pub games_written: u64,
+    pub games_rejected: u64,
+    pub rejected_by_reason: BTreeMap<&'static str, u64>,
+    /// Rejected games that were stored up to the invalid move, with
+    /// `--continue-on-invalid-move`.
+    pub games_truncated: u64,
+    /// Bytes read from the inputs, before decompression.
+    pub input_bytes: u64,
+    pub elapsed_seconds: f64,
+    pub games_per_second: f64,
+    /// Total size of the `movedata` of the written games.
+    pub movedata_bytes: u64,
+    /// Games and `movedata` size by the compression level the games are encoded with.
+    pub compression_levels: BTreeMap<&'static str, LevelStats>,
+}
+
+#[derive(Default, Serialize)]
+pub struct LevelStats {
+    pub games: u64,
+    pub movedata_bytes: u64,
+    pub average_bytes_per_game: f64,
+}
+
+impl Stats {
+    pub fn add_game(&mut self, level: CompressionLevel, movedata_bytes: usize) {
+        let level = self
+            .compression_levels
+            .entry(match level {
+                CompressionLevel::Low => "low",
+                CompressionLevel::Medium => "medium",
+                CompressionLevel::High => "high",
+            })
+            .or_default();
+        level.games += 1;
+        level.movedata_bytes += movedata_bytes as u64;
+        self.games_written += 1;
+        self.movedata_bytes += movedata_bytes as u64;
+    }
+
+    pub fn add_rejected(&mut self, reason: &'static str, truncated: bool) {
+        self.games_rejected += 1;
+        *self.rejected_by_reason.entry(reason).or_default() += 1;
+        if truncated {
+            self.games_truncated += 1;
+        }
+    }
+
+    /// Completes the statistics at the end of the conversion.
+    pub fn finish(&mut self, progress: &Progress) {
+        let elapsed = progress.elapsed().as_secs_f64();
+        self.input_bytes = progress.bytes_read();
+        self.elapsed_seconds = elapsed;
+        self.games_per_second = if elapsed > 0.0 {
+            self.games_written as f64 / elapsed
+        } else {
+            0.0
+        };
+        for level in self.compression_levels.values_mut() {
+            level.average_bytes_per_game = level.movedata_bytes as f64 / level.games as f64;
+        }
+    }
+}
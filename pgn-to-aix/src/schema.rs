@@ -0,0 +1,219 @@
+use duckdb::ToSql;
+use serde::Deserialize;
+
+use crate::pgn::{CommentCommand, parse_time_control};
+
+/// Columns written by pgn-to-aix next to the tag columns, which a schema cannot use.
+const GAME_COLUMNS: [&str; 7] = [
+    "movedata",
+    "clocks_white",
+    "clocks_black",
+    "evals",
+    "ply_count",
+    "source_file",
+    "annotations",
+];
+
+/// Maps PGN tags to typed columns of the `games` table, for PGN files that are not Lichess or
+/// chess.com games.
+#[derive(Clone, Debug)]
+pub struct Schema {
+    columns: Vec<SchemaColumn>,
+}
+
+/// A PGN tag and the column(s) it is stored in.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SchemaColumn {
+    /// Name of the tag, matched case-insensitively.
+    tag: String,
+    /// Name of the column, the lowercase tag name by default. For `time_control`, the prefix
+    /// of the `_initial` and `_increment` columns.
+    #[serde(default)]
+    column: Option<String>,
+    #[serde(default, rename = "type")]
+    column_type: ColumnType,
+}
+
+/// Type of a tag column.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnType {
+    /// The tag value as is.
+    #[default]
+    Varchar,
+    /// A whole number, e.g. a FIDE ID.
+    Integer,
+    /// A rating, like `white_rating` of Lichess databases.
+    Elo,
+    /// A `YYYY.MM.DD` date. Dates with unknown parts (`2024.??.??`) are stored as `NULL`.
+    Date,
+    /// A `initial+increment` time control in seconds, split into two columns like
+    /// `time_initial` and `time_increment` of Lichess databases.
+    TimeControl,
+}
+
+/// Built-in schemas for common PGN sources.
+#[derive(Clone, Copy, Debug)]
+pub enum Preset {
+    /// The Week in Chess.
+    Twic,
+    /// PGNs of FIDE-rated events.
+    Fide,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SchemaFile {
+    columns: Vec<SchemaColumn>,
+}
+
+impl Schema {
+    /// Creates a schema with a `VARCHAR` column for every tag of `--headers`.
+    pub fn from_headers(headers: &[String]) -> Result<Schema, String> {
+        Schema::new(
+            headers
+                .iter()
+                .map(|tag| SchemaColumn {
+                    tag: tag.clone(),
+                    column: None,
+                    column_type: ColumnType::Varchar,
+                })
+                .collect(),
+        )
+    }
+
+    /// Reads a schema from JSON, e.g.
+    /// `{"columns": [{"tag": "WhiteElo", "column": "white_rating", "type": "elo"}]}`.
+    pub fn from_json(json: &str) -> Result<Schema, String> {
+        let file: SchemaFile =
+            serde_json::from_str(json).map_err(|e| format!("Invalid schema: {e}"))?;
+        Schema::new(file.columns)
+    }
+
+    pub fn preset(preset: Preset) -> Schema {
+        let json = match preset {
+            Preset::Twic => include_str!("schemas/twic.json"),
+            Preset::Fide => include_str!("schemas/fide.json"),
+        };
+        Schema::from_json(json).expect("built-in schemas are valid")
+    }
+
+    fn new(columns: Vec<SchemaColumn>) -> Result<Schema, String> {
+        if columns.is_empty() {
+            return Err("The schema must have at least one column.".to_owned());
+        }
+        if columns.iter().any(|c| c.tag.trim().is_empty()) {
+            return Err("The schema cannot contain empty tag names.".to_owned());
+        }
+
+        let reserved = GAME_COLUMNS.iter().copied().chain(
+            CommentCommand::ALL
+                .iter()
+                .flat_map(|command| command.columns())
+                .map(|(column, _)| *column),
+        );
+        let mut names: Vec<String> = reserved.map(str::to_owned).collect();
+        for column in &columns {
+            for (name, _) in column.sql_columns() {
+                // DuckDB identifiers are case-insensitive
+                let name = name.to_lowercase();
+                if name.is_empty() {
+                    return Err("The schema cannot contain empty column names.".to_owned());
+                }
+                if names.contains(&name) {
+                    return Err(format!("Column '{name}' is defined twice in the schema."));
+                }
+                names.push(name);
+            }
+        }
+
+        Ok(Schema { columns })
+    }
+
+    pub fn columns(&self) -> &[SchemaColumn] {
+        &self.columns
+    }
+
+    /// Returns the column definitions for `init-other-database.sql`.
+    pub fn columns_sql(&self) -> String {
+        self.columns
+            .iter()
+            .flat_map(|column| column.sql_columns())
+            .map(|(name, column_type)| format!("{} {column_type}", quote_identifier(&name)))
+            .collect::<Vec<_>>()
+            .join(",\n  ")
+    }
+}
+
+impl SchemaColumn {
+    /// Returns whether `tag` is stored in this column.
+    pub fn matches(&self, tag: &[u8]) -> bool {
+        self.tag.as_bytes().eq_ignore_ascii_case(tag)
+    }
+
+    fn name(&self) -> String {
+        self.column
+            .clone()
+            .unwrap_or_else(|| self.tag.to_lowercase())
+    }
+
+    /// Returns the names and types of the columns of this tag.
+    fn sql_columns(&self) -> Vec<(String, &'static str)> {
+        let name = self.name();
+        match self.column_type {
+            ColumnType::Varchar => vec![(name, "VARCHAR")],
+            ColumnType::Integer => vec![(name, "INTEGER")],
+            ColumnType::Elo => vec![(name, "SMALLINT")],
+            ColumnType::Date => vec![(name, "DATE")],
+            ColumnType::TimeControl => vec![
+                (format!("{name}_initial"), "USMALLINT"),
+                (format!("{name}_increment"), "UTINYINT"),
+            ],
+        }
+    }
+
+    /// Converts the value of the tag into the values of its columns. Values that do not match
+    /// the type are stored as `NULL`.
+    pub fn values(&self, value: Option<String>) -> Vec<Box<dyn ToSql>> {
+        match self.column_type {
+            ColumnType::Varchar => vec![Box::new(value)],
+            ColumnType::Integer => vec![Box::new(value.and_then(|v| v.parse::<i32>().ok()))],
+            ColumnType::Elo => vec![Box::new(value.and_then(|v| v.parse::<i16>().ok()))],
+            ColumnType::Date => vec![Box::new(value.and_then(|v| parse_date(&v)))],
+            ColumnType::TimeControl => {
+                let time_control = value.and_then(|v| parse_time_control(&v));
+                vec![
+                    Box::new(time_control.map(|c| c.0)),
+                    Box::new(time_control.map(|c| c.1)),
+                ]
+            }
+        }
+    }
+}
+
+/// Quotes a column name for SQL.
+pub fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Converts a PGN `YYYY.MM.DD` date into an SQL `YYYY-MM-DD` date.
+pub fn parse_date(date: &str) -> Option<String> {
+    let mut parts = date.trim().split('.').map(|p| p.parse::<u32>().ok());
+    let (year, month, day) = (parts.next()??, parts.next()??, parts.next()??);
+    if parts.next().is_some() || year > 9999 {
+        return None;
+    }
+
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return None,
+    };
+    (1..=days)
+        .contains(&day)
+        .then(|| format!("{year:04}-{month:02}-{day:02}"))
+}
@@ -0,0 +1,8 @@
+INSERT INTO games
+SELECT s.*
+FROM games_staging AS s
+WHERE s.lichess_id IS NULL
+  OR NOT EXISTS (SELECT 1 FROM games AS g WHERE g.lichess_id = s.lichess_id)
+QUALIFY s.lichess_id IS NULL
+  OR row_number() OVER (PARTITION BY s.lichess_id ORDER BY s.rowid) = 1
+ORDER BY s.rowid
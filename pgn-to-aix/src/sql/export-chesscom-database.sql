@@ -0,0 +1,32 @@
+SELECT
+  movedata,
+  CAST(clocks_white AS USMALLINT[]),
+  CAST(clocks_black AS USMALLINT[]),
+  CAST(evals AS SMALLINT[]),
+  $ANNOTATIONS AS annotations,
+  $CLOCKS_WHITE_MS AS clocks_white_ms,
+  $CLOCKS_BLACK_MS AS clocks_black_ms,
+  event AS "Event",
+  'Chess.com' AS "Site",
+  strftime(utc_timestamp, '%Y.%m.%d') AS "Date",
+  '-' AS "Round",
+  white AS "White",
+  black AS "Black",
+  result AS "Result",
+  eco AS "ECO",
+  eco_url AS "ECOUrl",
+  CAST(white_rating AS VARCHAR) AS "WhiteElo",
+  CAST(black_rating AS VARCHAR) AS "BlackElo",
+  COALESCE(
+    '1/' || daily_seconds,
+    CASE WHEN time_increment = 0 THEN CAST(time_initial AS VARCHAR) ELSE time_initial || '+' || time_increment END,
+    '-'
+  ) AS "TimeControl",
+  termination AS "Termination",
+  strftime(utc_timestamp, '%Y.%m.%d') AS "UTCDate",
+  strftime(utc_timestamp, '%H:%M:%S') AS "UTCTime",
+  strftime(end_timestamp, '%Y.%m.%d') AS "EndDate",
+  strftime(end_timestamp, '%H:%M:%S') AS "EndTime",
+  'https://www.chess.com/game/' || chesscom_id AS "Link"
+FROM games
+$WHERE;
@@ -0,0 +1,28 @@
+SELECT
+  movedata,
+  CAST(clocks_white AS USMALLINT[]),
+  CAST(clocks_black AS USMALLINT[]),
+  CAST(evals AS SMALLINT[]),
+  $ANNOTATIONS AS annotations,
+  $CLOCKS_WHITE_MS AS clocks_white_ms,
+  $CLOCKS_BLACK_MS AS clocks_black_ms,
+  'https://lichess.org/tournament/' || tournament AS "Event",
+  'https://lichess.org/' || lichess_id AS "Site",
+  strftime(utc_timestamp, '%Y.%m.%d') AS "Date",
+  white AS "White",
+  black AS "Black",
+  result AS "Result",
+  strftime(utc_timestamp, '%Y.%m.%d') AS "UTCDate",
+  strftime(utc_timestamp, '%H:%M:%S') AS "UTCTime",
+  CAST(white_rating AS VARCHAR) AS "WhiteElo",
+  CAST(black_rating AS VARCHAR) AS "BlackElo",
+  CASE WHEN white_rating_diff >= 0 THEN '+' ELSE '' END || white_rating_diff AS "WhiteRatingDiff",
+  CASE WHEN black_rating_diff >= 0 THEN '+' ELSE '' END || black_rating_diff AS "BlackRatingDiff",
+  white_title AS "WhiteTitle",
+  black_title AS "BlackTitle",
+  eco AS "ECO",
+  opening AS "Opening",
+  COALESCE(time_initial || '+' || time_increment, '-') AS "TimeControl",
+  termination AS "Termination"
+FROM games
+$WHERE;
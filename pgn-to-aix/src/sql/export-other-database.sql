@@ -0,0 +1,11 @@
+SELECT
+  movedata,
+  CAST(clocks_white AS USMALLINT[]),
+  CAST(clocks_black AS USMALLINT[]),
+  CAST(evals AS SMALLINT[]),
+  $ANNOTATIONS AS annotations,
+  $CLOCKS_WHITE_MS AS clocks_white_ms,
+  $CLOCKS_BLACK_MS AS clocks_black_ms,
+  $HEADERS
+FROM games
+$WHERE;
@@ -0,0 +1,22 @@
+CREATE TABLE games (
+  chesscom_id VARCHAR,
+  event VARCHAR,
+  movedata BLOB,
+  clocks_white USMALLINT[],
+  clocks_black USMALLINT[],
+  evals SMALLINT[],
+  ply_count USMALLINT,
+  white VARCHAR,
+  black VARCHAR,
+  white_rating SMALLINT,
+  black_rating SMALLINT,
+  time_initial USMALLINT,
+  time_increment UTINYINT,
+  daily_seconds UINTEGER,
+  result VARCHAR,
+  termination VARCHAR,
+  eco VARCHAR,
+  eco_url VARCHAR,
+  utc_timestamp TIMESTAMP,
+  end_timestamp TIMESTAMP
+);
\ No newline at end of file
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS ingest_progress (
+  input VARCHAR,
+  bytes_read UBIGINT,
+  game_count UBIGINT,
+  finished BOOLEAN
+);
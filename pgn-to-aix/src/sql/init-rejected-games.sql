@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS rejected_games (
+  source_file VARCHAR,
+  tags VARCHAR,
+  movetext VARCHAR,
+  ply USMALLINT,
+  reason VARCHAR,
+  message VARCHAR
+);
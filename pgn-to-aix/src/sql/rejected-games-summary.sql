@@ -0,0 +1,4 @@
+SELECT reason, count(*)
+FROM rejected_games
+GROUP BY reason
+ORDER BY reason
@@ -0,0 +1,8 @@
+CASE
+  WHEN time_initial IS NULL THEN 'correspondence'
+  WHEN time_initial + 40 * time_increment < 30 THEN 'ultrabullet'
+  WHEN time_initial + 40 * time_increment < 180 THEN 'bullet'
+  WHEN time_initial + 40 * time_increment < 480 THEN 'blitz'
+  WHEN time_initial + 40 * time_increment < 1500 THEN 'rapid'
+  ELSE 'classical'
+END
\ No newline at end of file
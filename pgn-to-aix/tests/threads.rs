@@ -0,0 +1,121 @@
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const OPENINGS: [&str; 4] = [
+    "1. e4 e5 2. Nf3 Nc6 3. Bb5 a6",
+    "1. d4 { [%clk 0:03:00] } d5 { [%clk 0:02:59] } 2. c4",
+    "1. c4 e5 ; a comment\n2. Nc3",
+    "1. Nf3 {\n[Event \"not a game\"]\n} d5",
+];
+
+/// Generates a PGN file with enough games for several chunks, some of which are rejected.
+fn write_pgn(path: &Path) {
+    let mut pgn = String::new();
+    for i in 0..2500 {
+        if i % 7 == 0 {
+            pgn.push_str("% escaped line\n");
+        }
+        writeln!(pgn, "[Event \"Game {i}\"]").unwrap();
+        writeln!(pgn, "[Site \"{}\"]", i % 13).unwrap();
+        if i % 5 != 0 {
+            pgn.push('\n');
+        }
+        let opening = OPENINGS[i % OPENINGS.len()];
+        if i % 101 == 0 {
+            // illegal move
+            writeln!(pgn, "{opening} Ke8 *").unwrap();
+        } else {
+            writeln!(pgn, "{opening} *").unwrap();
+        }
+        if i % 3 != 0 {
+            pgn.push('\n');
+        }
+    }
+    std::fs::write(path, pgn).unwrap();
+}
+
+fn convert(input: &Path, output: &Path, args: &[&str]) {
+    let status = Command::new(env!("CARGO_BIN_EXE_pgn-to-aix"))
+        .arg("-i")
+        .arg(input)
+        .arg("-o")
+        .arg(output)
+        .args([
+            "-c",
+            "low",
+            "--headers",
+            "Event,Site",
+            "--skip-parquet-export",
+        ])
+        .args(args)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+/// Returns the number of rows of `table` that differ between the two databases, by position
+/// unless `columns` excludes `rowid`.
+fn differing_rows(db: &duckdb::Connection, table: &str, columns: &str) -> i64 {
+    db.query_row(
+        &format!(
+            "SELECT count(*) FROM (
+               (SELECT {columns} FROM one.{table} EXCEPT ALL SELECT {columns} FROM many.{table})
+               UNION ALL
+               (SELECT {columns} FROM many.{table} EXCEPT ALL SELECT {columns} FROM one.{table})
+             )"
+        ),
+        [],
+        |row| row.get(0),
+    )
+    .unwrap()
+}
+
+/// Converts the generated PGN file with one thread and with `args`, and attaches both outputs.
+fn convert_twice(name: &str, args: &[&str]) -> duckdb::Connection {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let input = dir.join("games.pgn");
+    write_pgn(&input);
+    convert(&input, &dir.join("one"), &["--threads", "1"]);
+    convert(&input, &dir.join("many"), args);
+
+    let db = duckdb::Connection::open_in_memory().unwrap();
+    db.execute_batch(&format!(
+        "ATTACH '{}' AS one (READ_ONLY); ATTACH '{}' AS many (READ_ONLY);",
+        dir.join("one.duckdb").display(),
+        dir.join("many.duckdb").display(),
+    ))
+    .unwrap();
+
+    let games: i64 = db
+        .query_row("SELECT count(*) FROM one.games", [], |row| row.get(0))
+        .unwrap();
+    let rejected: i64 = db
+        .query_row("SELECT count(*) FROM one.rejected_games", [], |row| {
+            row.get(0)
+        })
+        .unwrap();
+    assert_eq!(games, 2475);
+    assert_eq!(rejected, 25);
+    db
+}
+
+#[test]
+fn threads_produce_same_rows() {
+    let db = convert_twice("threads_produce_same_rows", &["--threads", "4"]);
+    assert_eq!(differing_rows(&db, "games", "rowid, *"), 0);
+    assert_eq!(differing_rows(&db, "rejected_games", "rowid, *"), 0);
+}
+
+#[test]
+fn unordered_threads_produce_same_games() {
+    let db = convert_twice(
+        "unordered_threads_produce_same_games",
+        &["--threads", "4", "--unordered"],
+    );
+    assert_eq!(differing_rows(&db, "games", "*"), 0);
+    assert_eq!(differing_rows(&db, "rejected_games", "*"), 0);
+}
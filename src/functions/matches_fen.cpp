@@ -0,0 +1,114 @@
+#include "aixchess_functions.hpp"
+
+namespace duckdb {
+
+namespace {
+
+// Same order as fen_match::MODES in aixrs.
+const char *const MODES[] = {"placement", "placement_turn", "full"};
+
+struct MatchesFenBindData : public FunctionData {
+	MatchesFenBindData(string fen_p, uint8_t mode, bool is_null) : fen(std::move(fen_p)), mode(mode), is_null(is_null) {
+	}
+
+	string fen;
+	uint8_t mode;
+	bool is_null;
+
+	unique_ptr<FunctionData> Copy() const override {
+		return make_uniq<MatchesFenBindData>(fen, mode, is_null);
+	}
+
+	bool Equals(const FunctionData &other_p) const override {
+		auto &other = other_p.Cast<MatchesFenBindData>();
+		return fen == other.fen && mode == other.mode && is_null == other.is_null;
+	}
+};
+
+static Value EvaluateConstantArgument(ClientContext &context, unique_ptr<Expression> &arg, const char *name) {
+	if (arg->HasParameter()) {
+		throw ParameterNotResolvedException();
+	}
+	if (!arg->IsFoldable()) {
+		throw InvalidInputException(*arg, "%s must be a constant", name);
+	}
+	return ExpressionExecutor::EvaluateScalar(context, *arg);
+}
+
+static unique_ptr<FunctionData> MatchesFenBindFunction(ClientContext &context, ScalarFunction &bound_function,
+                                                       vector<unique_ptr<Expression>> &arguments) {
+	Value fen = EvaluateConstantArgument(context, arguments[1], "fen");
+	Value mode_value = EvaluateConstantArgument(context, arguments[2], "mode");
+	if (fen.IsNull() || mode_value.IsNull()) {
+		return make_uniq<MatchesFenBindData>(string(), 0, true);
+	}
+
+	auto mode_it = std::find(std::begin(MODES), std::end(MODES), mode_value.GetValue<string>());
+	if (mode_it == std::end(MODES)) {
+		throw InvalidInputException(*arguments[2], "mode must be one of 'placement', 'placement_turn' or 'full'");
+	}
+	auto mode = static_cast<uint8_t>(mode_it - std::begin(MODES));
+
+	auto fen_string = fen.GetValue<string>();
+	if (FenPattern::parse(fen_string, mode).is_err()) {
+		throw InvalidInputException(*arguments[1], "failed to parse fen");
+	}
+	return make_uniq<MatchesFenBindData>(fen_string, mode, false);
+}
+
+template <bool PLY>
+inline void MatchesFen(DataChunk &args, ExpressionState &state, Vector &result) {
+	auto &func_expr = state.expr.Cast<BoundFunctionExpression>();
+	auto &info = func_expr.bind_info->Cast<MatchesFenBindData>();
+
+	if (info.is_null) {
+		result.SetVectorType(VectorType::CONSTANT_VECTOR);
+		ConstantVector::SetNull(result, true);
+		return;
+	}
+
+	auto &game_vector = args.data[0];
+	auto count = args.size();
+
+	auto pattern_r = FenPattern::parse(info.fen, info.mode);
+	if (pattern_r.is_err()) {
+		throw InvalidInputException("matches_fen internal error (please report): parse");
+	}
+	auto pattern = std::move(pattern_r).ok().value();
+
+	if constexpr (!PLY) {
+		UnaryExecutor::Execute<string_t, bool>(game_vector, result, count, [&](string_t game) {
+			diplomat::span<const uint8_t> data = {const_data_ptr_cast(game.GetData()), game.GetSize()};
+			return UnwrapOptionalDecoded<uint16_t>(pattern->first_ply(data), "matches_fen").has_value();
+		});
+	} else {
+		UnaryExecutor::ExecuteWithNulls<string_t, uint16_t>(
+		    game_vector, result, count, [&](string_t game, ValidityMask &mask, idx_t idx) {
+			    diplomat::span<const uint8_t> data = {const_data_ptr_cast(game.GetData()), game.GetSize()};
+			    auto ply_opt = UnwrapOptionalDecoded<uint16_t>(pattern->first_ply(data), "matches_fen_ply");
+
+			    if (!ply_opt.has_value()) {
+				    mask.SetInvalid(idx);
+				    return uint16_t(0);
+			    }
+
+			    return *ply_opt;
+		    });
+	}
+}
+
+} // namespace
+
+void Register_MatchesFen(ExtensionLoader &loader) {
+	auto matches_fen_function =
+	    ScalarFunction("matches_fen", {LogicalType::BLOB, LogicalType::VARCHAR, LogicalType::VARCHAR},
+	                   LogicalType::BOOLEAN, MatchesFen<false>, MatchesFenBindFunction);
+	loader.RegisterFunction(matches_fen_function);
+
+	auto matches_fen_ply_function =
+	    ScalarFunction("matches_fen_ply", {LogicalType::BLOB, LogicalType::VARCHAR, LogicalType::VARCHAR},
+	                   LogicalType::USMALLINT, MatchesFen<true>, MatchesFenBindFunction);
+	loader.RegisterFunction(matches_fen_ply_function);
+}
+
+} // namespace duckdb
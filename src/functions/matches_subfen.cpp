@@ -20,7 +20,13 @@ struct MatchesSubfenBindData : public FunctionData {
 		return subfen.white == other.subfen.white && subfen.black == other.subfen.black &&
 		       subfen.king == other.subfen.king && subfen.queen == other.subfen.queen &&
 		       subfen.rook == other.subfen.rook && subfen.bishop == other.subfen.bishop &&
-		       subfen.knight == other.subfen.knight && subfen.pawn == other.subfen.pawn;
+		       subfen.knight == other.subfen.knight && subfen.pawn == other.subfen.pawn &&
+		       subfen.minor == other.subfen.minor && subfen.occupied == other.subfen.occupied &&
+		       subfen.empty == other.subfen.empty && subfen.not_white == other.subfen.not_white &&
+		       subfen.not_black == other.subfen.not_black && subfen.not_king == other.subfen.not_king &&
+		       subfen.not_queen == other.subfen.not_queen && subfen.not_rook == other.subfen.not_rook &&
+		       subfen.not_bishop == other.subfen.not_bishop && subfen.not_knight == other.subfen.not_knight &&
+		       subfen.not_pawn == other.subfen.not_pawn && subfen.not_minor == other.subfen.not_minor;
 	}
 };
 
@@ -38,7 +38,8 @@ struct MoveDetailsStruct {
 
 		FlatVector::GetData<uint16_t>(*entries[PLY_I])[i] = value.inner.ply;
 		FlatVector::GetData<string_t>(*entries[ROLE_I])[i] = std::string(1, value.inner.role);
-		FlatVector::GetData<string_t>(*entries[FROM_I])[i] = SQUARES[value.inner.from];
+		FlatVector::GetData<string_t>(*entries[FROM_I])[i] =
+		    value.inner.from == 64 ? "" : SQUARES[value.inner.from];
 		FlatVector::GetData<string_t>(*entries[TO_I])[i] = SQUARES[value.inner.to];
 		FlatVector::GetData<string_t>(*entries[PROMOTION_I])[i] =
 		    value.inner.promotion == 0 ? "" : std::string(1, value.inner.promotion);
@@ -0,0 +1,194 @@
+#include "aixchess_functions.hpp"
+
+namespace duckdb {
+
+namespace {
+
+const int PLY_I = 0;
+const int POSITION_I = 1;
+const int FEN_I = 2;
+const int MOVE_I = 3;
+const int SAN_I = 4;
+const int GAMES_I = 5;
+const int WHITE_WINS_I = 6;
+const int DRAWS_I = 7;
+const int BLACK_WINS_I = 8;
+
+struct OpeningTreeBindData : public FunctionData {
+	explicit OpeningTreeBindData(uint16_t max_ply) : max_ply(max_ply) {
+	}
+
+	uint16_t max_ply;
+
+	unique_ptr<FunctionData> Copy() const override {
+		return make_uniq<OpeningTreeBindData>(max_ply);
+	}
+
+	bool Equals(const FunctionData &other_p) const override {
+		auto &other = other_p.Cast<OpeningTreeBindData>();
+		return max_ply == other.max_ply;
+	}
+};
+
+// The tree is built in Rust, the state only owns it. It is created for the first game of a group.
+struct OpeningTreeState {
+	OpeningTree *tree;
+};
+
+static unique_ptr<FunctionData> OpeningTreeBind(ClientContext &context, AggregateFunction &function,
+                                                vector<unique_ptr<Expression>> &arguments) {
+	auto &max_ply_arg = arguments[2];
+	if (max_ply_arg->HasParameter()) {
+		throw ParameterNotResolvedException();
+	}
+	if (!max_ply_arg->IsFoldable()) {
+		throw InvalidInputException(*max_ply_arg, "opening_tree - max_ply must be a constant");
+	}
+	Value max_ply = ExpressionExecutor::EvaluateScalar(context, *max_ply_arg);
+	if (max_ply.IsNull() || max_ply.GetValue<int32_t>() < 0 || max_ply.GetValue<int32_t>() > UINT16_MAX) {
+		throw InvalidInputException(*max_ply_arg, "opening_tree - max_ply must be between 0 and 65535");
+	}
+
+	Function::EraseArgument(function, arguments, 2);
+	return make_uniq<OpeningTreeBindData>(static_cast<uint16_t>(max_ply.GetValue<int32_t>()));
+}
+
+static idx_t OpeningTreeStateSize(const AggregateFunction &) {
+	return sizeof(OpeningTreeState);
+}
+
+static void OpeningTreeInitialize(const AggregateFunction &, data_ptr_t state) {
+	reinterpret_cast<OpeningTreeState *>(state)->tree = nullptr;
+}
+
+static void OpeningTreeUpdate(Vector inputs[], AggregateInputData &aggr_input_data, idx_t input_count, Vector &states,
+                              idx_t count) {
+	auto max_ply = aggr_input_data.bind_data->Cast<OpeningTreeBindData>().max_ply;
+
+	UnifiedVectorFormat game_data;
+	UnifiedVectorFormat result_data;
+	UnifiedVectorFormat states_data;
+	inputs[0].ToUnifiedFormat(count, game_data);
+	inputs[1].ToUnifiedFormat(count, result_data);
+	states.ToUnifiedFormat(count, states_data);
+	auto games = UnifiedVectorFormat::GetData<string_t>(game_data);
+	auto results = UnifiedVectorFormat::GetData<string_t>(result_data);
+	auto state_ptrs = UnifiedVectorFormat::GetData<OpeningTreeState *>(states_data);
+
+	for (idx_t i = 0; i < count; i++) {
+		auto game_idx = game_data.sel->get_index(i);
+		if (!game_data.validity.RowIsValid(game_idx)) {
+			continue;
+		}
+		auto &state = *state_ptrs[states_data.sel->get_index(i)];
+		if (!state.tree) {
+			state.tree = OpeningTree::create().release();
+		}
+
+		// games without a result are counted, but not as a win, draw or loss
+		auto result_idx = result_data.sel->get_index(i);
+		std::string_view result;
+		if (result_data.validity.RowIsValid(result_idx)) {
+			result = std::string_view(results[result_idx].GetData(), results[result_idx].GetSize());
+		}
+
+		auto &game = games[game_idx];
+		diplomat::span<const uint8_t> data = {const_data_ptr_cast(game.GetData()), game.GetSize()};
+		UnwrapDecoded(state.tree->add_game(data, result, max_ply), "opening_tree");
+	}
+}
+
+static void OpeningTreeCombine(Vector &source, Vector &target, AggregateInputData &, idx_t count) {
+	auto sources = FlatVector::GetData<OpeningTreeState *>(source);
+	auto targets = FlatVector::GetData<OpeningTreeState *>(target);
+	for (idx_t i = 0; i < count; i++) {
+		auto &source_state = *sources[i];
+		auto &target_state = *targets[i];
+		if (!source_state.tree) {
+			continue;
+		}
+		if (!target_state.tree) {
+			target_state.tree = source_state.tree;
+			source_state.tree = nullptr;
+		} else {
+			target_state.tree->merge(*source_state.tree);
+		}
+	}
+}
+
+static void OpeningTreeFinalize(Vector &states, AggregateInputData &, Vector &result, idx_t count, idx_t offset) {
+	UnifiedVectorFormat states_data;
+	states.ToUnifiedFormat(count, states_data);
+	auto state_ptrs = UnifiedVectorFormat::GetData<OpeningTreeState *>(states_data);
+
+	auto list_entries = FlatVector::GetData<list_entry_t>(result);
+	auto &child = ListVector::GetEntry(result);
+	auto &fields = StructVector::GetEntries(child);
+
+	for (idx_t i = 0; i < count; i++) {
+		auto &state = *state_ptrs[states_data.sel->get_index(i)];
+		auto rid = i + offset;
+		if (!state.tree) {
+			FlatVector::SetNull(result, rid, true);
+			continue;
+		}
+
+		auto entries = state.tree->entries();
+		auto size = entries->count();
+		auto list_offset = ListVector::GetListSize(result);
+		ListVector::Reserve(result, list_offset + size);
+		for (size_t j = 0; j < size; j++) {
+			auto entry = entries->get(j);
+			auto idx = list_offset + j;
+			FlatVector::GetData<uint16_t>(*fields[PLY_I])[idx] = entry.ply;
+			FlatVector::GetData<uint64_t>(*fields[POSITION_I])[idx] = entry.position;
+			FlatVector::GetData<string_t>(*fields[FEN_I])[idx] =
+			    StringVector::AddString(*fields[FEN_I], entries->fen(j));
+			FlatVector::GetData<string_t>(*fields[MOVE_I])[idx] =
+			    StringVector::AddString(*fields[MOVE_I], entries->uci(j));
+			FlatVector::GetData<string_t>(*fields[SAN_I])[idx] =
+			    StringVector::AddString(*fields[SAN_I], entries->san(j));
+			FlatVector::GetData<uint64_t>(*fields[GAMES_I])[idx] = entry.games;
+			FlatVector::GetData<uint64_t>(*fields[WHITE_WINS_I])[idx] = entry.white_wins;
+			FlatVector::GetData<uint64_t>(*fields[DRAWS_I])[idx] = entry.draws;
+			FlatVector::GetData<uint64_t>(*fields[BLACK_WINS_I])[idx] = entry.black_wins;
+		}
+
+		list_entries[rid].offset = list_offset;
+		list_entries[rid].length = size;
+		ListVector::SetListSize(result, list_offset + size);
+	}
+}
+
+static void OpeningTreeDestroy(Vector &states, AggregateInputData &, idx_t count) {
+	auto state_ptrs = FlatVector::GetData<OpeningTreeState *>(states);
+	for (idx_t i = 0; i < count; i++) {
+		// deleting the tree frees it on the Rust side
+		delete state_ptrs[i]->tree;
+		state_ptrs[i]->tree = nullptr;
+	}
+}
+
+} // namespace
+
+void Register_OpeningTree(ExtensionLoader &loader) {
+	child_list_t<LogicalType> entry_children;
+	entry_children.push_back(std::make_pair("ply", LogicalType::USMALLINT));
+	entry_children.push_back(std::make_pair("position", LogicalType::UBIGINT));
+	entry_children.push_back(std::make_pair("fen", LogicalType::VARCHAR));
+	entry_children.push_back(std::make_pair("move", LogicalType::VARCHAR));
+	entry_children.push_back(std::make_pair("san", LogicalType::VARCHAR));
+	entry_children.push_back(std::make_pair("games", LogicalType::UBIGINT));
+	entry_children.push_back(std::make_pair("white_wins", LogicalType::UBIGINT));
+	entry_children.push_back(std::make_pair("draws", LogicalType::UBIGINT));
+	entry_children.push_back(std::make_pair("black_wins", LogicalType::UBIGINT));
+
+	auto opening_tree_function = AggregateFunction(
+	    "opening_tree", {LogicalType::BLOB, LogicalType::VARCHAR, LogicalType::INTEGER},
+	    LogicalType::LIST(LogicalType::STRUCT(entry_children)), OpeningTreeStateSize, OpeningTreeInitialize,
+	    OpeningTreeUpdate, OpeningTreeCombine, OpeningTreeFinalize, FunctionNullHandling::SPECIAL_HANDLING, nullptr,
+	    OpeningTreeBind, OpeningTreeDestroy);
+	loader.RegisterFunction(opening_tree_function);
+}
+
+} // namespace duckdb
@@ -0,0 +1,72 @@
+#include "aixchess_functions.hpp"
+
+#include <algorithm>
+
+namespace duckdb {
+
+namespace {
+
+const idx_t INITIAL_BUFFER_SIZE = 256;
+
+inline void PositionHashes(DataChunk &args, ExpressionState &state, Vector &result) {
+	std::vector<uint64_t> buffer;
+	GenericExecutor::ExecuteBinary<PrimitiveType<string_t>, PrimitiveType<int32_t>,
+	                               GenericListType<PrimitiveType<uint64_t>>>(
+	    args.data[0], args.data[1], result, args.size(), [&](PrimitiveType<string_t> game, PrimitiveType<int32_t> max_ply) {
+		    if (max_ply.val < 0) {
+			    throw InvalidInputException("position_hashes - max_ply must not be negative, got %d", max_ply.val);
+		    }
+
+		    diplomat::span<const uint8_t> data = {const_data_ptr_cast(game.val.GetData()), game.val.GetSize()};
+		    // the number of plies is unknown before decoding, retry with a larger buffer if it is filled
+		    const auto max_size = static_cast<idx_t>(max_ply.val) + 1;
+		    auto size = std::min<idx_t>(max_size, INITIAL_BUFFER_SIZE);
+		    size_t written;
+		    while (true) {
+			    buffer.resize(size);
+			    diplomat::span<uint64_t> buffer_span = {buffer.data(), buffer.size()};
+			    written = UnwrapDecoded<size_t>(Game::position_hashes(data, buffer_span), "position_hashes");
+			    if (written < size || size == max_size) {
+				    break;
+			    }
+			    size = std::min<idx_t>(max_size, size * 2);
+		    }
+
+		    GenericListType<PrimitiveType<uint64_t>> hashes;
+		    for (size_t i = 0; i < written; i++) {
+			    hashes.values.push_back(PrimitiveType<uint64_t>(buffer[i]));
+		    }
+		    return hashes;
+	    });
+}
+
+inline void ZobristAtPosition(DataChunk &args, ExpressionState &state, Vector &result) {
+	BinaryExecutor::ExecuteWithNulls<string_t, int32_t, uint64_t>(
+	    args.data[0], args.data[1], result, args.size(),
+	    [&](string_t game, int32_t pos, ValidityMask &mask, idx_t idx) {
+		    diplomat::span<const uint8_t> data = {const_data_ptr_cast(game.GetData()), game.GetSize()};
+		    auto hash_opt = UnwrapOptionalDecoded<uint64_t>(Game::zobrist_at_position(data, pos), "zobrist_at_position");
+
+		    if (!hash_opt.has_value()) {
+			    mask.SetInvalid(idx);
+			    return uint64_t(0);
+		    }
+
+		    return *hash_opt;
+	    });
+}
+
+} // namespace
+
+void Register_PositionHashes(ExtensionLoader &loader) {
+	auto position_hashes_function =
+	    ScalarFunction("position_hashes", {LogicalType::BLOB, LogicalType::INTEGER},
+	                   LogicalType::LIST(LogicalType::UBIGINT), PositionHashes);
+	loader.RegisterFunction(position_hashes_function);
+
+	auto zobrist_at_position_function = ScalarFunction(
+	    "zobrist_at_position", {LogicalType::BLOB, LogicalType::INTEGER}, LogicalType::UBIGINT, ZobristAtPosition);
+	loader.RegisterFunction(zobrist_at_position_function);
+}
+
+} // namespace duckdb
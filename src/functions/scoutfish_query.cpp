@@ -42,6 +42,12 @@ static const char *ScoutfishParseErrorToString(ScoutfishQueryParseError err) {
 		return "Scoutfish query parsing internal error (please report): BufferTooSmall";
 	case ScoutfishQueryParseError::CursorWriteError:
 		return "Scoutfish query parsing internal error (please report): CursorWriteError";
+	case ScoutfishQueryParseError::InvalidPawnCountFormat:
+		return "Scoutfish query parsing error: invalid format for a pawn structure count";
+	case ScoutfishQueryParseError::InvalidOpenFilesFormat:
+		return "Scoutfish query parsing error: invalid format for 'open-files'";
+	case ScoutfishQueryParseError::InvalidCastledFormat:
+		return "Scoutfish query parsing error: invalid format for 'castled'";
 	default:
 		return "Scoutfish query parsing internal error (please report): Unknown error";
 	}
@@ -13,11 +13,55 @@ inline void ToPgn(DataChunk &args, ExpressionState &state, Vector &result) {
 	});
 }
 
+inline void ToPgnAnnotated(DataChunk &args, ExpressionState &state, Vector &result) {
+	auto count = args.size();
+	UnifiedVectorFormat game_data;
+	UnifiedVectorFormat annotation_data;
+	args.data[0].ToUnifiedFormat(count, game_data);
+	args.data[1].ToUnifiedFormat(count, annotation_data);
+	auto games = UnifiedVectorFormat::GetData<string_t>(game_data);
+	auto annotations = UnifiedVectorFormat::GetData<string_t>(annotation_data);
+
+	result.SetVectorType(VectorType::FLAT_VECTOR);
+	auto result_data = FlatVector::GetData<string_t>(result);
+	auto &result_validity = FlatVector::Validity(result);
+	for (idx_t i = 0; i < count; i++) {
+		auto game_idx = game_data.sel->get_index(i);
+		if (!game_data.validity.RowIsValid(game_idx)) {
+			result_validity.SetInvalid(i);
+			continue;
+		}
+
+		// games without annotations are rendered without them
+		auto annotation_idx = annotation_data.sel->get_index(i);
+		diplomat::span<const uint8_t> annotation_span = {nullptr, 0};
+		if (annotation_data.validity.RowIsValid(annotation_idx)) {
+			auto &annotation = annotations[annotation_idx];
+			annotation_span = {const_data_ptr_cast(annotation.GetData()), annotation.GetSize()};
+		}
+
+		auto &game = games[game_idx];
+		diplomat::span<const uint8_t> data = {const_data_ptr_cast(game.GetData()), game.GetSize()};
+		auto pgn_result = Game::to_annotated_pgn_string(data, annotation_span);
+		auto pgn = UnwrapDecoded<std::string>(std::move(pgn_result), "to_pgn_annotated");
+		result_data[i] = StringVector::AddString(result, pgn);
+	}
+
+	if (args.AllConstant()) {
+		result.SetVectorType(VectorType::CONSTANT_VECTOR);
+	}
+}
+
 } // namespace
 
 void Register_ToPgn(ExtensionLoader &loader) {
 	auto to_pgn_function = ScalarFunction("to_pgn", {LogicalType::BLOB}, LogicalType::VARCHAR, ToPgn);
 	loader.RegisterFunction(to_pgn_function);
+
+	auto to_pgn_annotated_function = ScalarFunction("to_pgn_annotated", {LogicalType::BLOB, LogicalType::BLOB},
+	                                                LogicalType::VARCHAR, ToPgnAnnotated);
+	to_pgn_annotated_function.null_handling = FunctionNullHandling::SPECIAL_HANDLING;
+	loader.RegisterFunction(to_pgn_annotated_function);
 }
 
 } // namespace duckdb
\ No newline at end of file
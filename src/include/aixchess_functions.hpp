@@ -7,11 +7,14 @@
 #include "rust/Game.hpp"
 #include "rust/Bitboards.hpp"
 #include "rust/Subfen.hpp"
+#include "rust/FenPattern.hpp"
 #include "rust/ScoutfishQuery.hpp"
 #include "rust/ScoutfishQueryParseError.hpp"
 #include "rust/MoveDetails.hpp"
 #include "rust/MoveDetailsIterator.hpp"
+#include "rust/OpeningTree.hpp"
 #include "rust/DecodeError.hpp"
+#include "rust/DecodeFailure.hpp"
 #include "rust/diplomat_runtime.hpp"
 
 #include "bits.h"
@@ -23,6 +26,7 @@ void Register_PiecesAtPosition(ExtensionLoader &loader);
 void Register_PieceCountsAtPosition(ExtensionLoader &loader);
 void Register_BoardAtPosition(ExtensionLoader &loader);
 void Register_MatchesSubfen(ExtensionLoader &loader);
+void Register_MatchesFen(ExtensionLoader &loader);
 void Register_ScoutfishQuery(ExtensionLoader &loader);
 void Register_ClocksToMoveTimes(ExtensionLoader &loader);
 void Register_LichessTimeControl(ExtensionLoader &loader);
@@ -33,26 +37,65 @@ void Register_ToPgn(ExtensionLoader &loader);
 void Register_MovedPieces(ExtensionLoader &loader);
 void Register_MoveDetails(ExtensionLoader &loader);
 void Register_Recompress(ExtensionLoader &loader);
+void Register_OpeningTree(ExtensionLoader &loader);
+void Register_PositionHashes(ExtensionLoader &loader);
+
+inline std::string DecodeErrorMessage(DecodeError err) {
+	switch (err) {
+	case DecodeError::EmptyBlob:
+		return "empty movedata";
+	case DecodeError::InvalidCompressionLevel:
+		return "invalid compression level";
+	case DecodeError::InvalidEncodedGameConstructionData:
+		return "invalid encoded game";
+	case DecodeError::InvalidDataDuringDecoding:
+		return "invalid data";
+	case DecodeError::UnsupportedVersion:
+		return "unsupported format version, movedata was written by a newer version of aix";
+	case DecodeError::TruncatedData:
+		return "data ends in the middle of a move";
+	case DecodeError::InvalidIndex:
+		return "invalid piece, square or move index";
+	case DecodeError::IllegalMove:
+		return "illegal move";
+	default:
+		return "unknown error";
+	}
+}
+
+// Appends where decoding failed, if the error is about a move of the game.
+inline std::string DecodeFailureMessage(const DecodeFailure &err) {
+	auto message = DecodeErrorMessage(err.kind);
+	if (err.ply > 0) {
+		const char *const levels[] = {"low", "medium", "high"};
+		message += " at ply " + std::to_string(err.ply);
+		if (err.level < 3) {
+			message += std::string(" (") + levels[err.level] + " compression)";
+		}
+	}
+	return message;
+}
 
 template <typename T>
-T UnwrapDecoded(diplomat::result<T, DecodeError> &&result, const char *function_name) {
+T UnwrapDecoded(diplomat::result<T, DecodeFailure> &&result, const char *function_name) {
 	if (result.is_ok()) {
 		return *(std::move(result).ok());
 	} else {
 		const auto err = std::move(result).err().value();
 		const auto fn_name = std::string(function_name);
-		const auto code = std::to_string(static_cast<int>(err));
-		throw InvalidInputException(fn_name + " - failed to decode movedata (error code " + code + ")");
+		const auto code = std::to_string(static_cast<int>(err.kind));
+		throw InvalidInputException(fn_name + " - failed to decode movedata: " + DecodeFailureMessage(err) +
+		                            " (error code " + code + ")");
 	}
 }
 
 template <typename T>
-std::optional<T> UnwrapOptionalDecoded(diplomat::result<T, DecodeError> &&result, const char *function_name) {
+std::optional<T> UnwrapOptionalDecoded(diplomat::result<T, DecodeFailure> &&result, const char *function_name) {
 	if (result.is_ok()) {
 		return std::move(result).ok();
 	} else {
 		const auto err = *std::move(result).err();
-		const auto code = static_cast<int>(err);
+		const auto code = static_cast<int>(err.kind);
 
 		if (code == 0) {
 			return std::nullopt;
@@ -60,7 +103,8 @@ std::optional<T> UnwrapOptionalDecoded(diplomat::result<T, DecodeError> &&result
 
 		const auto fn_name = std::string(function_name);
 		const auto code_s = std::to_string(code);
-		throw InvalidInputException(fn_name + " - failed to decode movedata (error code " + code_s + ")");
+		throw InvalidInputException(fn_name + " - failed to decode movedata: " + DecodeFailureMessage(err) +
+		                            " (error code " + code_s + ")");
 	}
 }
 
@@ -18,6 +18,10 @@ namespace capi {
       DecodeError_InvalidCompressionLevel = 2,
       DecodeError_InvalidEncodedGameConstructionData = 3,
       DecodeError_InvalidDataDuringDecoding = 4,
+      DecodeError_UnsupportedVersion = 5,
+      DecodeError_TruncatedData = 6,
+      DecodeError_InvalidIndex = 7,
+      DecodeError_IllegalMove = 8,
     };
     
     typedef struct DecodeError_option {union { DecodeError ok; }; bool is_ok; } DecodeError_option;
@@ -32,6 +36,10 @@ public:
     InvalidCompressionLevel = 2,
     InvalidEncodedGameConstructionData = 3,
     InvalidDataDuringDecoding = 4,
+    UnsupportedVersion = 5,
+    TruncatedData = 6,
+    InvalidIndex = 7,
+    IllegalMove = 8,
   };
 
   DecodeError() = default;
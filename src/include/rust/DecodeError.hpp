@@ -32,6 +32,10 @@ inline DecodeError DecodeError::FromFFI(diplomat::capi::DecodeError c_enum) {
     case diplomat::capi::DecodeError_InvalidCompressionLevel:
     case diplomat::capi::DecodeError_InvalidEncodedGameConstructionData:
     case diplomat::capi::DecodeError_InvalidDataDuringDecoding:
+    case diplomat::capi::DecodeError_UnsupportedVersion:
+    case diplomat::capi::DecodeError_TruncatedData:
+    case diplomat::capi::DecodeError_InvalidIndex:
+    case diplomat::capi::DecodeError_IllegalMove:
       return static_cast<DecodeError::Value>(c_enum);
     default:
       abort();
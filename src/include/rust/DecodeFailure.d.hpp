@@ -0,0 +1,37 @@
+#ifndef DecodeFailure_D_HPP
+#define DecodeFailure_D_HPP
+
+#include <stdio.h>
+#include <stdint.h>
+#include <stddef.h>
+#include <stdbool.h>
+#include <memory>
+#include <optional>
+#include "DecodeError.d.hpp"
+#include "diplomat_runtime.hpp"
+
+
+namespace diplomat {
+namespace capi {
+    struct DecodeFailure {
+      diplomat::capi::DecodeError kind;
+      uint32_t ply;
+      uint8_t level;
+    };
+    
+    typedef struct DecodeFailure_option {union { DecodeFailure ok; }; bool is_ok; } DecodeFailure_option;
+} // namespace capi
+} // namespace
+
+
+struct DecodeFailure {
+  DecodeError kind;
+  uint32_t ply;
+  uint8_t level;
+
+  inline diplomat::capi::DecodeFailure AsFFI() const;
+  inline static DecodeFailure FromFFI(diplomat::capi::DecodeFailure c_struct);
+};
+
+
+#endif // DecodeFailure_D_HPP
@@ -0,0 +1,43 @@
+#ifndef DecodeFailure_HPP
+#define DecodeFailure_HPP
+
+#include "DecodeFailure.d.hpp"
+
+#include <stdio.h>
+#include <stdint.h>
+#include <stddef.h>
+#include <stdbool.h>
+#include <memory>
+#include <optional>
+#include "DecodeError.hpp"
+#include "diplomat_runtime.hpp"
+
+
+namespace diplomat {
+namespace capi {
+    extern "C" {
+    
+    
+    } // extern "C"
+} // namespace capi
+} // namespace
+
+
+inline diplomat::capi::DecodeFailure DecodeFailure::AsFFI() const {
+  return diplomat::capi::DecodeFailure {
+    /* .kind = */ kind.AsFFI(),
+    /* .ply = */ ply,
+    /* .level = */ level,
+  };
+}
+
+inline DecodeFailure DecodeFailure::FromFFI(diplomat::capi::DecodeFailure c_struct) {
+  return DecodeFailure {
+    /* .kind = */ DecodeError::FromFFI(c_struct.kind),
+    /* .ply = */ c_struct.ply,
+    /* .level = */ c_struct.level,
+  };
+}
+
+
+#endif // DecodeFailure_HPP
@@ -0,0 +1,43 @@
+#ifndef FenPattern_D_HPP
+#define FenPattern_D_HPP
+
+#include <stdio.h>
+#include <stdint.h>
+#include <stddef.h>
+#include <stdbool.h>
+#include <memory>
+#include <optional>
+#include "diplomat_runtime.hpp"
+
+struct DecodeFailure;
+
+
+namespace diplomat {
+namespace capi {
+    struct FenPattern;
+} // namespace capi
+} // namespace
+
+class FenPattern {
+public:
+
+  inline static diplomat::result<std::unique_ptr<FenPattern>, std::monostate> parse(std::string_view fen, uint8_t mode);
+
+  inline diplomat::result<uint16_t, DecodeFailure> first_ply(diplomat::span<const uint8_t> game) const;
+
+  inline const diplomat::capi::FenPattern* AsFFI() const;
+  inline diplomat::capi::FenPattern* AsFFI();
+  inline static const FenPattern* FromFFI(const diplomat::capi::FenPattern* ptr);
+  inline static FenPattern* FromFFI(diplomat::capi::FenPattern* ptr);
+  inline static void operator delete(void* ptr);
+private:
+  FenPattern() = delete;
+  FenPattern(const FenPattern&) = delete;
+  FenPattern(FenPattern&&) noexcept = delete;
+  FenPattern operator=(const FenPattern&) = delete;
+  FenPattern operator=(FenPattern&&) noexcept = delete;
+  static void operator delete[](void*, size_t) = delete;
+};
+
+
+#endif // FenPattern_D_HPP
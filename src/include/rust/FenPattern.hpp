@@ -0,0 +1,66 @@
+#ifndef FenPattern_HPP
+#define FenPattern_HPP
+
+#include "FenPattern.d.hpp"
+
+#include <stdio.h>
+#include <stdint.h>
+#include <stddef.h>
+#include <stdbool.h>
+#include <memory>
+#include <optional>
+#include "DecodeFailure.hpp"
+#include "diplomat_runtime.hpp"
+
+
+namespace diplomat {
+namespace capi {
+    extern "C" {
+    
+    typedef struct FenPattern_parse_result {union {diplomat::capi::FenPattern* ok; }; bool is_ok;} FenPattern_parse_result;
+    FenPattern_parse_result FenPattern_parse(diplomat::capi::DiplomatStringView fen, uint8_t mode);
+    
+    typedef struct FenPattern_first_ply_result {union {uint16_t ok; diplomat::capi::DecodeFailure err;}; bool is_ok;} FenPattern_first_ply_result;
+    FenPattern_first_ply_result FenPattern_first_ply(const diplomat::capi::FenPattern* self, diplomat::capi::DiplomatU8View game);
+    
+    
+    void FenPattern_destroy(FenPattern* self);
+    
+    } // extern "C"
+} // namespace capi
+} // namespace
+
+inline diplomat::result<std::unique_ptr<FenPattern>, std::monostate> FenPattern::parse(std::string_view fen, uint8_t mode) {
+  auto result = diplomat::capi::FenPattern_parse({fen.data(), fen.size()},
+    mode);
+  return result.is_ok ? diplomat::result<std::unique_ptr<FenPattern>, std::monostate>(diplomat::Ok<std::unique_ptr<FenPattern>>(std::unique_ptr<FenPattern>(FenPattern::FromFFI(result.ok)))) : diplomat::result<std::unique_ptr<FenPattern>, std::monostate>(diplomat::Err<std::monostate>());
+}
+
+inline diplomat::result<uint16_t, DecodeFailure> FenPattern::first_ply(diplomat::span<const uint8_t> game) const {
+  auto result = diplomat::capi::FenPattern_first_ply(this->AsFFI(),
+    {game.data(), game.size()});
+  return result.is_ok ? diplomat::result<uint16_t, DecodeFailure>(diplomat::Ok<uint16_t>(result.ok)) : diplomat::result<uint16_t, DecodeFailure>(diplomat::Err<DecodeFailure>(DecodeFailure::FromFFI(result.err)));
+}
+
+inline const diplomat::capi::FenPattern* FenPattern::AsFFI() const {
+  return reinterpret_cast<const diplomat::capi::FenPattern*>(this);
+}
+
+inline diplomat::capi::FenPattern* FenPattern::AsFFI() {
+  return reinterpret_cast<diplomat::capi::FenPattern*>(this);
+}
+
+inline const FenPattern* FenPattern::FromFFI(const diplomat::capi::FenPattern* ptr) {
+  return reinterpret_cast<const FenPattern*>(ptr);
+}
+
+inline FenPattern* FenPattern::FromFFI(diplomat::capi::FenPattern* ptr) {
+  return reinterpret_cast<FenPattern*>(ptr);
+}
+
+inline void FenPattern::operator delete(void* ptr) {
+  diplomat::capi::FenPattern_destroy(reinterpret_cast<diplomat::capi::FenPattern*>(ptr));
+}
+
+
+#endif // FenPattern_HPP
@@ -12,7 +12,7 @@
 namespace diplomat::capi { struct MoveDetailsIterator; }
 class MoveDetailsIterator;
 struct Bitboards;
-class DecodeError;
+struct DecodeFailure;
 
 
 namespace diplomat {
@@ -24,21 +24,27 @@ namespace capi {
 class Game {
 public:
 
-  inline static diplomat::result<std::unique_ptr<Game>, DecodeError> from_bytes(diplomat::span<const uint8_t> data);
+  inline static diplomat::result<std::unique_ptr<Game>, DecodeFailure> from_bytes(diplomat::span<const uint8_t> data);
 
-  inline static diplomat::result<Bitboards, DecodeError> pieces_at_position(diplomat::span<const uint8_t> data, int32_t pos);
+  inline static diplomat::result<Bitboards, DecodeFailure> pieces_at_position(diplomat::span<const uint8_t> data, int32_t pos);
 
-  inline static diplomat::result<std::monostate, DecodeError> board_at_position(diplomat::span<const uint8_t> data, int32_t pos, diplomat::span<int8_t> out);
+  inline static diplomat::result<std::monostate, DecodeFailure> board_at_position(diplomat::span<const uint8_t> data, int32_t pos, diplomat::span<int8_t> out);
 
-  inline static diplomat::result<std::string, DecodeError> fen_at_position(diplomat::span<const uint8_t> data, int32_t pos);
+  inline static diplomat::result<std::string, DecodeFailure> fen_at_position(diplomat::span<const uint8_t> data, int32_t pos);
 
-  inline static diplomat::result<std::string, DecodeError> to_uci_string(diplomat::span<const uint8_t> data);
+  inline static diplomat::result<uint64_t, DecodeFailure> zobrist_at_position(diplomat::span<const uint8_t> data, int32_t pos);
 
-  inline static diplomat::result<std::string, DecodeError> to_pgn_string(diplomat::span<const uint8_t> data);
+  inline static diplomat::result<size_t, DecodeFailure> position_hashes(diplomat::span<const uint8_t> data, diplomat::span<uint64_t> out);
 
-  inline static diplomat::result<std::string, DecodeError> moved_pieces(diplomat::span<const uint8_t> data);
+  inline static diplomat::result<std::string, DecodeFailure> to_uci_string(diplomat::span<const uint8_t> data);
 
-  inline static diplomat::result<size_t, DecodeError> recompress(diplomat::span<const uint8_t> data, uint8_t level, diplomat::span<uint8_t> out);
+  inline static diplomat::result<std::string, DecodeFailure> to_pgn_string(diplomat::span<const uint8_t> data);
+
+  inline static diplomat::result<std::string, DecodeFailure> to_annotated_pgn_string(diplomat::span<const uint8_t> data, diplomat::span<const uint8_t> annotations);
+
+  inline static diplomat::result<std::string, DecodeFailure> moved_pieces(diplomat::span<const uint8_t> data);
+
+  inline static diplomat::result<size_t, DecodeFailure> recompress(diplomat::span<const uint8_t> data, uint8_t level, diplomat::span<uint8_t> out);
 
   inline std::unique_ptr<MoveDetailsIterator> move_details_iterator() const;
 
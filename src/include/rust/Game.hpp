@@ -10,7 +10,7 @@
 #include <memory>
 #include <optional>
 #include "Bitboards.hpp"
-#include "DecodeError.hpp"
+#include "DecodeFailure.hpp"
 #include "MoveDetailsIterator.hpp"
 #include "diplomat_runtime.hpp"
 
@@ -19,28 +19,37 @@ namespace diplomat {
 namespace capi {
     extern "C" {
     
-    typedef struct Game_from_bytes_result {union {diplomat::capi::Game* ok; diplomat::capi::DecodeError err;}; bool is_ok;} Game_from_bytes_result;
+    typedef struct Game_from_bytes_result {union {diplomat::capi::Game* ok; diplomat::capi::DecodeFailure err;}; bool is_ok;} Game_from_bytes_result;
     Game_from_bytes_result Game_from_bytes(diplomat::capi::DiplomatU8View data);
     
-    typedef struct Game_pieces_at_position_result {union {diplomat::capi::Bitboards ok; diplomat::capi::DecodeError err;}; bool is_ok;} Game_pieces_at_position_result;
+    typedef struct Game_pieces_at_position_result {union {diplomat::capi::Bitboards ok; diplomat::capi::DecodeFailure err;}; bool is_ok;} Game_pieces_at_position_result;
     Game_pieces_at_position_result Game_pieces_at_position(diplomat::capi::DiplomatU8View data, int32_t pos);
     
-    typedef struct Game_board_at_position_result {union { diplomat::capi::DecodeError err;}; bool is_ok;} Game_board_at_position_result;
+    typedef struct Game_board_at_position_result {union { diplomat::capi::DecodeFailure err;}; bool is_ok;} Game_board_at_position_result;
     Game_board_at_position_result Game_board_at_position(diplomat::capi::DiplomatU8View data, int32_t pos, diplomat::capi::DiplomatI8ViewMut out);
     
-    typedef struct Game_fen_at_position_result {union { diplomat::capi::DecodeError err;}; bool is_ok;} Game_fen_at_position_result;
+    typedef struct Game_fen_at_position_result {union { diplomat::capi::DecodeFailure err;}; bool is_ok;} Game_fen_at_position_result;
     Game_fen_at_position_result Game_fen_at_position(diplomat::capi::DiplomatU8View data, int32_t pos, diplomat::capi::DiplomatWrite* write);
     
-    typedef struct Game_to_uci_string_result {union { diplomat::capi::DecodeError err;}; bool is_ok;} Game_to_uci_string_result;
+    typedef struct Game_zobrist_at_position_result {union {uint64_t ok; diplomat::capi::DecodeFailure err;}; bool is_ok;} Game_zobrist_at_position_result;
+    Game_zobrist_at_position_result Game_zobrist_at_position(diplomat::capi::DiplomatU8View data, int32_t pos);
+    
+    typedef struct Game_position_hashes_result {union {size_t ok; diplomat::capi::DecodeFailure err;}; bool is_ok;} Game_position_hashes_result;
+    Game_position_hashes_result Game_position_hashes(diplomat::capi::DiplomatU8View data, diplomat::capi::DiplomatU64ViewMut out);
+    
+    typedef struct Game_to_uci_string_result {union { diplomat::capi::DecodeFailure err;}; bool is_ok;} Game_to_uci_string_result;
     Game_to_uci_string_result Game_to_uci_string(diplomat::capi::DiplomatU8View data, diplomat::capi::DiplomatWrite* write);
     
-    typedef struct Game_to_pgn_string_result {union { diplomat::capi::DecodeError err;}; bool is_ok;} Game_to_pgn_string_result;
+    typedef struct Game_to_pgn_string_result {union { diplomat::capi::DecodeFailure err;}; bool is_ok;} Game_to_pgn_string_result;
     Game_to_pgn_string_result Game_to_pgn_string(diplomat::capi::DiplomatU8View data, diplomat::capi::DiplomatWrite* write);
     
-    typedef struct Game_moved_pieces_result {union { diplomat::capi::DecodeError err;}; bool is_ok;} Game_moved_pieces_result;
+    typedef struct Game_to_annotated_pgn_string_result {union { diplomat::capi::DecodeFailure err;}; bool is_ok;} Game_to_annotated_pgn_string_result;
+    Game_to_annotated_pgn_string_result Game_to_annotated_pgn_string(diplomat::capi::DiplomatU8View data, diplomat::capi::DiplomatU8View annotations, diplomat::capi::DiplomatWrite* write);
+    
+    typedef struct Game_moved_pieces_result {union { diplomat::capi::DecodeFailure err;}; bool is_ok;} Game_moved_pieces_result;
     Game_moved_pieces_result Game_moved_pieces(diplomat::capi::DiplomatU8View data, diplomat::capi::DiplomatWrite* write);
     
-    typedef struct Game_recompress_result {union {size_t ok; diplomat::capi::DecodeError err;}; bool is_ok;} Game_recompress_result;
+    typedef struct Game_recompress_result {union {size_t ok; diplomat::capi::DecodeFailure err;}; bool is_ok;} Game_recompress_result;
     Game_recompress_result Game_recompress(diplomat::capi::DiplomatU8View data, uint8_t level, diplomat::capi::DiplomatU8ViewMut out);
     
     diplomat::capi::MoveDetailsIterator* Game_move_details_iterator(const diplomat::capi::Game* self);
@@ -52,62 +61,83 @@ namespace capi {
 } // namespace capi
 } // namespace
 
-inline diplomat::result<std::unique_ptr<Game>, DecodeError> Game::from_bytes(diplomat::span<const uint8_t> data) {
+inline diplomat::result<std::unique_ptr<Game>, DecodeFailure> Game::from_bytes(diplomat::span<const uint8_t> data) {
   auto result = diplomat::capi::Game_from_bytes({data.data(), data.size()});
-  return result.is_ok ? diplomat::result<std::unique_ptr<Game>, DecodeError>(diplomat::Ok<std::unique_ptr<Game>>(std::unique_ptr<Game>(Game::FromFFI(result.ok)))) : diplomat::result<std::unique_ptr<Game>, DecodeError>(diplomat::Err<DecodeError>(DecodeError::FromFFI(result.err)));
+  return result.is_ok ? diplomat::result<std::unique_ptr<Game>, DecodeFailure>(diplomat::Ok<std::unique_ptr<Game>>(std::unique_ptr<Game>(Game::FromFFI(result.ok)))) : diplomat::result<std::unique_ptr<Game>, DecodeFailure>(diplomat::Err<DecodeFailure>(DecodeFailure::FromFFI(result.err)));
 }
 
-inline diplomat::result<Bitboards, DecodeError> Game::pieces_at_position(diplomat::span<const uint8_t> data, int32_t pos) {
+inline diplomat::result<Bitboards, DecodeFailure> Game::pieces_at_position(diplomat::span<const uint8_t> data, int32_t pos) {
   auto result = diplomat::capi::Game_pieces_at_position({data.data(), data.size()},
     pos);
-  return result.is_ok ? diplomat::result<Bitboards, DecodeError>(diplomat::Ok<Bitboards>(Bitboards::FromFFI(result.ok))) : diplomat::result<Bitboards, DecodeError>(diplomat::Err<DecodeError>(DecodeError::FromFFI(result.err)));
+  return result.is_ok ? diplomat::result<Bitboards, DecodeFailure>(diplomat::Ok<Bitboards>(Bitboards::FromFFI(result.ok))) : diplomat::result<Bitboards, DecodeFailure>(diplomat::Err<DecodeFailure>(DecodeFailure::FromFFI(result.err)));
 }
 
-inline diplomat::result<std::monostate, DecodeError> Game::board_at_position(diplomat::span<const uint8_t> data, int32_t pos, diplomat::span<int8_t> out) {
+inline diplomat::result<std::monostate, DecodeFailure> Game::board_at_position(diplomat::span<const uint8_t> data, int32_t pos, diplomat::span<int8_t> out) {
   auto result = diplomat::capi::Game_board_at_position({data.data(), data.size()},
     pos,
     {out.data(), out.size()});
-  return result.is_ok ? diplomat::result<std::monostate, DecodeError>(diplomat::Ok<std::monostate>()) : diplomat::result<std::monostate, DecodeError>(diplomat::Err<DecodeError>(DecodeError::FromFFI(result.err)));
+  return result.is_ok ? diplomat::result<std::monostate, DecodeFailure>(diplomat::Ok<std::monostate>()) : diplomat::result<std::monostate, DecodeFailure>(diplomat::Err<DecodeFailure>(DecodeFailure::FromFFI(result.err)));
 }
 
-inline diplomat::result<std::string, DecodeError> Game::fen_at_position(diplomat::span<const uint8_t> data, int32_t pos) {
+inline diplomat::result<std::string, DecodeFailure> Game::fen_at_position(diplomat::span<const uint8_t> data, int32_t pos) {
   std::string output;
   diplomat::capi::DiplomatWrite write = diplomat::WriteFromString(output);
   auto result = diplomat::capi::Game_fen_at_position({data.data(), data.size()},
     pos,
     &write);
-  return result.is_ok ? diplomat::result<std::string, DecodeError>(diplomat::Ok<std::string>(std::move(output))) : diplomat::result<std::string, DecodeError>(diplomat::Err<DecodeError>(DecodeError::FromFFI(result.err)));
+  return result.is_ok ? diplomat::result<std::string, DecodeFailure>(diplomat::Ok<std::string>(std::move(output))) : diplomat::result<std::string, DecodeFailure>(diplomat::Err<DecodeFailure>(DecodeFailure::FromFFI(result.err)));
+}
+
+inline diplomat::result<uint64_t, DecodeFailure> Game::zobrist_at_position(diplomat::span<const uint8_t> data, int32_t pos) {
+  auto result = diplomat::capi::Game_zobrist_at_position({data.data(), data.size()},
+    pos);
+  return result.is_ok ? diplomat::result<uint64_t, DecodeFailure>(diplomat::Ok<uint64_t>(result.ok)) : diplomat::result<uint64_t, DecodeFailure>(diplomat::Err<DecodeFailure>(DecodeFailure::FromFFI(result.err)));
+}
+
+inline diplomat::result<size_t, DecodeFailure> Game::position_hashes(diplomat::span<const uint8_t> data, diplomat::span<uint64_t> out) {
+  auto result = diplomat::capi::Game_position_hashes({data.data(), data.size()},
+    {out.data(), out.size()});
+  return result.is_ok ? diplomat::result<size_t, DecodeFailure>(diplomat::Ok<size_t>(result.ok)) : diplomat::result<size_t, DecodeFailure>(diplomat::Err<DecodeFailure>(DecodeFailure::FromFFI(result.err)));
 }
 
-inline diplomat::result<std::string, DecodeError> Game::to_uci_string(diplomat::span<const uint8_t> data) {
+inline diplomat::result<std::string, DecodeFailure> Game::to_uci_string(diplomat::span<const uint8_t> data) {
   std::string output;
   diplomat::capi::DiplomatWrite write = diplomat::WriteFromString(output);
   auto result = diplomat::capi::Game_to_uci_string({data.data(), data.size()},
     &write);
-  return result.is_ok ? diplomat::result<std::string, DecodeError>(diplomat::Ok<std::string>(std::move(output))) : diplomat::result<std::string, DecodeError>(diplomat::Err<DecodeError>(DecodeError::FromFFI(result.err)));
+  return result.is_ok ? diplomat::result<std::string, DecodeFailure>(diplomat::Ok<std::string>(std::move(output))) : diplomat::result<std::string, DecodeFailure>(diplomat::Err<DecodeFailure>(DecodeFailure::FromFFI(result.err)));
 }
 
-inline diplomat::result<std::string, DecodeError> Game::to_pgn_string(diplomat::span<const uint8_t> data) {
+inline diplomat::result<std::string, DecodeFailure> Game::to_pgn_string(diplomat::span<const uint8_t> data) {
   std::string output;
   diplomat::capi::DiplomatWrite write = diplomat::WriteFromString(output);
   auto result = diplomat::capi::Game_to_pgn_string({data.data(), data.size()},
     &write);
-  return result.is_ok ? diplomat::result<std::string, DecodeError>(diplomat::Ok<std::string>(std::move(output))) : diplomat::result<std::string, DecodeError>(diplomat::Err<DecodeError>(DecodeError::FromFFI(result.err)));
+  return result.is_ok ? diplomat::result<std::string, DecodeFailure>(diplomat::Ok<std::string>(std::move(output))) : diplomat::result<std::string, DecodeFailure>(diplomat::Err<DecodeFailure>(DecodeFailure::FromFFI(result.err)));
+}
+
+inline diplomat::result<std::string, DecodeFailure> Game::to_annotated_pgn_string(diplomat::span<const uint8_t> data, diplomat::span<const uint8_t> annotations) {
+  std::string output;
+  diplomat::capi::DiplomatWrite write = diplomat::WriteFromString(output);
+  auto result = diplomat::capi::Game_to_annotated_pgn_string({data.data(), data.size()},
+    {annotations.data(), annotations.size()},
+    &write);
+  return result.is_ok ? diplomat::result<std::string, DecodeFailure>(diplomat::Ok<std::string>(std::move(output))) : diplomat::result<std::string, DecodeFailure>(diplomat::Err<DecodeFailure>(DecodeFailure::FromFFI(result.err)));
 }
 
-inline diplomat::result<std::string, DecodeError> Game::moved_pieces(diplomat::span<const uint8_t> data) {
+inline diplomat::result<std::string, DecodeFailure> Game::moved_pieces(diplomat::span<const uint8_t> data) {
   std::string output;
   diplomat::capi::DiplomatWrite write = diplomat::WriteFromString(output);
   auto result = diplomat::capi::Game_moved_pieces({data.data(), data.size()},
     &write);
-  return result.is_ok ? diplomat::result<std::string, DecodeError>(diplomat::Ok<std::string>(std::move(output))) : diplomat::result<std::string, DecodeError>(diplomat::Err<DecodeError>(DecodeError::FromFFI(result.err)));
+  return result.is_ok ? diplomat::result<std::string, DecodeFailure>(diplomat::Ok<std::string>(std::move(output))) : diplomat::result<std::string, DecodeFailure>(diplomat::Err<DecodeFailure>(DecodeFailure::FromFFI(result.err)));
 }
 
-inline diplomat::result<size_t, DecodeError> Game::recompress(diplomat::span<const uint8_t> data, uint8_t level, diplomat::span<uint8_t> out) {
+inline diplomat::result<size_t, DecodeFailure> Game::recompress(diplomat::span<const uint8_t> data, uint8_t level, diplomat::span<uint8_t> out) {
   auto result = diplomat::capi::Game_recompress({data.data(), data.size()},
     level,
     {out.data(), out.size()});
-  return result.is_ok ? diplomat::result<size_t, DecodeError>(diplomat::Ok<size_t>(result.ok)) : diplomat::result<size_t, DecodeError>(diplomat::Err<DecodeError>(DecodeError::FromFFI(result.err)));
+  return result.is_ok ? diplomat::result<size_t, DecodeFailure>(diplomat::Ok<size_t>(result.ok)) : diplomat::result<size_t, DecodeFailure>(diplomat::Err<DecodeFailure>(DecodeFailure::FromFFI(result.err)));
 }
 
 inline std::unique_ptr<MoveDetailsIterator> Game::move_details_iterator() const {
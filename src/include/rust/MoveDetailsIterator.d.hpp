@@ -10,7 +10,7 @@
 #include "diplomat_runtime.hpp"
 
 struct MoveDetails;
-class DecodeError;
+struct DecodeFailure;
 
 
 namespace diplomat {
@@ -22,9 +22,9 @@ namespace capi {
 class MoveDetailsIterator {
 public:
 
-  inline diplomat::result<MoveDetails, DecodeError> next();
+  inline diplomat::result<MoveDetails, DecodeFailure> next();
 
-  inline diplomat::result<MoveDetails, DecodeError> nth(int16_t n);
+  inline diplomat::result<MoveDetails, DecodeFailure> nth(int16_t n);
 
   inline const diplomat::capi::MoveDetailsIterator* AsFFI() const;
   inline diplomat::capi::MoveDetailsIterator* AsFFI();
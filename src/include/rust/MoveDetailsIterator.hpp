@@ -9,7 +9,7 @@
 #include <stdbool.h>
 #include <memory>
 #include <optional>
-#include "DecodeError.hpp"
+#include "DecodeFailure.hpp"
 #include "MoveDetails.hpp"
 #include "diplomat_runtime.hpp"
 
@@ -18,10 +18,10 @@ namespace diplomat {
 namespace capi {
     extern "C" {
     
-    typedef struct MoveDetailsIterator_next_result {union {diplomat::capi::MoveDetails ok; diplomat::capi::DecodeError err;}; bool is_ok;} MoveDetailsIterator_next_result;
+    typedef struct MoveDetailsIterator_next_result {union {diplomat::capi::MoveDetails ok; diplomat::capi::DecodeFailure err;}; bool is_ok;} MoveDetailsIterator_next_result;
     MoveDetailsIterator_next_result MoveDetailsIterator_next(diplomat::capi::MoveDetailsIterator* self);
     
-    typedef struct MoveDetailsIterator_nth_result {union {diplomat::capi::MoveDetails ok; diplomat::capi::DecodeError err;}; bool is_ok;} MoveDetailsIterator_nth_result;
+    typedef struct MoveDetailsIterator_nth_result {union {diplomat::capi::MoveDetails ok; diplomat::capi::DecodeFailure err;}; bool is_ok;} MoveDetailsIterator_nth_result;
     MoveDetailsIterator_nth_result MoveDetailsIterator_nth(diplomat::capi::MoveDetailsIterator* self, int16_t n);
     
     
@@ -31,15 +31,15 @@ namespace capi {
 } // namespace capi
 } // namespace
 
-inline diplomat::result<MoveDetails, DecodeError> MoveDetailsIterator::next() {
+inline diplomat::result<MoveDetails, DecodeFailure> MoveDetailsIterator::next() {
   auto result = diplomat::capi::MoveDetailsIterator_next(this->AsFFI());
-  return result.is_ok ? diplomat::result<MoveDetails, DecodeError>(diplomat::Ok<MoveDetails>(MoveDetails::FromFFI(result.ok))) : diplomat::result<MoveDetails, DecodeError>(diplomat::Err<DecodeError>(DecodeError::FromFFI(result.err)));
+  return result.is_ok ? diplomat::result<MoveDetails, DecodeFailure>(diplomat::Ok<MoveDetails>(MoveDetails::FromFFI(result.ok))) : diplomat::result<MoveDetails, DecodeFailure>(diplomat::Err<DecodeFailure>(DecodeFailure::FromFFI(result.err)));
 }
 
-inline diplomat::result<MoveDetails, DecodeError> MoveDetailsIterator::nth(int16_t n) {
+inline diplomat::result<MoveDetails, DecodeFailure> MoveDetailsIterator::nth(int16_t n) {
   auto result = diplomat::capi::MoveDetailsIterator_nth(this->AsFFI(),
     n);
-  return result.is_ok ? diplomat::result<MoveDetails, DecodeError>(diplomat::Ok<MoveDetails>(MoveDetails::FromFFI(result.ok))) : diplomat::result<MoveDetails, DecodeError>(diplomat::Err<DecodeError>(DecodeError::FromFFI(result.err)));
+  return result.is_ok ? diplomat::result<MoveDetails, DecodeFailure>(diplomat::Ok<MoveDetails>(MoveDetails::FromFFI(result.ok))) : diplomat::result<MoveDetails, DecodeFailure>(diplomat::Err<DecodeFailure>(DecodeFailure::FromFFI(result.err)));
 }
 
 inline const diplomat::capi::MoveDetailsIterator* MoveDetailsIterator::AsFFI() const {
@@ -0,0 +1,49 @@
+#ifndef OpeningTree_D_HPP
+#define OpeningTree_D_HPP
+
+#include <stdio.h>
+#include <stdint.h>
+#include <stddef.h>
+#include <stdbool.h>
+#include <memory>
+#include <optional>
+#include "diplomat_runtime.hpp"
+
+namespace diplomat::capi { struct OpeningTreeEntries; }
+class OpeningTreeEntries;
+struct DecodeFailure;
+
+
+namespace diplomat {
+namespace capi {
+    struct OpeningTree;
+} // namespace capi
+} // namespace
+
+class OpeningTree {
+public:
+
+  inline static std::unique_ptr<OpeningTree> create();
+
+  inline diplomat::result<std::monostate, DecodeFailure> add_game(diplomat::span<const uint8_t> game, std::string_view result, uint16_t max_ply);
+
+  inline void merge(const OpeningTree& other);
+
+  inline std::unique_ptr<OpeningTreeEntries> entries() const;
+
+  inline const diplomat::capi::OpeningTree* AsFFI() const;
+  inline diplomat::capi::OpeningTree* AsFFI();
+  inline static const OpeningTree* FromFFI(const diplomat::capi::OpeningTree* ptr);
+  inline static OpeningTree* FromFFI(diplomat::capi::OpeningTree* ptr);
+  inline static void operator delete(void* ptr);
+private:
+  OpeningTree() = delete;
+  OpeningTree(const OpeningTree&) = delete;
+  OpeningTree(OpeningTree&&) noexcept = delete;
+  OpeningTree operator=(const OpeningTree&) = delete;
+  OpeningTree operator=(OpeningTree&&) noexcept = delete;
+  static void operator delete[](void*, size_t) = delete;
+};
+
+
+#endif // OpeningTree_D_HPP
@@ -0,0 +1,81 @@
+#ifndef OpeningTree_HPP
+#define OpeningTree_HPP
+
+#include "OpeningTree.d.hpp"
+
+#include <stdio.h>
+#include <stdint.h>
+#include <stddef.h>
+#include <stdbool.h>
+#include <memory>
+#include <optional>
+#include "DecodeFailure.hpp"
+#include "OpeningTreeEntries.hpp"
+#include "diplomat_runtime.hpp"
+
+
+namespace diplomat {
+namespace capi {
+    extern "C" {
+    
+    diplomat::capi::OpeningTree* OpeningTree_create(void);
+    
+    typedef struct OpeningTree_add_game_result {union { diplomat::capi::DecodeFailure err;}; bool is_ok;} OpeningTree_add_game_result;
+    OpeningTree_add_game_result OpeningTree_add_game(diplomat::capi::OpeningTree* self, diplomat::capi::DiplomatU8View game, diplomat::capi::DiplomatStringView result, uint16_t max_ply);
+    
+    void OpeningTree_merge(diplomat::capi::OpeningTree* self, const diplomat::capi::OpeningTree* other);
+    
+    diplomat::capi::OpeningTreeEntries* OpeningTree_entries(const diplomat::capi::OpeningTree* self);
+    
+    
+    void OpeningTree_destroy(OpeningTree* self);
+    
+    } // extern "C"
+} // namespace capi
+} // namespace
+
+inline std::unique_ptr<OpeningTree> OpeningTree::create() {
+  auto result = diplomat::capi::OpeningTree_create();
+  return std::unique_ptr<OpeningTree>(OpeningTree::FromFFI(result));
+}
+
+inline diplomat::result<std::monostate, DecodeFailure> OpeningTree::add_game(diplomat::span<const uint8_t> game, std::string_view result, uint16_t max_ply) {
+  auto result_ = diplomat::capi::OpeningTree_add_game(this->AsFFI(),
+    {game.data(), game.size()},
+    {result.data(), result.size()},
+    max_ply);
+  return result_.is_ok ? diplomat::result<std::monostate, DecodeFailure>(diplomat::Ok<std::monostate>()) : diplomat::result<std::monostate, DecodeFailure>(diplomat::Err<DecodeFailure>(DecodeFailure::FromFFI(result_.err)));
+}
+
+inline void OpeningTree::merge(const OpeningTree& other) {
+  diplomat::capi::OpeningTree_merge(this->AsFFI(),
+    other.AsFFI());
+}
+
+inline std::unique_ptr<OpeningTreeEntries> OpeningTree::entries() const {
+  auto result = diplomat::capi::OpeningTree_entries(this->AsFFI());
+  return std::unique_ptr<OpeningTreeEntries>(OpeningTreeEntries::FromFFI(result));
+}
+
+inline const diplomat::capi::OpeningTree* OpeningTree::AsFFI() const {
+  return reinterpret_cast<const diplomat::capi::OpeningTree*>(this);
+}
+
+inline diplomat::capi::OpeningTree* OpeningTree::AsFFI() {
+  return reinterpret_cast<diplomat::capi::OpeningTree*>(this);
+}
+
+inline const OpeningTree* OpeningTree::FromFFI(const diplomat::capi::OpeningTree* ptr) {
+  return reinterpret_cast<const OpeningTree*>(ptr);
+}
+
+inline OpeningTree* OpeningTree::FromFFI(diplomat::capi::OpeningTree* ptr) {
+  return reinterpret_cast<OpeningTree*>(ptr);
+}
+
+inline void OpeningTree::operator delete(void* ptr) {
+  diplomat::capi::OpeningTree_destroy(reinterpret_cast<diplomat::capi::OpeningTree*>(ptr));
+}
+
+
+#endif // OpeningTree_HPP
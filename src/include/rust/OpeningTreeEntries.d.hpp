@@ -0,0 +1,49 @@
+#ifndef OpeningTreeEntries_D_HPP
+#define OpeningTreeEntries_D_HPP
+
+#include <stdio.h>
+#include <stdint.h>
+#include <stddef.h>
+#include <stdbool.h>
+#include <memory>
+#include <optional>
+#include "diplomat_runtime.hpp"
+
+struct OpeningTreeEntry;
+
+
+namespace diplomat {
+namespace capi {
+    struct OpeningTreeEntries;
+} // namespace capi
+} // namespace
+
+class OpeningTreeEntries {
+public:
+
+  inline size_t count() const;
+
+  inline OpeningTreeEntry get(size_t index) const;
+
+  inline std::string fen(size_t index) const;
+
+  inline std::string uci(size_t index) const;
+
+  inline std::string san(size_t index) const;
+
+  inline const diplomat::capi::OpeningTreeEntries* AsFFI() const;
+  inline diplomat::capi::OpeningTreeEntries* AsFFI();
+  inline static const OpeningTreeEntries* FromFFI(const diplomat::capi::OpeningTreeEntries* ptr);
+  inline static OpeningTreeEntries* FromFFI(diplomat::capi::OpeningTreeEntries* ptr);
+  inline static void operator delete(void* ptr);
+private:
+  OpeningTreeEntries() = delete;
+  OpeningTreeEntries(const OpeningTreeEntries&) = delete;
+  OpeningTreeEntries(OpeningTreeEntries&&) noexcept = delete;
+  OpeningTreeEntries operator=(const OpeningTreeEntries&) = delete;
+  OpeningTreeEntries operator=(OpeningTreeEntries&&) noexcept = delete;
+  static void operator delete[](void*, size_t) = delete;
+};
+
+
+#endif // OpeningTreeEntries_D_HPP
@@ -0,0 +1,96 @@
+#ifndef OpeningTreeEntries_HPP
+#define OpeningTreeEntries_HPP
+
+#include "OpeningTreeEntries.d.hpp"
+
+#include <stdio.h>
+#include <stdint.h>
+#include <stddef.h>
+#include <stdbool.h>
+#include <memory>
+#include <optional>
+#include "OpeningTreeEntry.hpp"
+#include "diplomat_runtime.hpp"
+
+
+namespace diplomat {
+namespace capi {
+    extern "C" {
+    
+    size_t OpeningTreeEntries_count(const diplomat::capi::OpeningTreeEntries* self);
+    
+    diplomat::capi::OpeningTreeEntry OpeningTreeEntries_get(const diplomat::capi::OpeningTreeEntries* self, size_t index);
+    
+    void OpeningTreeEntries_fen(const diplomat::capi::OpeningTreeEntries* self, size_t index, diplomat::capi::DiplomatWrite* write);
+    
+    void OpeningTreeEntries_uci(const diplomat::capi::OpeningTreeEntries* self, size_t index, diplomat::capi::DiplomatWrite* write);
+    
+    void OpeningTreeEntries_san(const diplomat::capi::OpeningTreeEntries* self, size_t index, diplomat::capi::DiplomatWrite* write);
+    
+    
+    void OpeningTreeEntries_destroy(OpeningTreeEntries* self);
+    
+    } // extern "C"
+} // namespace capi
+} // namespace
+
+inline size_t OpeningTreeEntries::count() const {
+  auto result = diplomat::capi::OpeningTreeEntries_count(this->AsFFI());
+  return result;
+}
+
+inline OpeningTreeEntry OpeningTreeEntries::get(size_t index) const {
+  auto result = diplomat::capi::OpeningTreeEntries_get(this->AsFFI(),
+    index);
+  return OpeningTreeEntry::FromFFI(result);
+}
+
+inline std::string OpeningTreeEntries::fen(size_t index) const {
+  std::string output;
+  diplomat::capi::DiplomatWrite write = diplomat::WriteFromString(output);
+  diplomat::capi::OpeningTreeEntries_fen(this->AsFFI(),
+    index,
+    &write);
+  return output;
+}
+
+inline std::string OpeningTreeEntries::uci(size_t index) const {
+  std::string output;
+  diplomat::capi::DiplomatWrite write = diplomat::WriteFromString(output);
+  diplomat::capi::OpeningTreeEntries_uci(this->AsFFI(),
+    index,
+    &write);
+  return output;
+}
+
+inline std::string OpeningTreeEntries::san(size_t index) const {
+  std::string output;
+  diplomat::capi::DiplomatWrite write = diplomat::WriteFromString(output);
+  diplomat::capi::OpeningTreeEntries_san(this->AsFFI(),
+    index,
+    &write);
+  return output;
+}
+
+inline const diplomat::capi::OpeningTreeEntries* OpeningTreeEntries::AsFFI() const {
+  return reinterpret_cast<const diplomat::capi::OpeningTreeEntries*>(this);
+}
+
+inline diplomat::capi::OpeningTreeEntries* OpeningTreeEntries::AsFFI() {
+  return reinterpret_cast<diplomat::capi::OpeningTreeEntries*>(this);
+}
+
+inline const OpeningTreeEntries* OpeningTreeEntries::FromFFI(const diplomat::capi::OpeningTreeEntries* ptr) {
+  return reinterpret_cast<const OpeningTreeEntries*>(ptr);
+}
+
+inline OpeningTreeEntries* OpeningTreeEntries::FromFFI(diplomat::capi::OpeningTreeEntries* ptr) {
+  return reinterpret_cast<OpeningTreeEntries*>(ptr);
+}
+
+inline void OpeningTreeEntries::operator delete(void* ptr) {
+  diplomat::capi::OpeningTreeEntries_destroy(reinterpret_cast<diplomat::capi::OpeningTreeEntries*>(ptr));
+}
+
+
+#endif // OpeningTreeEntries_HPP
@@ -0,0 +1,42 @@
+#ifndef OpeningTreeEntry_D_HPP
+#define OpeningTreeEntry_D_HPP
+
+#include <stdio.h>
+#include <stdint.h>
+#include <stddef.h>
+#include <stdbool.h>
+#include <memory>
+#include <optional>
+#include "diplomat_runtime.hpp"
+
+
+namespace diplomat {
+namespace capi {
+    struct OpeningTreeEntry {
+      uint16_t ply;
+      uint64_t position;
+      uint64_t games;
+      uint64_t white_wins;
+      uint64_t draws;
+      uint64_t black_wins;
+    };
+    
+    typedef struct OpeningTreeEntry_option {union { OpeningTreeEntry ok; }; bool is_ok; } OpeningTreeEntry_option;
+} // namespace capi
+} // namespace
+
+
+struct OpeningTreeEntry {
+  uint16_t ply;
+  uint64_t position;
+  uint64_t games;
+  uint64_t white_wins;
+  uint64_t draws;
+  uint64_t black_wins;
+
+  inline diplomat::capi::OpeningTreeEntry AsFFI() const;
+  inline static OpeningTreeEntry FromFFI(diplomat::capi::OpeningTreeEntry c_struct);
+};
+
+
+#endif // OpeningTreeEntry_D_HPP
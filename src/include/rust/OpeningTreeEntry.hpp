@@ -0,0 +1,48 @@
+#ifndef OpeningTreeEntry_HPP
+#define OpeningTreeEntry_HPP
+
+#include "OpeningTreeEntry.d.hpp"
+
+#include <stdio.h>
+#include <stdint.h>
+#include <stddef.h>
+#include <stdbool.h>
+#include <memory>
+#include <optional>
+#include "diplomat_runtime.hpp"
+
+
+namespace diplomat {
+namespace capi {
+    extern "C" {
+    
+    
+    } // extern "C"
+} // namespace capi
+} // namespace
+
+
+inline diplomat::capi::OpeningTreeEntry OpeningTreeEntry::AsFFI() const {
+  return diplomat::capi::OpeningTreeEntry {
+    /* .ply = */ ply,
+    /* .position = */ position,
+    /* .games = */ games,
+    /* .white_wins = */ white_wins,
+    /* .draws = */ draws,
+    /* .black_wins = */ black_wins,
+  };
+}
+
+inline OpeningTreeEntry OpeningTreeEntry::FromFFI(diplomat::capi::OpeningTreeEntry c_struct) {
+  return OpeningTreeEntry {
+    /* .ply = */ c_struct.ply,
+    /* .position = */ c_struct.position,
+    /* .games = */ c_struct.games,
+    /* .white_wins = */ c_struct.white_wins,
+    /* .draws = */ c_struct.draws,
+    /* .black_wins = */ c_struct.black_wins,
+  };
+}
+
+
+#endif // OpeningTreeEntry_HPP
@@ -9,7 +9,7 @@
 #include <optional>
 #include "diplomat_runtime.hpp"
 
-class DecodeError;
+struct DecodeFailure;
 class ScoutfishQueryParseError;
 
 
@@ -26,9 +26,9 @@ public:
 
   inline static diplomat::result<std::unique_ptr<ScoutfishQuery>, std::monostate> decode_bytes(diplomat::span<const uint8_t> data);
 
-  inline diplomat::result<bool, DecodeError> matches(diplomat::span<const uint8_t> game) const;
+  inline diplomat::result<bool, DecodeFailure> matches(diplomat::span<const uint8_t> game) const;
 
-  inline diplomat::result<uint32_t, DecodeError> matches_plies(diplomat::span<const uint8_t> game, diplomat::span<uint32_t> out) const;
+  inline diplomat::result<uint32_t, DecodeFailure> matches_plies(diplomat::span<const uint8_t> game, diplomat::span<uint32_t> out) const;
 
   inline const diplomat::capi::ScoutfishQuery* AsFFI() const;
   inline diplomat::capi::ScoutfishQuery* AsFFI();
@@ -9,7 +9,7 @@
 #include <stdbool.h>
 #include <memory>
 #include <optional>
-#include "DecodeError.hpp"
+#include "DecodeFailure.hpp"
 #include "ScoutfishQueryParseError.hpp"
 #include "diplomat_runtime.hpp"
 
@@ -24,10 +24,10 @@ namespace capi {
     typedef struct ScoutfishQuery_decode_bytes_result {union {diplomat::capi::ScoutfishQuery* ok; }; bool is_ok;} ScoutfishQuery_decode_bytes_result;
     ScoutfishQuery_decode_bytes_result ScoutfishQuery_decode_bytes(diplomat::capi::DiplomatU8View data);
     
-    typedef struct ScoutfishQuery_matches_result {union {bool ok; diplomat::capi::DecodeError err;}; bool is_ok;} ScoutfishQuery_matches_result;
+    typedef struct ScoutfishQuery_matches_result {union {bool ok; diplomat::capi::DecodeFailure err;}; bool is_ok;} ScoutfishQuery_matches_result;
     ScoutfishQuery_matches_result ScoutfishQuery_matches(const diplomat::capi::ScoutfishQuery* self, diplomat::capi::DiplomatU8View game);
     
-    typedef struct ScoutfishQuery_matches_plies_result {union {uint32_t ok; diplomat::capi::DecodeError err;}; bool is_ok;} ScoutfishQuery_matches_plies_result;
+    typedef struct ScoutfishQuery_matches_plies_result {union {uint32_t ok; diplomat::capi::DecodeFailure err;}; bool is_ok;} ScoutfishQuery_matches_plies_result;
     ScoutfishQuery_matches_plies_result ScoutfishQuery_matches_plies(const diplomat::capi::ScoutfishQuery* self, diplomat::capi::DiplomatU8View game, diplomat::capi::DiplomatU32ViewMut out);
     
     
@@ -48,17 +48,17 @@ inline diplomat::result<std::unique_ptr<ScoutfishQuery>, std::monostate> Scoutfi
   return result.is_ok ? diplomat::result<std::unique_ptr<ScoutfishQuery>, std::monostate>(diplomat::Ok<std::unique_ptr<ScoutfishQuery>>(std::unique_ptr<ScoutfishQuery>(ScoutfishQuery::FromFFI(result.ok)))) : diplomat::result<std::unique_ptr<ScoutfishQuery>, std::monostate>(diplomat::Err<std::monostate>());
 }
 
-inline diplomat::result<bool, DecodeError> ScoutfishQuery::matches(diplomat::span<const uint8_t> game) const {
+inline diplomat::result<bool, DecodeFailure> ScoutfishQuery::matches(diplomat::span<const uint8_t> game) const {
   auto result = diplomat::capi::ScoutfishQuery_matches(this->AsFFI(),
     {game.data(), game.size()});
-  return result.is_ok ? diplomat::result<bool, DecodeError>(diplomat::Ok<bool>(result.ok)) : diplomat::result<bool, DecodeError>(diplomat::Err<DecodeError>(DecodeError::FromFFI(result.err)));
+  return result.is_ok ? diplomat::result<bool, DecodeFailure>(diplomat::Ok<bool>(result.ok)) : diplomat::result<bool, DecodeFailure>(diplomat::Err<DecodeFailure>(DecodeFailure::FromFFI(result.err)));
 }
 
-inline diplomat::result<uint32_t, DecodeError> ScoutfishQuery::matches_plies(diplomat::span<const uint8_t> game, diplomat::span<uint32_t> out) const {
+inline diplomat::result<uint32_t, DecodeFailure> ScoutfishQuery::matches_plies(diplomat::span<const uint8_t> game, diplomat::span<uint32_t> out) const {
   auto result = diplomat::capi::ScoutfishQuery_matches_plies(this->AsFFI(),
     {game.data(), game.size()},
     {out.data(), out.size()});
-  return result.is_ok ? diplomat::result<uint32_t, DecodeError>(diplomat::Ok<uint32_t>(result.ok)) : diplomat::result<uint32_t, DecodeError>(diplomat::Err<DecodeError>(DecodeError::FromFFI(result.err)));
+  return result.is_ok ? diplomat::result<uint32_t, DecodeFailure>(diplomat::Ok<uint32_t>(result.ok)) : diplomat::result<uint32_t, DecodeFailure>(diplomat::Err<DecodeFailure>(DecodeFailure::FromFFI(result.err)));
 }
 
 inline const diplomat::capi::ScoutfishQuery* ScoutfishQuery::AsFFI() const {
@@ -22,6 +22,9 @@ namespace capi {
       ScoutfishQueryParseError_BincodeError = 7,
       ScoutfishQueryParseError_BufferTooSmall = 8,
       ScoutfishQueryParseError_CursorWriteError = 9,
+      ScoutfishQueryParseError_InvalidPawnCountFormat = 10,
+      ScoutfishQueryParseError_InvalidOpenFilesFormat = 11,
+      ScoutfishQueryParseError_InvalidCastledFormat = 12,
     };
     
     typedef struct ScoutfishQueryParseError_option {union { ScoutfishQueryParseError ok; }; bool is_ok; } ScoutfishQueryParseError_option;
@@ -40,6 +43,9 @@ public:
     BincodeError = 7,
     BufferTooSmall = 8,
     CursorWriteError = 9,
+    InvalidPawnCountFormat = 10,
+    InvalidOpenFilesFormat = 11,
+    InvalidCastledFormat = 12,
   };
 
   ScoutfishQueryParseError() = default;
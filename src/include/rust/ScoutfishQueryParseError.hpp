@@ -36,6 +36,9 @@ inline ScoutfishQueryParseError ScoutfishQueryParseError::FromFFI(diplomat::capi
     case diplomat::capi::ScoutfishQueryParseError_BincodeError:
     case diplomat::capi::ScoutfishQueryParseError_BufferTooSmall:
     case diplomat::capi::ScoutfishQueryParseError_CursorWriteError:
+    case diplomat::capi::ScoutfishQueryParseError_InvalidPawnCountFormat:
+    case diplomat::capi::ScoutfishQueryParseError_InvalidOpenFilesFormat:
+    case diplomat::capi::ScoutfishQueryParseError_InvalidCastledFormat:
       return static_cast<ScoutfishQueryParseError::Value>(c_enum);
     default:
       abort();
@@ -9,7 +9,7 @@
 #include <optional>
 #include "diplomat_runtime.hpp"
 
-class DecodeError;
+struct DecodeFailure;
 
 
 namespace diplomat {
@@ -23,6 +23,18 @@ namespace capi {
       uint64_t bishop;
       uint64_t knight;
       uint64_t pawn;
+      uint64_t minor;
+      uint64_t occupied;
+      uint64_t empty;
+      uint64_t not_white;
+      uint64_t not_black;
+      uint64_t not_king;
+      uint64_t not_queen;
+      uint64_t not_rook;
+      uint64_t not_bishop;
+      uint64_t not_knight;
+      uint64_t not_pawn;
+      uint64_t not_minor;
     };
     
     typedef struct Subfen_option {union { Subfen ok; }; bool is_ok; } Subfen_option;
@@ -39,10 +51,22 @@ struct Subfen {
   uint64_t bishop;
   uint64_t knight;
   uint64_t pawn;
+  uint64_t minor;
+  uint64_t occupied;
+  uint64_t empty;
+  uint64_t not_white;
+  uint64_t not_black;
+  uint64_t not_king;
+  uint64_t not_queen;
+  uint64_t not_rook;
+  uint64_t not_bishop;
+  uint64_t not_knight;
+  uint64_t not_pawn;
+  uint64_t not_minor;
 
   inline static diplomat::result<Subfen, std::monostate> parse(std::string_view subfen);
 
-  inline diplomat::result<bool, DecodeError> matches(diplomat::span<const uint8_t> game);
+  inline diplomat::result<bool, DecodeFailure> matches(diplomat::span<const uint8_t> game);
 
   inline diplomat::capi::Subfen AsFFI() const;
   inline static Subfen FromFFI(diplomat::capi::Subfen c_struct);
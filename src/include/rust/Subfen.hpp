@@ -9,7 +9,7 @@
 #include <stdbool.h>
 #include <memory>
 #include <optional>
-#include "DecodeError.hpp"
+#include "DecodeFailure.hpp"
 #include "diplomat_runtime.hpp"
 
 
@@ -20,7 +20,7 @@ namespace capi {
     typedef struct Subfen_parse_result {union {diplomat::capi::Subfen ok; }; bool is_ok;} Subfen_parse_result;
     Subfen_parse_result Subfen_parse(diplomat::capi::DiplomatStringView subfen);
     
-    typedef struct Subfen_matches_result {union {bool ok; diplomat::capi::DecodeError err;}; bool is_ok;} Subfen_matches_result;
+    typedef struct Subfen_matches_result {union {bool ok; diplomat::capi::DecodeFailure err;}; bool is_ok;} Subfen_matches_result;
     Subfen_matches_result Subfen_matches(diplomat::capi::Subfen self, diplomat::capi::DiplomatU8View game);
     
     
@@ -33,10 +33,10 @@ inline diplomat::result<Subfen, std::monostate> Subfen::parse(std::string_view s
   return result.is_ok ? diplomat::result<Subfen, std::monostate>(diplomat::Ok<Subfen>(Subfen::FromFFI(result.ok))) : diplomat::result<Subfen, std::monostate>(diplomat::Err<std::monostate>());
 }
 
-inline diplomat::result<bool, DecodeError> Subfen::matches(diplomat::span<const uint8_t> game) {
+inline diplomat::result<bool, DecodeFailure> Subfen::matches(diplomat::span<const uint8_t> game) {
   auto result = diplomat::capi::Subfen_matches(this->AsFFI(),
     {game.data(), game.size()});
-  return result.is_ok ? diplomat::result<bool, DecodeError>(diplomat::Ok<bool>(result.ok)) : diplomat::result<bool, DecodeError>(diplomat::Err<DecodeError>(DecodeError::FromFFI(result.err)));
+  return result.is_ok ? diplomat::result<bool, DecodeFailure>(diplomat::Ok<bool>(result.ok)) : diplomat::result<bool, DecodeFailure>(diplomat::Err<DecodeFailure>(DecodeFailure::FromFFI(result.err)));
 }
 
 
@@ -50,6 +50,18 @@ inline diplomat::capi::Subfen Subfen::AsFFI() const {
     /* .bishop = */ bishop,
     /* .knight = */ knight,
     /* .pawn = */ pawn,
+    /* .minor = */ minor,
+    /* .occupied = */ occupied,
+    /* .empty = */ empty,
+    /* .not_white = */ not_white,
+    /* .not_black = */ not_black,
+    /* .not_king = */ not_king,
+    /* .not_queen = */ not_queen,
+    /* .not_rook = */ not_rook,
+    /* .not_bishop = */ not_bishop,
+    /* .not_knight = */ not_knight,
+    /* .not_pawn = */ not_pawn,
+    /* .not_minor = */ not_minor,
   };
 }
 
@@ -63,6 +75,18 @@ inline Subfen Subfen::FromFFI(diplomat::capi::Subfen c_struct) {
     /* .bishop = */ c_struct.bishop,
     /* .knight = */ c_struct.knight,
     /* .pawn = */ c_struct.pawn,
+    /* .minor = */ c_struct.minor,
+    /* .occupied = */ c_struct.occupied,
+    /* .empty = */ c_struct.empty,
+    /* .not_white = */ c_struct.not_white,
+    /* .not_black = */ c_struct.not_black,
+    /* .not_king = */ c_struct.not_king,
+    /* .not_queen = */ c_struct.not_queen,
+    /* .not_rook = */ c_struct.not_rook,
+    /* .not_bishop = */ c_struct.not_bishop,
+    /* .not_knight = */ c_struct.not_knight,
+    /* .not_pawn = */ c_struct.not_pawn,
+    /* .not_minor = */ c_struct.not_minor,
   };
 }
 